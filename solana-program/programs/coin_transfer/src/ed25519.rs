@@ -0,0 +1,72 @@
+//! Minimal Ed25519 program helpers for the Coin Transfer program
+//!
+//! Programs cannot check ed25519 signatures themselves. Instead the transaction
+//! carries an Ed25519 program instruction, which fails the whole transaction
+//! on a bad signature, and the program reads back what it verified through the
+//! instructions sysvar.
+
+use anchor_lang::prelude::*;
+use solana_instructions_sysvar::{load_current_index_checked, load_instruction_at_checked};
+
+use crate::errors::TransferError;
+
+/// Ed25519 signature verification program ID
+pub const ED25519_PROGRAM_ID: Pubkey = pubkey!("Ed25519SigVerify111111111111111111111111111");
+
+/// Offset of the first signature's offsets table in the instruction data
+const OFFSETS_START: usize = 2;
+
+/// Size of one signature's offsets table in bytes
+const OFFSETS_LEN: usize = 14;
+
+/// Instruction index meaning "this Ed25519 instruction's own data"
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// Require that the instruction right before the current one is an Ed25519
+/// program instruction verifying `signature` by `signer` over `message`
+///
+/// Only single-signature instructions that carry the signature, key and
+/// message in their own data are accepted.
+pub fn require_verified(
+    instructions: &AccountInfo,
+    signer: &Pubkey,
+    signature: &[u8; 64],
+    message: &[u8],
+) -> Result<()> {
+    let current = load_current_index_checked(instructions)?;
+    require!(current > 0, TransferError::InvalidSignature);
+    let instruction = load_instruction_at_checked(current as usize - 1, instructions)?;
+    require_keys_eq!(
+        instruction.program_id,
+        ED25519_PROGRAM_ID,
+        TransferError::InvalidSignature
+    );
+
+    let data = &instruction.data;
+    require!(
+        data.len() >= OFFSETS_START + OFFSETS_LEN && data[0] == 1,
+        TransferError::InvalidSignature
+    );
+    // Offsets table: signature, public key and message, each as (offset, instruction
+    // index), with the message size between its offset and index
+    let field = |index: usize| {
+        let at = OFFSETS_START + 2 * index;
+        u16::from_le_bytes([data[at], data[at + 1]])
+    };
+    require!(
+        field(1) == CURRENT_INSTRUCTION
+            && field(3) == CURRENT_INSTRUCTION
+            && field(6) == CURRENT_INSTRUCTION,
+        TransferError::InvalidSignature
+    );
+
+    let bytes = |offset: u16, len: usize| data.get(offset as usize..offset as usize + len);
+    require!(
+        bytes(field(0), 64) == Some(signature.as_ref())
+            && bytes(field(2), 32) == Some(signer.as_ref())
+            && field(5) as usize == message.len()
+            && bytes(field(4), message.len()) == Some(message),
+        TransferError::InvalidSignature
+    );
+    Ok(())
+}
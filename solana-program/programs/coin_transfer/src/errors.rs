@@ -20,4 +20,224 @@ pub enum TransferError {
     /// Unauthorized operation attempted
     #[msg("Unauthorized: Signer does not have permission")]
     Unauthorized,
+
+    /// Recipient has been frozen by the program authority
+    #[msg("Recipient is frozen and cannot receive transfers")]
+    RecipientFrozen,
+
+    /// Ordered transfer presented a nonce other than the recipient's current one
+    #[msg("Invalid nonce: does not match the recipient's current nonce")]
+    InvalidNonce,
+
+    /// Fee configuration values are out of range
+    #[msg("Invalid fee configuration: basis points must not exceed 10000")]
+    InvalidFeeConfig,
+
+    /// Token account is not a valid SPL token account for this transfer
+    #[msg("Invalid token account")]
+    InvalidTokenAccount,
+
+    /// Transfer accumulator has no room for another leaf
+    #[msg("Transfer accumulator is full")]
+    AccumulatorFull,
+
+    /// Amount denomination is not one of the supported values
+    #[msg("Invalid denomination: use 0 for lamports or 1 for SOL")]
+    InvalidDenomination,
+
+    /// Transfer amount is not one of the allowed fixed amounts
+    #[msg("Amount is not one of the allowed fixed amounts")]
+    AmountNotAllowed,
+
+    /// Allowed amounts list exceeds its maximum length
+    #[msg("Too many allowed amounts")]
+    TooManyAllowedAmounts,
+
+    /// Timeout must be a positive number of seconds
+    #[msg("Invalid timeout")]
+    InvalidTimeout,
+
+    /// Escrow deadline has not passed yet
+    #[msg("Escrow has not expired yet")]
+    EscrowNotExpired,
+
+    /// Recipient's registered category differs from the expected one
+    #[msg("Recipient category does not match the expected category")]
+    CategoryMismatch,
+
+    /// Refund would exceed the amount originally transferred
+    #[msg("Transfer already refunded: amount exceeds the refundable balance")]
+    AlreadyRefunded,
+
+    /// Transfers are paused by the program authority
+    #[msg("Program is paused")]
+    ProgramPaused,
+
+    /// Timelock unlock time is further away than the configured maximum
+    #[msg("Lock duration exceeds the maximum allowed")]
+    LockTooLong,
+
+    /// Recipient has not been KYC-verified by the authority
+    #[msg("Recipient is not KYC-verified")]
+    RecipientNotVerified,
+
+    /// Program configuration has not been finalized yet
+    #[msg("Program is not initialized: configuration has not been finalized")]
+    NotInitialized,
+
+    /// A required program setting is missing or inconsistent
+    #[msg("Invalid program configuration")]
+    InvalidConfig,
+
+    /// Redemption asks for more points than the account holds
+    #[msg("Insufficient loyalty points")]
+    InsufficientPoints,
+
+    /// Transfer attempted outside the configured business hours
+    #[msg("Transfers are only accepted during business hours")]
+    OutsideBusinessHours,
+
+    /// Region code is outside the supported range
+    #[msg("Invalid region code")]
+    InvalidRegion,
+
+    /// Sender already has the maximum number of open transfer records
+    #[msg("Too many open transfer records: close an old one first")]
+    TooManyRecords,
+
+    /// Nothing has accrued since the last withdrawal
+    #[msg("Nothing to withdraw")]
+    NothingToWithdraw,
+
+    /// Transaction is signed by someone other than the sender
+    #[msg("Relayers are not allowed: the sender must pay their own fees")]
+    RelayerNotAllowed,
+
+    /// Transfer is smaller than the recipient's registered minimum
+    #[msg("Transfer amount is below the recipient's minimum")]
+    BelowRecipientMinimum,
+
+    /// Recipient does not hold a token of the required mint
+    #[msg("Recipient does not hold a token of the required mint")]
+    MissingRequiredToken,
+
+    /// Fee must be swept to the cold wallet but no cold token account was provided
+    #[msg("Cold wallet token account required to sweep fees past the hot treasury cap")]
+    ColdTokenRequired,
+
+    /// Fee must be rebated but no rebate vault token account was provided
+    #[msg("Rebate vault token account required to pay the fee rebate")]
+    RebateTokenRequired,
+
+    /// A transfer with this event id was already made and has not expired
+    #[msg("Duplicate event id: a transfer for it was already made")]
+    DuplicateEventId,
+
+    /// Idempotency marker has not expired yet
+    #[msg("Idempotency marker has not expired yet")]
+    MarkerNotExpired,
+
+    /// Transaction made more transfers through the program than allowed
+    #[msg("Too many transfers in one transaction")]
+    TooManyTransfersInTransaction,
+
+    /// Arithmetic result does not fit in a u64
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+
+    /// Claim attempted too soon after an incorrect secret
+    #[msg("Too many claim attempts: wait for the cooldown after an incorrect secret")]
+    ClaimRateLimited,
+
+    /// Day given for a daily account is not the current UTC day
+    #[msg("Day does not match the current UTC day")]
+    InvalidDay,
+
+    /// Sender has made fewer tracked transfers than required
+    #[msg("Sender does not have enough transfer history")]
+    InsufficientHistory,
+
+    /// Invoice has more line items than allowed
+    #[msg("Too many line items")]
+    TooManyLineItems,
+
+    /// Invoice was already paid
+    #[msg("Invoice has already been paid")]
+    InvoiceAlreadyPaid,
+
+    /// Payment is less than the invoice total
+    #[msg("Payment is less than the invoice total")]
+    InvoiceUnderpaid,
+
+    /// Payment is more than the invoice total
+    #[msg("Payment is more than the invoice total")]
+    InvoiceOverpaid,
+
+    /// Too few emergency council members signed
+    #[msg("Emergency threshold not met")]
+    EmergencyThresholdNotMet,
+
+    /// Provided notification program is not the one the recipient registered
+    #[msg("Invalid notification program")]
+    InvalidNotificationProgram,
+
+    /// Recipient is an executable (program) account
+    #[msg("Recipient is a program account")]
+    RecipientIsProgram,
+
+    /// Grace period has ended, so the transfer can no longer be cancelled
+    #[msg("Grace period is over")]
+    GracePeriodOver,
+
+    /// Recipient already received its allowed transfers for this window
+    #[msg("Recipient rate limit exceeded")]
+    RecipientRateLimited,
+
+    /// Transfer would take a group past its shared spending cap
+    #[msg("Group budget exceeded")]
+    GroupBudgetExceeded,
+
+    /// No matching Ed25519 signature verification precedes the instruction
+    #[msg("Missing or mismatched signature verification")]
+    InvalidSignature,
+
+    /// Challenge period has ended, so the transfer can no longer be reversed
+    #[msg("Challenge period is over")]
+    ChallengePeriodOver,
+
+    /// Transfer carries no memo but one is required
+    #[msg("A memo is required")]
+    MemoRequired,
+
+    /// Withholding rate above `MAX_WITHHOLD_BPS`
+    #[msg("Withholding exceeds the maximum rate")]
+    WithholdingTooHigh,
+
+    /// PDA bump is not the canonical one returned by `find_program_address`
+    #[msg("Non-canonical PDA bump")]
+    NonCanonicalBump,
+
+    /// Transfer would take the sender past their daily cap
+    #[msg("Sender daily cap exceeded")]
+    DailyCapExceeded,
+
+    /// Config was changed too recently to be changed again
+    #[msg("Config cooldown is still active")]
+    ConfigCooldownActive,
+
+    /// Journal entry index is at or past the write cursor
+    #[msg("Journal entry index out of range")]
+    JournalIndexOutOfRange,
+
+    /// Journal already holds `Journal::MAX_ENTRIES` entries
+    #[msg("Transfer journal is full")]
+    JournalFull,
+
+    /// Transfer moves more of the sender's balance than allowed without confirmation
+    #[msg("Transfer exceeds the allowed fraction of the sender's balance")]
+    ExceedsBalanceFraction,
+
+    /// The sender's guardian did not co-sign where its approval is needed
+    #[msg("Guardian co-signature required")]
+    GuardianRequired,
 }
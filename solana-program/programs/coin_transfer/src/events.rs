@@ -0,0 +1,108 @@
+//! Log formats emitted by the Coin Transfer program
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::log::sol_log_data;
+
+/// Emitted after every successful `transfer_sol`
+#[event]
+pub struct TransferExecuted {
+    /// Sender public key
+    pub sender: Pubkey,
+
+    /// Recipient public key
+    pub recipient: Pubkey,
+
+    /// Amount transferred in lamports
+    pub amount: u64,
+
+    /// Sender's lamports after the transfer
+    pub sender_balance_after: u64,
+
+    /// Unix timestamp of the transfer
+    pub timestamp: i64,
+}
+
+/// Emitted by `transfer_sol_paired` the first time a sender pays a recipient
+#[event]
+pub struct FirstTransferBetween {
+    /// Sender public key
+    pub sender: Pubkey,
+
+    /// Recipient public key
+    pub recipient: Pubkey,
+
+    /// Amount of the first transfer in lamports
+    pub amount: u64,
+
+    /// Unix timestamp of the first transfer
+    pub timestamp: i64,
+}
+
+/// Emitted once by a summarized `transfer_sol_batch` in place of per-recipient events
+#[event]
+pub struct BatchSummary {
+    /// Sender public key
+    pub sender: Pubkey,
+
+    /// Number of recipients paid
+    pub recipient_count: u32,
+
+    /// Total amount transferred in lamports
+    pub total_amount: u64,
+
+    /// `sha256` over the recipient keys, concatenated in batch order
+    pub recipients_hash: [u8; 32],
+
+    /// Unix timestamp of the batch
+    pub timestamp: i64,
+}
+
+/// Compact binary record of a transfer, logged with `sol_log_data`
+///
+/// Indexers read it from the `Program data:` log line (base64). All integers
+/// are little-endian and the layout is fixed at 81 bytes:
+///
+/// | Offset | Size | Field       |
+/// |--------|------|-------------|
+/// | 0      | 1    | `tag` (`COMPACT_TRANSFER_TAG`) |
+/// | 1      | 32   | `sender`    |
+/// | 33     | 32   | `recipient` |
+/// | 65     | 8    | `amount` (u64, lamports) |
+/// | 73     | 8    | `timestamp` (i64, unix seconds) |
+pub struct CompactTransfer {
+    /// Sender public key
+    pub sender: Pubkey,
+
+    /// Recipient public key
+    pub recipient: Pubkey,
+
+    /// Amount transferred in lamports
+    pub amount: u64,
+
+    /// Unix timestamp of the transfer
+    pub timestamp: i64,
+}
+
+/// First byte of every compact transfer record
+pub const COMPACT_TRANSFER_TAG: u8 = 0x01;
+
+impl CompactTransfer {
+    /// Encoded length in bytes
+    pub const LEN: usize = 1 + 32 + 32 + 8 + 8;
+
+    /// Encode into the fixed byte layout
+    pub fn to_bytes(&self) -> [u8; Self::LEN] {
+        let mut bytes = [0u8; Self::LEN];
+        bytes[0] = COMPACT_TRANSFER_TAG;
+        bytes[1..33].copy_from_slice(self.sender.as_ref());
+        bytes[33..65].copy_from_slice(self.recipient.as_ref());
+        bytes[65..73].copy_from_slice(&self.amount.to_le_bytes());
+        bytes[73..81].copy_from_slice(&self.timestamp.to_le_bytes());
+        bytes
+    }
+
+    /// Write the record to the program logs
+    pub fn log(&self) {
+        sol_log_data(&[&self.to_bytes()]);
+    }
+}
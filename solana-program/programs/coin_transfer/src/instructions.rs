@@ -1,10 +1,28 @@
 //! Instruction handlers for the Coin Transfer program
 
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program::invoke;
 use anchor_lang::system_program;
+use solana_instructions_sysvar::load_instruction_at_checked;
+use solana_sha256_hasher::hashv;
 
+use crate::ed25519;
 use crate::errors::TransferError;
-use crate::state::ProgramState;
+use crate::events::{BatchSummary, CompactTransfer, FirstTransferBetween, TransferExecuted};
+use crate::state::{
+    bps_of, utc_day, AllowedAmounts, ApprovedRecipient, ConditionalEscrow, Curve, CurvePosition,
+    DailySnapshot, DailyWindow, EmergencyCouncil, FeeConfig, FrozenRecipient, GraceTransfer,
+    GroupBudget, Histogram, IdempotencyMarker, Invoice, Journal, JournalEntry, LoyaltyConfig,
+    LoyaltyPoints, MovingAverage, NotificationConfig, PairMarker, PasswordEscrow, PauseExempt,
+    PriorityFeeStats, PriorityRecipient, ProgramState, Receipt, RecipientBloom, RecipientNonce,
+    RecipientRate, RecipientStats, RegionStats, ReversibleTransfer, SenderChain, SenderDailySpend,
+    SenderGuardian, SenderStats, SharePool, Shares, Stream, TaxWithholding, Timelock, TokenGate,
+    TransferAccumulator, TransferRecord, BPS_DENOMINATOR, DENOM_LAMPORTS, DENOM_SOL,
+    HISTOGRAM_BUCKETS, LAMPORTS_PER_SOL, MAX_ALLOWED_AMOUNTS, MAX_EMERGENCY_MEMBERS,
+    MAX_GROUP_MEMBERS, MAX_LINE_ITEMS, MAX_WITHHOLD_BPS, REGION_COUNT,
+};
+use crate::token;
 
 /// Initialize the program with optional state tracking
 pub fn handle_initialize(ctx: Context<Initialize>) -> Result<()> {
@@ -17,8 +35,82 @@ pub fn handle_initialize(ctx: Context<Initialize>) -> Result<()> {
     Ok(())
 }
 
-/// Transfer SOL from sender to recipient
-pub fn handle_transfer_sol(ctx: Context<TransferSol>, amount: u64) -> Result<()> {
+/// Validate the program's settings and start accepting transfers
+pub fn handle_finalize_config(ctx: Context<UpdateState>) -> Result<()> {
+    let state = &mut ctx.accounts.state;
+    state.validate_config()?;
+    state.configured = true;
+
+    msg!("Configuration finalized, transfers enabled");
+
+    Ok(())
+}
+
+/// Grow a program state account created with the v1 layout to the current one
+///
+/// The account can't be loaded as `ProgramState` until it has been grown, so
+/// its discriminator and authority are checked on the raw bytes.
+pub fn handle_migrate_state(ctx: Context<MigrateState>) -> Result<()> {
+    let state = ctx.accounts.state.to_account_info();
+    require_keys_eq!(*state.owner, crate::ID, TransferError::InvalidConfig);
+
+    if state.data_len() == ProgramState::SIZE {
+        msg!("Program state already uses the current layout");
+        return Ok(());
+    }
+    require!(
+        state.data_len() == ProgramState::V1_SIZE,
+        TransferError::InvalidConfig
+    );
+
+    {
+        let data = state.try_borrow_data()?;
+        require!(
+            &data[..8] == ProgramState::DISCRIMINATOR,
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+        let authority = Pubkey::try_from(&data[8..40]).map_err(|_| TransferError::InvalidConfig)?;
+        require_keys_eq!(authority, ctx.accounts.authority.key(), TransferError::Unauthorized);
+    }
+
+    // Keep the grown account rent-exempt
+    let top_up = Rent::get()?
+        .minimum_balance(ProgramState::SIZE)
+        .saturating_sub(state.lamports());
+    if top_up > 0 {
+        system_transfer(
+            &ctx.accounts.system_program,
+            &ctx.accounts.authority.to_account_info(),
+            &state,
+            top_up,
+        )?;
+    }
+
+    // New bytes are zeroed, which is the default of every field they hold
+    state.resize(ProgramState::SIZE)?;
+    let mut data = state.try_borrow_mut_data()?;
+    let mut migrated = ProgramState::try_deserialize(&mut &data[..])?;
+    migrated.version = ProgramState::VERSION;
+    migrated.try_serialize(&mut &mut data[..])?;
+
+    msg!("Program state migrated to version {}", ProgramState::VERSION);
+
+    Ok(())
+}
+
+/// Transfer SOL from sender to recipient, returning the sender's new balance
+pub fn handle_transfer_sol(ctx: Context<TransferSol>, amount: u64) -> Result<u64> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+
     // Validate amount is greater than 0
     require!(amount > 0, TransferError::InvalidAmount);
 
@@ -47,10 +139,21 @@ pub fn handle_transfer_sol(ctx: Context<TransferSol>, amount: u64) -> Result<()>
     );
 
     system_program::transfer(cpi_context, amount)?;
+    ctx.accounts.state.record_transfer(&sender.key(), &recipient.key(), amount);
+
+    // Lamports reflect the CPI, so this is the balance the sender ends with
+    let sender_balance_after = sender.lamports();
+    emit!(TransferExecuted {
+        sender: sender.key(),
+        recipient: recipient.key(),
+        amount,
+        sender_balance_after,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
 
     msg!("Transfer successful! ✓");
     
-    Ok(())
+    Ok(sender_balance_after)
 }
 
 /// Get account balance (view function)
@@ -61,50 +164,7246 @@ pub fn handle_get_balance(ctx: Context<GetBalance>) -> Result<u64> {
     Ok(balance)
 }
 
- 
-// Account Contexts
- 
+/// Escrow SOL for a recipient until the arbiter resolves the condition
+pub fn handle_transfer_conditional(
+    ctx: Context<TransferConditional>,
+    amount: u64,
+    timeout_secs: i64,
+) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(timeout_secs > 0, TransferError::InvalidTimeout);
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
 
-/// Accounts required for program initialization
-#[derive(Accounts)]
-pub struct Initialize<'info> {
-    /// Program state account (PDA)
-    #[account(
-        init,
-        payer = authority,
-        space = ProgramState::SIZE,
-        seeds = [b"program_state"],
-        bump
-    )]
-    pub state: Account<'info, ProgramState>,
-    
-    /// Authority initializing the program (pays for state account)
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    
-    /// System program for account creation
-    pub system_program: Program<'info, System>,
+    let escrow = &mut ctx.accounts.escrow;
+    escrow.sender = ctx.accounts.sender.key();
+    escrow.recipient = ctx.accounts.recipient.key();
+    escrow.arbiter = ctx.accounts.arbiter.key();
+    escrow.amount = amount;
+    escrow.deadline = Clock::get()?
+        .unix_timestamp
+        .checked_add(timeout_secs)
+        .ok_or(TransferError::InvalidTimeout)?;
+    escrow.bump = ctx.bumps.escrow;
+
+    // Move the escrowed amount on top of the rent already held by the PDA
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.escrow.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.escrow.key(),
+        amount,
+    );
+
+    msg!("=== Conditional Transfer ===");
+    msg!("Escrowed: {} lamports", amount);
+    msg!("Recipient: {}", ctx.accounts.recipient.key());
+    msg!("Arbiter: {}", ctx.accounts.arbiter.key());
+    msg!("Deadline: {}", ctx.accounts.escrow.deadline);
+
+    Ok(())
 }
 
-/// Accounts required for SOL transfer
-#[derive(Accounts)]
-pub struct TransferSol<'info> {
-    /// The sender account (must sign the transaction)
-    #[account(mut)]
-    pub sender: Signer<'info>,
-    
-    /// The recipient account (receives SOL)
-    /// CHECK: This account is only used to receive SOL, no validation needed
-    #[account(mut)]
-    pub recipient: AccountInfo<'info>,
-    
-    /// Solana System Program (required for native SOL transfers)
-    pub system_program: Program<'info, System>,
+/// Resolve a conditional escrow: release to the recipient or refund the sender
+pub fn handle_resolve_conditional(ctx: Context<ResolveConditional>, success: bool) -> Result<()> {
+    let escrow = &ctx.accounts.escrow;
+    let amount = escrow.amount;
+
+    let destination = if success {
+        ctx.accounts.recipient.to_account_info()
+    } else {
+        ctx.accounts.sender.to_account_info()
+    };
+    move_lamports(&escrow.to_account_info(), &destination, amount)?;
+
+    msg!("=== Conditional Resolved ===");
+    msg!("Outcome: {}", if success { "released" } else { "refunded" });
+    msg!("Amount: {} lamports", amount);
+    msg!("To: {}", destination.key());
+
+    // Remaining rent goes back to the sender, any dust to the treasury
+    close_account(
+        &ctx.accounts.escrow,
+        &ctx.accounts.sender,
+        &ctx.accounts.fee_config,
+        &ctx.accounts.treasury,
+    )
 }
 
-/// Accounts required for balance query
-#[derive(Accounts)]
-pub struct GetBalance<'info> {
-    /// CHECK: Any account can have its balance queried
-    pub account: AccountInfo<'info>,
+/// Refund an escrow the arbiter left unresolved past its deadline
+///
+/// Callable by anyone so funds can't be stuck on arbiter inaction.
+pub fn handle_resolve_conditional_default(ctx: Context<ResolveConditionalDefault>) -> Result<()> {
+    let escrow = &ctx.accounts.escrow;
+    let now = Clock::get()?.unix_timestamp;
+    require!(now >= escrow.deadline, TransferError::EscrowNotExpired);
+
+    move_lamports(
+        &escrow.to_account_info(),
+        &ctx.accounts.sender.to_account_info(),
+        escrow.amount,
+    )?;
+
+    msg!("=== Conditional Expired ===");
+    msg!("Refunded: {} lamports", escrow.amount);
+    msg!("To: {}", ctx.accounts.sender.key());
+
+    // Remaining rent goes back to the sender, any dust to the treasury
+    close_account(
+        &ctx.accounts.escrow,
+        &ctx.accounts.sender,
+        &ctx.accounts.fee_config,
+        &ctx.accounts.treasury,
+    )
+}
+
+/// Lock SOL for a recipient until `unlock_ts`
+pub fn handle_escrow_timelock(
+    ctx: Context<EscrowTimelock>,
+    amount: u64,
+    unlock_ts: i64,
+) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(unlock_ts > now, TransferError::InvalidTimeout);
+
+    let max_lock_secs = ctx.accounts.state.max_lock_secs;
+    require!(
+        max_lock_secs == 0 || unlock_ts - now <= max_lock_secs as i64,
+        TransferError::LockTooLong
+    );
+
+    let timelock = &mut ctx.accounts.timelock;
+    timelock.sender = ctx.accounts.sender.key();
+    timelock.recipient = ctx.accounts.recipient.key();
+    timelock.amount = amount;
+    timelock.unlock_ts = unlock_ts;
+    timelock.bump = ctx.bumps.timelock;
+
+    // Move the locked amount on top of the rent already held by the PDA
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.timelock.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.timelock.key(),
+        amount,
+    );
+
+    msg!("=== Timelock Escrow ===");
+    msg!("Locked: {} lamports", amount);
+    msg!("Recipient: {}", ctx.accounts.recipient.key());
+    msg!("Unlocks at: {}", unlock_ts);
+
+    Ok(())
+}
+
+/// Release an unlocked timelock to its recipient
+///
+/// Callable by anyone once the unlock time has passed.
+pub fn handle_release_timelock(ctx: Context<ReleaseTimelock>) -> Result<()> {
+    let timelock = &ctx.accounts.timelock;
+    let now = Clock::get()?.unix_timestamp;
+    require!(now >= timelock.unlock_ts, TransferError::EscrowNotExpired);
+
+    move_lamports(
+        &timelock.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        timelock.amount,
+    )?;
+
+    msg!("=== Timelock Released ===");
+    msg!("Released: {} lamports", timelock.amount);
+    msg!("To: {}", ctx.accounts.recipient.key());
+
+    // Remaining rent goes back to the sender, any dust to the treasury
+    close_account(
+        &ctx.accounts.timelock,
+        &ctx.accounts.sender,
+        &ctx.accounts.fee_config,
+        &ctx.accounts.treasury,
+    )
+}
+
+/// Set the UTC business hours during which transfers are accepted
+pub fn handle_set_business_hours(
+    ctx: Context<UpdateState>,
+    open_hour: u8,
+    close_hour: u8,
+) -> Result<()> {
+    ctx.accounts.state.record_config_change(Clock::get()?.unix_timestamp)?;
+    require!(open_hour < 24 && close_hour < 24, TransferError::InvalidConfig);
+
+    let state = &mut ctx.accounts.state;
+    state.open_hour = open_hour;
+    state.close_hour = close_hour;
+
+    msg!("Business hours set: {}:00-{}:00 UTC", open_hour, close_hour);
+
+    Ok(())
+}
+
+/// Set the longest a timelock escrow may lock funds
+pub fn handle_set_max_lock_secs(ctx: Context<UpdateState>, max_lock_secs: u32) -> Result<()> {
+    ctx.accounts.state.record_config_change(Clock::get()?.unix_timestamp)?;
+    ctx.accounts.state.max_lock_secs = max_lock_secs;
+
+    msg!("Max lock duration set: {} seconds", max_lock_secs);
+
+    Ok(())
+}
+
+/// Set the fallback recipient used when a primary recipient is frozen
+pub fn handle_set_fallback_recipient(ctx: Context<UpdateState>, fallback: Pubkey) -> Result<()> {
+    ctx.accounts.state.record_config_change(Clock::get()?.unix_timestamp)?;
+    ctx.accounts.state.fallback_recipient = fallback;
+
+    msg!("Fallback recipient set: {}", fallback);
+
+    Ok(())
+}
+
+/// Freeze a recipient so it can no longer receive transfers
+pub fn handle_freeze_recipient(ctx: Context<FreezeRecipient>) -> Result<()> {
+    ctx.accounts.state.record_config_change(Clock::get()?.unix_timestamp)?;
+    let frozen = &mut ctx.accounts.frozen;
+    frozen.recipient = ctx.accounts.recipient.key();
+    frozen.bump = ctx.bumps.frozen;
+
+    msg!("Recipient frozen: {}", ctx.accounts.recipient.key());
+
+    Ok(())
+}
+
+/// Lift a recipient freeze
+pub fn handle_unfreeze_recipient(ctx: Context<UnfreezeRecipient>) -> Result<()> {
+    ctx.accounts.state.record_config_change(Clock::get()?.unix_timestamp)?;
+    msg!("Recipient unfrozen: {}", ctx.accounts.recipient.key());
+
+    // Remaining rent goes back to the authority, any dust to the treasury
+    close_account(
+        &ctx.accounts.frozen,
+        &ctx.accounts.authority,
+        &ctx.accounts.fee_config,
+        &ctx.accounts.treasury,
+    )
+}
+
+/// Exempt a sender from the global pause (authority only)
+pub fn handle_add_pause_exemption(ctx: Context<AddPauseExemption>) -> Result<()> {
+    ctx.accounts.state.record_config_change(Clock::get()?.unix_timestamp)?;
+    let exempt = &mut ctx.accounts.exempt;
+    exempt.sender = ctx.accounts.sender.key();
+    exempt.bump = ctx.bumps.exempt;
+
+    msg!("Sender exempt from pause: {}", ctx.accounts.sender.key());
+
+    Ok(())
+}
+
+/// Remove a sender's pause exemption
+pub fn handle_remove_pause_exemption(ctx: Context<RemovePauseExemption>) -> Result<()> {
+    ctx.accounts.state.record_config_change(Clock::get()?.unix_timestamp)?;
+    msg!("Pause exemption removed: {}", ctx.accounts.sender.key());
+
+    // Remaining rent goes back to the authority, any dust to the treasury
+    close_account(
+        &ctx.accounts.exempt,
+        &ctx.accounts.authority,
+        &ctx.accounts.fee_config,
+        &ctx.accounts.treasury,
+    )
+}
+
+/// Give a recipient priority, exempting transfers to it from daily caps (authority only)
+pub fn handle_add_priority_recipient(ctx: Context<AddPriorityRecipient>) -> Result<()> {
+    ctx.accounts.state.record_config_change(Clock::get()?.unix_timestamp)?;
+    let priority = &mut ctx.accounts.priority;
+    priority.recipient = ctx.accounts.recipient.key();
+    priority.bump = ctx.bumps.priority;
+
+    msg!("Priority recipient added: {}", ctx.accounts.recipient.key());
+
+    Ok(())
+}
+
+/// Remove a recipient's priority
+pub fn handle_remove_priority_recipient(ctx: Context<RemovePriorityRecipient>) -> Result<()> {
+    ctx.accounts.state.record_config_change(Clock::get()?.unix_timestamp)?;
+    msg!("Priority recipient removed: {}", ctx.accounts.recipient.key());
+
+    // Remaining rent goes back to the authority, any dust to the treasury
+    close_account(
+        &ctx.accounts.priority,
+        &ctx.accounts.authority,
+        &ctx.accounts.fee_config,
+        &ctx.accounts.treasury,
+    )
+}
+
+/// Transfer SOL, routing to the fallback recipient if the primary is frozen
+pub fn handle_transfer_sol_with_fallback(
+    ctx: Context<TransferSolWithFallback>,
+    amount: u64,
+    allow_fallback: bool,
+) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    // The freeze marker is a PDA; it only counts once this program owns it
+    let frozen = &ctx.accounts.frozen;
+    let is_frozen = frozen.owner == &crate::ID && !frozen.data_is_empty();
+
+    let destination = if is_frozen {
+        require!(allow_fallback, TransferError::RecipientFrozen);
+        let fallback = ctx.accounts.state.fallback_recipient;
+        require!(
+            fallback != Pubkey::default() && ctx.accounts.fallback.key() == fallback,
+            TransferError::InvalidRecipient
+        );
+        ctx.accounts.fallback.to_account_info()
+    } else {
+        ctx.accounts.recipient.to_account_info()
+    };
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &destination,
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &destination.key(),
+        amount,
+    );
+
+    msg!("=== SOL Transfer ===");
+    msg!("Amount: {} lamports", amount);
+    msg!("From: {}", ctx.accounts.sender.key());
+    if is_frozen {
+        msg!("Recipient {} is frozen, routed to fallback", ctx.accounts.recipient.key());
+    }
+    msg!("To: {}", destination.key());
+
+    Ok(())
+}
+
+/// Transfer SOL only if the caller knows the recipient's current nonce
+pub fn handle_transfer_sol_ordered(
+    ctx: Context<TransferSolOrdered>,
+    amount: u64,
+    expected_nonce: u64,
+) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    let nonce = &mut ctx.accounts.recipient_nonce;
+    require!(nonce.nonce == expected_nonce, TransferError::InvalidNonce);
+
+    nonce.recipient = ctx.accounts.recipient.key();
+    nonce.nonce = nonce.nonce.checked_add(1).ok_or(TransferError::InvalidNonce)?;
+    nonce.bump = ctx.bumps.recipient_nonce;
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.recipient.key(),
+        amount,
+    );
+
+    msg!("=== Ordered SOL Transfer ===");
+    msg!("Amount: {} lamports", amount);
+    msg!("To: {}", ctx.accounts.recipient.key());
+    msg!("Nonce: {} -> {}", expected_nonce, ctx.accounts.recipient_nonce.nonce);
+
+    Ok(())
+}
+
+/// Transfer SOL, accumulating stats in the sender's own PDA
+///
+/// Avoids write-locking `ProgramState` on every transfer; the accumulated
+/// stats are folded into the global totals later by `flush_stats`.
+pub fn handle_transfer_sol_batched(ctx: Context<TransferSolBatched>, amount: u64) -> Result<()> {
+    // Counted against the sender's stats below, keeping the state read-only
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        0,
+        &ctx.accounts.instructions,
+    )?;
+    let max_transfers = ctx.accounts.state.max_transfers_per_tx;
+    if max_transfers != 0 {
+        let fingerprint = tx_fingerprint(&ctx.accounts.instructions)?;
+        ctx.accounts
+            .sender_stats
+            .count_tx_transfer(fingerprint, 1, max_transfers)?;
+    }
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        amount,
+    )?;
+
+    let stats = &mut ctx.accounts.sender_stats;
+    stats.sender = ctx.accounts.sender.key();
+    stats.bump = ctx.bumps.sender_stats;
+    stats.record_transfer(amount, Clock::get()?.unix_timestamp);
+
+    msg!("=== SOL Transfer (batched stats) ===");
+    msg!("Amount: {} lamports", amount);
+    msg!("To: {}", ctx.accounts.recipient.key());
+    msg!("Pending: {} transfers, {} lamports", stats.pending_transfers, stats.pending_volume);
+
+    Ok(())
+}
+
+/// Fold a sender's pending stats into the global `ProgramState` totals
+pub fn handle_flush_stats(ctx: Context<FlushStats>) -> Result<()> {
+    let stats = &mut ctx.accounts.sender_stats;
+    let (transfers, volume) = (stats.pending_transfers, stats.pending_volume);
+
+    ctx.accounts.state.record_batch(transfers, volume);
+    stats.pending_transfers = 0;
+    stats.pending_volume = 0;
+
+    msg!("Flushed {} transfers, {} lamports for {}", transfers, volume, stats.sender);
+
+    Ok(())
+}
+
+/// Configure the transfer fee, its burn share and the treasury
+pub fn handle_set_fee(
+    ctx: Context<SetFee>,
+    fee_bps: u16,
+    burn_bps: u16,
+    treasury: Pubkey,
+) -> Result<()> {
+    ctx.accounts.state.record_config_change(Clock::get()?.unix_timestamp)?;
+    require!(
+        fee_bps as u64 <= BPS_DENOMINATOR && burn_bps as u64 <= BPS_DENOMINATOR,
+        TransferError::InvalidFeeConfig
+    );
+
+    let fee_config = &mut ctx.accounts.fee_config;
+    fee_config.fee_bps = fee_bps;
+    fee_config.burn_bps = burn_bps;
+    fee_config.treasury = treasury;
+    fee_config.bump = ctx.bumps.fee_config;
+
+    msg!("Fee set: {} bps ({} bps of it burned)", fee_bps, burn_bps);
+    msg!("Treasury: {}", treasury);
+
+    Ok(())
+}
+
+/// Configure the "hold to save" fee discount
+pub fn handle_set_fee_discount(
+    ctx: Context<UpdateFeeConfig>,
+    discount_min_balance: u64,
+    discount_fee_bps: u16,
+) -> Result<()> {
+    ctx.accounts.state.record_config_change(Clock::get()?.unix_timestamp)?;
+    require!(
+        discount_fee_bps as u64 <= BPS_DENOMINATOR,
+        TransferError::InvalidFeeConfig
+    );
+
+    let fee_config = &mut ctx.accounts.fee_config;
+    fee_config.discount_min_balance = discount_min_balance;
+    fee_config.discount_fee_bps = discount_fee_bps;
+
+    msg!(
+        "Fee discount set: {} bps for balances >= {} lamports",
+        discount_fee_bps,
+        discount_min_balance
+    );
+
+    Ok(())
+}
+
+/// Configure the fee rebate for large transfers
+pub fn handle_set_fee_rebate(
+    ctx: Context<UpdateFeeConfig>,
+    rebate_threshold: u64,
+    rebate_bps: u16,
+) -> Result<()> {
+    ctx.accounts.state.record_config_change(Clock::get()?.unix_timestamp)?;
+    require!(
+        rebate_bps as u64 <= BPS_DENOMINATOR,
+        TransferError::InvalidFeeConfig
+    );
+
+    let fee_config = &mut ctx.accounts.fee_config;
+    fee_config.rebate_threshold = rebate_threshold;
+    fee_config.rebate_bps = rebate_bps;
+
+    msg!(
+        "Fee rebate set: {} bps of the fee for transfers above {}",
+        rebate_bps,
+        rebate_threshold
+    );
+
+    Ok(())
+}
+
+/// Configure sweeping of fees past the hot treasury cap to the cold wallet
+pub fn handle_set_cold_sweep(
+    ctx: Context<UpdateFeeConfig>,
+    hot_treasury_cap: u64,
+    cold_wallet: Pubkey,
+) -> Result<()> {
+    ctx.accounts.state.record_config_change(Clock::get()?.unix_timestamp)?;
+    require!(
+        hot_treasury_cap == 0 || cold_wallet != Pubkey::default(),
+        TransferError::InvalidFeeConfig
+    );
+
+    let fee_config = &mut ctx.accounts.fee_config;
+    fee_config.hot_treasury_cap = hot_treasury_cap;
+    fee_config.cold_wallet = cold_wallet;
+
+    msg!("Hot treasury cap set: {} (cold wallet {})", hot_treasury_cap, cold_wallet);
+
+    Ok(())
+}
+
+/// Transfer SPL tokens, splitting the fee between the treasury and a burn
+pub fn handle_transfer_token_with_fee(
+    ctx: Context<TransferTokenWithFee>,
+    amount: u64,
+) -> Result<()> {
+    require!(amount > 0, TransferError::InvalidAmount);
+
+    let sender_lamports = ctx.accounts.sender.lamports();
+    let fee = ctx.accounts.fee_config.accrue_fee(amount, sender_lamports);
+    // Past the lifetime cap, transfers go through fee-free
+    let fee = ctx.accounts.state.capped_fee(fee);
+
+    // The full fee is collected; the rebate is paid back out of the rebate vault
+    let rebate = ctx.accounts.fee_config.rebate_for(amount, fee);
+    let state = &mut ctx.accounts.state;
+    state.total_fees_collected = state.total_fees_collected.saturating_add(fee - rebate);
+
+    let accounts = &ctx.accounts;
+    let mint = accounts.mint.key();
+    let source = token::read_token_account(&accounts.sender_token)?;
+    let destination = token::read_token_account(&accounts.recipient_token)?;
+    let treasury = token::read_token_account(&accounts.treasury_token)?;
+
+    require_keys_eq!(source.owner, accounts.sender.key(), TransferError::Unauthorized);
+    require!(
+        source.mint == mint && destination.mint == mint && treasury.mint == mint,
+        TransferError::InvalidTokenAccount
+    );
+    require_keys_eq!(
+        treasury.owner,
+        accounts.fee_config.treasury,
+        TransferError::InvalidTokenAccount
+    );
+    require!(source.amount >= amount, TransferError::InsufficientFunds);
+
+    let (treasury_share, burn_share) = accounts.fee_config.split_fee(fee);
+    let (treasury_share, swept) = accounts.fee_config.sweep_split(treasury.amount, treasury_share);
+    let net = amount - fee;
+
+    let authority = accounts.sender.to_account_info();
+    token::transfer(&accounts.sender_token, &accounts.recipient_token, &authority, net, &[])?;
+    if treasury_share > 0 {
+        token::transfer(
+            &accounts.sender_token,
+            &accounts.treasury_token,
+            &authority,
+            treasury_share,
+            &[],
+        )?;
+    }
+    if burn_share > 0 {
+        token::burn(&accounts.sender_token, &accounts.mint, &authority, burn_share, &[])?;
+    }
+    if swept > 0 {
+        let cold_token = accounts
+            .cold_token
+            .as_ref()
+            .ok_or(TransferError::ColdTokenRequired)?;
+        let cold = token::read_token_account(cold_token)?;
+        require!(
+            cold.mint == mint && cold.owner == accounts.fee_config.cold_wallet,
+            TransferError::InvalidTokenAccount
+        );
+        token::transfer(&accounts.sender_token, cold_token, &authority, swept, &[])?;
+        msg!("Swept {} past the hot treasury cap to {}", swept, cold_token.key());
+    }
+    if rebate > 0 {
+        let rebate_token = accounts
+            .rebate_token
+            .as_ref()
+            .ok_or(TransferError::RebateTokenRequired)?;
+        let vault = token::read_token_account(rebate_token)?;
+        require!(
+            vault.mint == mint && vault.owner == accounts.fee_config.key(),
+            TransferError::InvalidTokenAccount
+        );
+        let bump = [accounts.fee_config.bump];
+        let signer_seeds: &[&[u8]] = &[b"fee_config", &bump];
+        token::transfer(
+            rebate_token,
+            &accounts.sender_token,
+            &accounts.fee_config.to_account_info(),
+            rebate,
+            &[signer_seeds],
+        )?;
+    }
+
+    msg!("=== Token Transfer ===");
+    msg!("Amount: {} (net {})", amount, net);
+    msg!(
+        "Fee: {} (treasury {}, burned {}, rebated {})",
+        fee,
+        treasury_share,
+        burn_share,
+        rebate
+    );
+    msg!("To: {}", accounts.recipient_token.key());
+
+    Ok(())
+}
+
+/// Transfer SOL and log it as a compact binary record
+pub fn handle_transfer_sol_compact(ctx: Context<TransferSol>, amount: u64) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.recipient.key(),
+        amount,
+    );
+
+    CompactTransfer {
+        sender: ctx.accounts.sender.key(),
+        recipient: ctx.accounts.recipient.key(),
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    }
+    .log();
+
+    Ok(())
+}
+
+/// Transfer SOL, persisting a `TransferRecord` and folding it into the accumulator
+pub fn handle_transfer_sol_recorded(ctx: Context<TransferSolRecorded>, amount: u64) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.recipient.key(),
+        amount,
+    );
+
+    let accumulator = &mut ctx.accounts.accumulator;
+    accumulator.bump = ctx.bumps.accumulator;
+
+    let record = &mut ctx.accounts.record;
+    record.sender = ctx.accounts.sender.key();
+    record.recipient = ctx.accounts.recipient.key();
+    record.amount = amount;
+    record.timestamp = Clock::get()?.unix_timestamp;
+    record.index = accumulator.leaf_count;
+    record.bump = ctx.bumps.record;
+
+    // Link this record after the sender's previous one
+    let chain = &mut ctx.accounts.chain;
+    let max_records = ctx.accounts.state.max_records;
+    require!(
+        max_records == 0 || chain.active_records < max_records,
+        TransferError::TooManyRecords
+    );
+    chain.sender = record.sender;
+    chain.bump = ctx.bumps.chain;
+    chain.active_records += 1;
+    record.prev = chain.last_record;
+    chain.last_record = record.key();
+
+    accumulator
+        .append(record.leaf())
+        .ok_or(TransferError::AccumulatorFull)?;
+
+    msg!("=== SOL Transfer (recorded) ===");
+    msg!("Amount: {} lamports", amount);
+    msg!("To: {}", ctx.accounts.recipient.key());
+    msg!("Record #{}: {}", record.index, record.key());
+
+    Ok(())
+}
+
+/// Set how many transfer records a sender may keep open at once
+pub fn handle_set_max_records(ctx: Context<UpdateState>, max_records: u32) -> Result<()> {
+    ctx.accounts.state.record_config_change(Clock::get()?.unix_timestamp)?;
+    ctx.accounts.state.max_records = max_records;
+
+    msg!("Max open records per sender set: {}", max_records);
+
+    Ok(())
+}
+
+/// Close a transfer record, freeing a slot under the sender's record cap
+///
+/// Later records keep their `prev` link to the closed record.
+pub fn handle_close_record(ctx: Context<CloseRecord>) -> Result<()> {
+    let chain = &mut ctx.accounts.chain;
+    chain.active_records = chain.active_records.saturating_sub(1);
+
+    msg!("Record #{} closed", ctx.accounts.record.index);
+    msg!("Open records: {}", chain.active_records);
+
+    // Remaining rent goes back to the sender, any dust to the treasury
+    close_account(
+        &ctx.accounts.record,
+        &ctx.accounts.sender,
+        &ctx.accounts.fee_config,
+        &ctx.accounts.treasury,
+    )
+}
+
+/// Transfer SOL with the amount given in an explicit denomination
+pub fn handle_transfer_sol_denominated(
+    ctx: Context<TransferSol>,
+    amount: u64,
+    denom: u8,
+) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+
+    let lamports = match denom {
+        DENOM_LAMPORTS => amount,
+        DENOM_SOL => amount
+            .checked_mul(LAMPORTS_PER_SOL)
+            .ok_or(TransferError::InvalidAmount)?,
+        _ => return err!(TransferError::InvalidDenomination),
+    };
+
+    require!(lamports > 0, TransferError::InvalidAmount);
+    require!(
+        ctx.accounts.sender.lamports() >= lamports,
+        TransferError::InsufficientFunds
+    );
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        lamports,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.recipient.key(),
+        lamports,
+    );
+
+    msg!("=== SOL Transfer ===");
+    msg!("Amount: {} (denom {}) = {} lamports", amount, denom, lamports);
+    msg!("To: {}", ctx.accounts.recipient.key());
+
+    Ok(())
+}
+
+/// Replace the list of fixed amounts accepted by `transfer_sol_fixed`
+pub fn handle_set_allowed_amounts(
+    ctx: Context<SetAllowedAmounts>,
+    amounts: Vec<u64>,
+) -> Result<()> {
+    ctx.accounts.state.record_config_change(Clock::get()?.unix_timestamp)?;
+    require!(
+        amounts.len() <= MAX_ALLOWED_AMOUNTS,
+        TransferError::TooManyAllowedAmounts
+    );
+    require!(amounts.iter().all(|&a| a > 0), TransferError::InvalidAmount);
+
+    let allowed = &mut ctx.accounts.allowed_amounts;
+    allowed.amounts = amounts;
+    allowed.bump = ctx.bumps.allowed_amounts;
+
+    msg!("Allowed amounts set: {:?}", allowed.amounts);
+
+    Ok(())
+}
+
+/// Transfer SOL restricted to the configured fixed amounts
+pub fn handle_transfer_sol_fixed(ctx: Context<TransferSolFixed>, amount: u64) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(
+        ctx.accounts.allowed_amounts.allows(amount),
+        TransferError::AmountNotAllowed
+    );
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.recipient.key(),
+        amount,
+    );
+
+    msg!("=== SOL Transfer (fixed amount) ===");
+    msg!("Amount: {} lamports", amount);
+    msg!("To: {}", ctx.accounts.recipient.key());
+
+    Ok(())
+}
+
+/// Transfer SOL and record the caller-reported priority fee for analytics
+///
+/// `priority_fee` is not charged; it is only stored next to the volume.
+pub fn handle_transfer_sol_with_priority_fee(
+    ctx: Context<TransferSolWithPriorityFee>,
+    amount: u64,
+    priority_fee: u64,
+) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.recipient.key(),
+        amount,
+    );
+
+    let stats = &mut ctx.accounts.priority_fee_stats;
+    stats.bump = ctx.bumps.priority_fee_stats;
+    stats.record(amount, priority_fee);
+
+    msg!("=== SOL Transfer ===");
+    msg!("Amount: {} lamports", amount);
+    msg!("To: {}", ctx.accounts.recipient.key());
+    msg!("Reported priority fee: {}", priority_fee);
+
+    Ok(())
+}
+
+/// Register (or update) the caller's recipient settings
+pub fn handle_register_recipient(ctx: Context<RegisterRecipient>, category: u8) -> Result<()> {
+    let stats = &mut ctx.accounts.recipient_stats;
+    stats.recipient = ctx.accounts.recipient.key();
+    stats.category = category;
+    stats.bump = ctx.bumps.recipient_stats;
+
+    msg!("Recipient {} registered with category {}", stats.recipient, category);
+
+    Ok(())
+}
+
+/// Set the smallest transfer the caller accepts through stats-checked instructions
+pub fn handle_set_min_incoming(ctx: Context<RegisterRecipient>, min_incoming: u64) -> Result<()> {
+    let stats = &mut ctx.accounts.recipient_stats;
+    stats.recipient = ctx.accounts.recipient.key();
+    stats.min_incoming = min_incoming;
+    stats.bump = ctx.bumps.recipient_stats;
+
+    msg!("Recipient {} minimum incoming: {} lamports", stats.recipient, min_incoming);
+
+    Ok(())
+}
+
+/// Register the constraints the caller accepts `transfer_with_policy` transfers under
+pub fn handle_set_acceptance_policy(
+    ctx: Context<RegisterRecipient>,
+    min_incoming: u64,
+    require_memo: bool,
+    require_kyc: bool,
+) -> Result<()> {
+    let stats = &mut ctx.accounts.recipient_stats;
+    stats.recipient = ctx.accounts.recipient.key();
+    stats.min_incoming = min_incoming;
+    stats.require_memo = require_memo;
+    stats.require_kyc = require_kyc;
+    stats.bump = ctx.bumps.recipient_stats;
+
+    msg!(
+        "Recipient {} policy: minimum {} lamports, memo required: {}, KYC required: {}",
+        stats.recipient,
+        min_incoming,
+        require_memo,
+        require_kyc
+    );
+
+    Ok(())
+}
+
+/// Transfer SOL only if it satisfies the recipient's acceptance policy
+pub fn handle_transfer_with_policy(
+    ctx: Context<TransferWithPolicy>,
+    amount: u64,
+    memo: String,
+) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    ctx.accounts.recipient_stats.require_policy(amount, &memo)?;
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.recipient.key(),
+        amount,
+    );
+    ctx.accounts.recipient_stats.record_transfer(amount);
+
+    msg!("=== SOL Transfer (recipient policy) ===");
+    msg!("Amount: {} lamports", amount);
+    msg!("To: {}", ctx.accounts.recipient.key());
+    if !memo.is_empty() {
+        msg!("Memo: {}", memo);
+    }
+
+    Ok(())
+}
+
+/// Transfer SOL only if the recipient registered the expected category
+pub fn handle_transfer_to_category(
+    ctx: Context<TransferToCategory>,
+    amount: u64,
+    expected_category: u8,
+) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(
+        ctx.accounts.recipient_stats.category == expected_category,
+        TransferError::CategoryMismatch
+    );
+    ctx.accounts.recipient_stats.require_min_incoming(amount)?;
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.recipient.key(),
+        amount,
+    );
+    ctx.accounts.recipient_stats.record_transfer(amount);
+
+    msg!("=== SOL Transfer (category {}) ===", expected_category);
+    msg!("Amount: {} lamports", amount);
+    msg!("To: {}", ctx.accounts.recipient.key());
+
+    Ok(())
+}
+
+/// Mark a recipient as KYC-verified or not (authority only)
+pub fn handle_set_kyc_verified(ctx: Context<SetKycVerified>, verified: bool) -> Result<()> {
+    ctx.accounts.state.record_config_change(Clock::get()?.unix_timestamp)?;
+    let stats = &mut ctx.accounts.recipient_stats;
+    stats.recipient = ctx.accounts.recipient.key();
+    stats.kyc_verified = verified;
+    stats.bump = ctx.bumps.recipient_stats;
+
+    msg!("Recipient {} KYC verified: {}", stats.recipient, verified);
+
+    Ok(())
+}
+
+/// Transfer SOL only to a KYC-verified recipient
+pub fn handle_transfer_kyc(ctx: Context<TransferKyc>, amount: u64) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(
+        ctx.accounts.recipient_stats.kyc_verified,
+        TransferError::RecipientNotVerified
+    );
+    ctx.accounts.recipient_stats.require_min_incoming(amount)?;
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.recipient.key(),
+        amount,
+    );
+    ctx.accounts.recipient_stats.record_transfer(amount);
+
+    msg!("=== SOL Transfer (KYC) ===");
+    msg!("Amount: {} lamports", amount);
+    msg!("To: {}", ctx.accounts.recipient.key());
+
+    Ok(())
+}
+
+/// Refund part or all of a recorded transfer back to its sender
+pub fn handle_refund_transfer(ctx: Context<RefundTransfer>, amount: u64) -> Result<()> {
+    // Returning funds is never blocked by a pause or the recipient's guardian
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.recipient.key(),
+        &[],
+        true,
+        false,
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.sender)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(
+        amount <= ctx.accounts.record.refundable(),
+        TransferError::AlreadyRefunded
+    );
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.recipient.to_account_info(),
+        &ctx.accounts.sender.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.recipient.key(),
+        &ctx.accounts.sender.key(),
+        amount,
+    );
+
+    let record = &mut ctx.accounts.record;
+    record.refunded_amount += amount;
+
+    msg!("=== Refund ===");
+    msg!("Record #{}: refunded {} lamports", record.index, amount);
+    msg!("Refunded so far: {} of {}", record.refunded_amount, record.amount);
+
+    Ok(())
+}
+
+/// Pause or unpause transfers
+pub fn handle_set_paused(ctx: Context<UpdateState>, paused: bool) -> Result<()> {
+    ctx.accounts.state.paused = paused;
+
+    msg!("Transfers {}", if paused { "paused" } else { "unpaused" });
+
+    Ok(())
+}
+
+/// Set the total volume at which transfers auto-pause (0 disables it)
+pub fn handle_set_milestone_volume(ctx: Context<UpdateState>, milestone_volume: u64) -> Result<()> {
+    ctx.accounts.state.record_config_change(Clock::get()?.unix_timestamp)?;
+    ctx.accounts.state.milestone_volume = milestone_volume;
+
+    msg!("Volume milestone set: {} lamports", milestone_volume);
+
+    Ok(())
+}
+
+/// Set the SOL/USD rate used to value subsequent transfers
+pub fn handle_set_sol_usd_rate(ctx: Context<UpdateState>, sol_usd_rate: u64) -> Result<()> {
+    ctx.accounts.state.record_config_change(Clock::get()?.unix_timestamp)?;
+    ctx.accounts.state.sol_usd_rate = sol_usd_rate;
+
+    msg!("SOL/USD rate set: {} micro-USD", sol_usd_rate);
+
+    Ok(())
+}
+
+/// Get the total transfer volume in micro-USD (view function)
+pub fn handle_get_usd_volume(ctx: Context<GetState>) -> Result<u64> {
+    let volume = ctx.accounts.state.total_volume_usd;
+    msg!("USD volume: {} micro-USD", volume);
+    Ok(volume)
+}
+
+/// Transfer SOL and create a receipt PDA derived from the transfer details
+pub fn handle_transfer_with_receipt(
+    ctx: Context<TransferWithReceipt>,
+    amount: u64,
+    nonce: u64,
+) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.recipient.key(),
+        amount,
+    );
+
+    let receipt = &mut ctx.accounts.receipt;
+    receipt.sender = ctx.accounts.sender.key();
+    receipt.recipient = ctx.accounts.recipient.key();
+    receipt.amount = amount;
+    receipt.nonce = nonce;
+    receipt.timestamp = Clock::get()?.unix_timestamp;
+    receipt.bump = ctx.bumps.receipt;
+
+    msg!("=== SOL Transfer (receipt) ===");
+    msg!("Amount: {} lamports", amount);
+    msg!("To: {}", receipt.recipient);
+    msg!("Receipt: {}", receipt.key());
+
+    Ok(())
+}
+
+/// Check that a receipt exists for the given transfer details (view function)
+pub fn handle_verify_receipt(
+    ctx: Context<VerifyReceipt>,
+    sender: Pubkey,
+    recipient: Pubkey,
+    amount: u64,
+    nonce: u64,
+) -> Result<bool> {
+    // Address is already checked against the details by the seeds constraint
+    let info = ctx.accounts.receipt.to_account_info();
+    if info.owner != &crate::ID || info.data_is_empty() {
+        msg!("No receipt at {}", info.key());
+        return Ok(false);
+    }
+
+    let receipt = Receipt::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+    let valid = receipt.sender == sender
+        && receipt.recipient == recipient
+        && receipt.amount == amount
+        && receipt.nonce == nonce;
+
+    msg!("Receipt {} valid: {}", info.key(), valid);
+    Ok(valid)
+}
+
+/// Settle two opposing debts by moving only the net difference
+pub fn handle_net_settle(ctx: Context<NetSettle>, a_to_b: u64, b_to_a: u64) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.party_a.key(),
+        &[ctx.accounts.party_b.key()],
+        false,
+        has_guardian(&ctx.accounts.party_a_guardian)
+            || has_guardian(&ctx.accounts.party_b_guardian),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    require!(a_to_b > 0 || b_to_a > 0, TransferError::InvalidAmount);
+
+    let party_a = ctx.accounts.party_a.to_account_info();
+    let party_b = ctx.accounts.party_b.to_account_info();
+    let (payer, payee, net) = if a_to_b >= b_to_a {
+        (party_a, party_b, a_to_b - b_to_a)
+    } else {
+        (party_b, party_a, b_to_a - a_to_b)
+    };
+
+    msg!("=== Net Settlement ===");
+    msg!("A owes B: {} lamports, B owes A: {} lamports", a_to_b, b_to_a);
+
+    if net == 0 {
+        msg!("Debts cancel out, nothing to transfer");
+        return Ok(());
+    }
+
+    require!(payer.lamports() >= net, TransferError::InsufficientFunds);
+    system_transfer(&ctx.accounts.system_program, &payer, &payee, net)?;
+    ctx.accounts.state.record_transfer(&payer.key(), &payee.key(), net);
+
+    msg!("Net {} lamports from {} to {}", net, payer.key(), payee.key());
+
+    Ok(())
+}
+
+/// Configure the loyalty point accrual and redemption rates
+pub fn handle_set_loyalty_config(
+    ctx: Context<SetLoyaltyConfig>,
+    points_per_lamport: u64,
+    redeem_points_per_lamport: u64,
+) -> Result<()> {
+    ctx.accounts.state.record_config_change(Clock::get()?.unix_timestamp)?;
+    require!(redeem_points_per_lamport > 0, TransferError::InvalidConfig);
+
+    let config = &mut ctx.accounts.loyalty_config;
+    config.points_per_lamport = points_per_lamport;
+    config.redeem_points_per_lamport = redeem_points_per_lamport;
+    config.bump = ctx.bumps.loyalty_config;
+
+    msg!("Loyalty: {} points per lamport earned", points_per_lamport);
+    msg!("Loyalty: {} points per lamport redeemed", redeem_points_per_lamport);
+
+    Ok(())
+}
+
+/// Transfer SOL and credit the sender with loyalty points
+pub fn handle_transfer_with_points(ctx: Context<TransferWithPoints>, amount: u64) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.recipient.key(),
+        amount,
+    );
+
+    let earned = amount.saturating_mul(ctx.accounts.loyalty_config.points_per_lamport);
+    let points = &mut ctx.accounts.points;
+    points.owner = ctx.accounts.sender.key();
+    points.points = points.points.saturating_add(earned);
+    points.bump = ctx.bumps.points;
+
+    msg!("=== SOL Transfer (loyalty) ===");
+    msg!("Amount: {} lamports", amount);
+    msg!("To: {}", ctx.accounts.recipient.key());
+    msg!("Points earned: {} (balance {})", earned, points.points);
+
+    Ok(())
+}
+
+/// Redeem loyalty points for SOL paid out of the loyalty vault
+pub fn handle_redeem_points(ctx: Context<RedeemPoints>, points: u64) -> Result<()> {
+    require!(
+        points <= ctx.accounts.points.points,
+        TransferError::InsufficientPoints
+    );
+
+    let rate = ctx.accounts.loyalty_config.redeem_points_per_lamport;
+    let lamports = points / rate;
+    require!(lamports > 0, TransferError::InvalidAmount);
+
+    // The vault is the config account itself; its rent reserve is not redeemable
+    let vault = ctx.accounts.loyalty_config.to_account_info();
+    let reserve = Rent::get()?.minimum_balance(vault.data_len());
+    require!(
+        vault.lamports().saturating_sub(reserve) >= lamports,
+        TransferError::InsufficientFunds
+    );
+
+    // Only the points backing whole lamports are spent
+    let spent = lamports * rate;
+    ctx.accounts.points.points -= spent;
+    move_lamports(&vault, &ctx.accounts.owner.to_account_info(), lamports)?;
+
+    msg!("=== Points Redeemed ===");
+    msg!("Points: {} for {} lamports", spent, lamports);
+    msg!("Remaining points: {}", ctx.accounts.points.points);
+
+    Ok(())
+}
+
+/// Transfer SOL and add it to the sender's self-reported region stats
+pub fn handle_transfer_with_region(
+    ctx: Context<TransferWithRegion>,
+    amount: u64,
+    region: u8,
+) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.recipient.key(),
+        amount,
+    );
+
+    let stats = &mut ctx.accounts.region_stats;
+    stats.region = region;
+    stats.bump = ctx.bumps.region_stats;
+    stats.record_transfer(amount);
+
+    msg!("=== SOL Transfer (region {}) ===", region);
+    msg!("Amount: {} lamports", amount);
+    msg!("To: {}", ctx.accounts.recipient.key());
+
+    Ok(())
+}
+
+/// Atomically swap SOL from the buyer for SPL tokens from the seller
+pub fn handle_swap_sol_for_token(
+    ctx: Context<SwapSolForToken>,
+    sol_amount: u64,
+    token_amount: u64,
+) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.buyer.key(),
+        &[ctx.accounts.seller.key()],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    require!(sol_amount > 0 && token_amount > 0, TransferError::InvalidAmount);
+
+    let accounts = &ctx.accounts;
+    let mint = accounts.mint.key();
+    let source = token::read_token_account(&accounts.seller_token)?;
+    let destination = token::read_token_account(&accounts.buyer_token)?;
+
+    require_keys_eq!(source.owner, accounts.seller.key(), TransferError::Unauthorized);
+    require!(
+        source.mint == mint && destination.mint == mint,
+        TransferError::InvalidTokenAccount
+    );
+    require!(source.amount >= token_amount, TransferError::InsufficientFunds);
+    require!(
+        accounts.buyer.lamports() >= sol_amount,
+        TransferError::InsufficientFunds
+    );
+
+    // Either leg failing reverts the whole transaction
+    system_transfer(
+        &accounts.system_program,
+        &accounts.buyer.to_account_info(),
+        &accounts.seller.to_account_info(),
+        sol_amount,
+    )?;
+    token::transfer(
+        &accounts.seller_token,
+        &accounts.buyer_token,
+        &accounts.seller.to_account_info(),
+        token_amount,
+        &[],
+    )?;
+
+    msg!("=== SOL/Token Swap ===");
+    msg!("Buyer: {} paid {} lamports", accounts.buyer.key(), sol_amount);
+    msg!("Seller: {} paid {} tokens of {}", accounts.seller.key(), token_amount, mint);
+
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.buyer.key(),
+        &ctx.accounts.seller.key(),
+        sol_amount,
+    );
+
+    Ok(())
+}
+
+/// Deposit SOL into a stream that pays the recipient `rate_per_sec`
+pub fn handle_create_stream(
+    ctx: Context<CreateStream>,
+    deposit: u64,
+    rate_per_sec: u64,
+) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(deposit > 0 && rate_per_sec > 0, TransferError::InvalidAmount);
+    require!(
+        ctx.accounts.sender.lamports() >= deposit,
+        TransferError::InsufficientFunds
+    );
+
+    let stream = &mut ctx.accounts.stream;
+    stream.sender = ctx.accounts.sender.key();
+    stream.recipient = ctx.accounts.recipient.key();
+    stream.deposit = deposit;
+    stream.rate_per_sec = rate_per_sec;
+    stream.start_ts = Clock::get()?.unix_timestamp;
+    stream.withdrawn = 0;
+    stream.bump = ctx.bumps.stream;
+
+    // Move the deposit on top of the rent already held by the PDA
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.stream.to_account_info(),
+        deposit,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.stream.key(),
+        deposit,
+    );
+
+    msg!("=== Stream Created ===");
+    msg!("Deposit: {} lamports at {} lamports/sec", deposit, rate_per_sec);
+    msg!("Recipient: {}", ctx.accounts.recipient.key());
+
+    Ok(())
+}
+
+/// Pay the recipient everything accrued on a stream so far
+pub fn handle_withdraw_stream(ctx: Context<WithdrawStream>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let amount = ctx.accounts.stream.withdrawable(now);
+    require!(amount > 0, TransferError::NothingToWithdraw);
+
+    move_lamports(
+        &ctx.accounts.stream.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        amount,
+    )?;
+    let stream = &mut ctx.accounts.stream;
+    stream.withdrawn += amount;
+
+    msg!("=== Stream Withdrawal ===");
+    msg!("Withdrawn: {} lamports ({} of {} total)", amount, stream.withdrawn, stream.deposit);
+
+    Ok(())
+}
+
+/// Cancel a stream: pay the recipient what accrued and refund the rest
+pub fn handle_cancel_stream(ctx: Context<CancelStream>) -> Result<()> {
+    let stream = &ctx.accounts.stream;
+    let now = Clock::get()?.unix_timestamp;
+    let owed = stream.withdrawable(now);
+    let refund = stream.deposit - stream.accrued(now);
+
+    if owed > 0 {
+        move_lamports(
+            &stream.to_account_info(),
+            &ctx.accounts.recipient.to_account_info(),
+            owed,
+        )?;
+    }
+    // Refunded explicitly so a small refund isn't mistaken for dust
+    move_lamports(
+        &stream.to_account_info(),
+        &ctx.accounts.sender.to_account_info(),
+        refund,
+    )?;
+
+    msg!("=== Stream Cancelled ===");
+    msg!("Paid to recipient: {} lamports", owed);
+    msg!("Refunded to sender: {} lamports", refund);
+
+    // Remaining rent goes back to the sender, any dust to the treasury
+    close_account(
+        &ctx.accounts.stream,
+        &ctx.accounts.sender,
+        &ctx.accounts.fee_config,
+        &ctx.accounts.treasury,
+    )
+}
+
+/// Set whether transfers must be signed by the sender alone
+pub fn handle_set_require_sole_signer(ctx: Context<UpdateState>, required: bool) -> Result<()> {
+    ctx.accounts.state.record_config_change(Clock::get()?.unix_timestamp)?;
+    ctx.accounts.state.require_sole_signer = required;
+
+    msg!("Sole signer required: {}", required);
+
+    Ok(())
+}
+
+/// Set how long reversible transfers stay reversible
+pub fn handle_set_challenge_period(ctx: Context<UpdateState>, secs: u32) -> Result<()> {
+    ctx.accounts.state.record_config_change(Clock::get()?.unix_timestamp)?;
+    ctx.accounts.state.challenge_period_secs = secs;
+
+    msg!("Challenge period set: {} seconds", secs);
+
+    Ok(())
+}
+
+/// Set the most lamports a sender may send per UTC day through capped transfers
+pub fn handle_set_sender_daily_cap(ctx: Context<UpdateState>, cap: u64) -> Result<()> {
+    ctx.accounts.state.record_config_change(Clock::get()?.unix_timestamp)?;
+    ctx.accounts.state.sender_daily_cap = cap;
+
+    msg!("Sender daily cap set: {} lamports", cap);
+
+    Ok(())
+}
+
+/// Set how long config changes must be spaced apart
+///
+/// Goes through the current cooldown itself, so it cannot be lifted early.
+pub fn handle_set_config_cooldown(ctx: Context<UpdateState>, secs: u32) -> Result<()> {
+    ctx.accounts.state.record_config_change(Clock::get()?.unix_timestamp)?;
+    ctx.accounts.state.config_cooldown_secs = secs;
+
+    msg!("Config cooldown set: {} seconds", secs);
+
+    Ok(())
+}
+
+/// Require or stop requiring a memo on every SOL-moving instruction
+pub fn handle_set_memo_required(ctx: Context<UpdateState>, required: bool) -> Result<()> {
+    ctx.accounts.state.record_config_change(Clock::get()?.unix_timestamp)?;
+    ctx.accounts.state.memo_required = required;
+
+    msg!("Memo required: {}", required);
+
+    Ok(())
+}
+
+/// Set the largest share of their balance a sender may move in one unconfirmed guarded transfer
+pub fn handle_set_max_fraction_bps(ctx: Context<UpdateState>, max_fraction_bps: u16) -> Result<()> {
+    ctx.accounts.state.record_config_change(Clock::get()?.unix_timestamp)?;
+    require!(
+        max_fraction_bps as u64 <= BPS_DENOMINATOR,
+        TransferError::InvalidConfig
+    );
+    ctx.accounts.state.max_fraction_bps = max_fraction_bps;
+
+    msg!("Max balance fraction set: {} bps", max_fraction_bps);
+
+    Ok(())
+}
+
+/// Set the most token fees the program will ever collect
+pub fn handle_set_lifetime_fee_cap(ctx: Context<UpdateState>, cap: u64) -> Result<()> {
+    ctx.accounts.state.record_config_change(Clock::get()?.unix_timestamp)?;
+    ctx.accounts.state.lifetime_fee_cap = cap;
+
+    msg!(
+        "Lifetime fee cap set: {} ({} collected so far)",
+        cap,
+        ctx.accounts.state.total_fees_collected
+    );
+
+    Ok(())
+}
+
+/// Allow or forbid transfers to executable (program) accounts
+pub fn handle_set_allow_executable(ctx: Context<UpdateState>, allowed: bool) -> Result<()> {
+    ctx.accounts.state.record_config_change(Clock::get()?.unix_timestamp)?;
+    ctx.accounts.state.allow_executable = allowed;
+
+    msg!("Executable recipients allowed: {}", allowed);
+
+    Ok(())
+}
+
+/// Shared gate for every instruction that moves SOL out of a signer's wallet
+///
+/// `co_signers` are the other parties the instruction itself requires to
+/// sign, such as a counterparty or guardian; they are not treated as
+/// relayers. `exempt` lets the sender through a pause, `guarded` marks a
+/// sender whose guardian this instruction does not consult, and `transfers` is
+/// how many transfers the instruction makes towards `max_transfers_per_tx`
+/// (0 when it counts them elsewhere).
+pub(crate) fn open_transfer(
+    state: &mut ProgramState,
+    sender: &Pubkey,
+    co_signers: &[Pubkey],
+    exempt: bool,
+    guarded: bool,
+    transfers: u16,
+    instructions: &AccountInfo,
+) -> Result<()> {
+    state.require_open(sender, exempt)?;
+    // Only `transfer_with_guardian` asks the guardian, so it is the only way out
+    require!(!guarded, TransferError::GuardianRequired);
+    require_sole_signer(state, instructions, sender, co_signers)?;
+    require_memo(state, instructions)?;
+    limit_transfers_per_tx(state, transfers, instructions)
+}
+
+/// Whether `marker` is an existing pause exemption marker
+///
+/// The marker is a PDA; it only counts once this program owns it.
+pub(crate) fn is_pause_exempt(marker: &AccountInfo) -> bool {
+    marker.owner == &crate::ID && !marker.data_is_empty()
+}
+
+/// Whether `config` is an existing guardian setting
+///
+/// Like the pause exemption marker, it only counts once this program owns it.
+pub(crate) fn has_guardian(config: &AccountInfo) -> bool {
+    config.owner == &crate::ID && !config.data_is_empty()
+}
+
+/// When `require_sole_signer` is set, reject transactions signed by anyone but `sender`
+/// and the instruction's own `co_signers`
+///
+/// Any other signer on any instruction of the transaction is treated as a
+/// relayer. This checks signers only: the sysvar lists no fee payer, so one
+/// that none of the transaction's instructions reference goes unnoticed.
+pub(crate) fn require_sole_signer(
+    state: &ProgramState,
+    instructions: &AccountInfo,
+    sender: &Pubkey,
+    co_signers: &[Pubkey],
+) -> Result<()> {
+    if !state.require_sole_signer {
+        return Ok(());
+    }
+
+    let mut index = 0;
+    while let Ok(instruction) = load_instruction_at_checked(index, instructions) {
+        require!(
+            instruction
+                .accounts
+                .iter()
+                .all(|meta| {
+                    !meta.is_signer || meta.pubkey == *sender || co_signers.contains(&meta.pubkey)
+                }),
+            TransferError::RelayerNotAllowed
+        );
+        index += 1;
+    }
+    Ok(())
+}
+
+/// Set the most transfers a single transaction may make through this program
+pub fn handle_set_max_transfers_per_tx(ctx: Context<UpdateState>, max_transfers: u16) -> Result<()> {
+    ctx.accounts.state.record_config_change(Clock::get()?.unix_timestamp)?;
+    ctx.accounts.state.max_transfers_per_tx = max_transfers;
+
+    msg!("Max transfers per transaction: {}", max_transfers);
+
+    Ok(())
+}
+
+/// SPL Memo program (v2)
+const MEMO_PROGRAM_ID: Pubkey = pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+
+/// When `memo_required` is set, require the transaction to carry a non-empty SPL Memo
+///
+/// The memo may sit anywhere in the transaction, before or after the transfer.
+pub(crate) fn require_memo(state: &ProgramState, instructions: &AccountInfo) -> Result<()> {
+    if !state.memo_required {
+        return Ok(());
+    }
+
+    let mut index = 0;
+    while let Ok(instruction) = load_instruction_at_checked(index, instructions) {
+        if instruction.program_id == MEMO_PROGRAM_ID && !instruction.data.is_empty() {
+            return Ok(());
+        }
+        index += 1;
+    }
+    err!(TransferError::MemoRequired)
+}
+
+/// When `max_transfers_per_tx` is set, count `transfers` against the running transaction
+pub(crate) fn limit_transfers_per_tx(
+    state: &mut ProgramState,
+    transfers: u16,
+    instructions: &AccountInfo,
+) -> Result<()> {
+    if state.max_transfers_per_tx == 0 || transfers == 0 {
+        return Ok(());
+    }
+
+    let fingerprint = tx_fingerprint(instructions)?;
+    state.count_tx_transfer(fingerprint, transfers)
+}
+
+/// Identify the running transaction for the per-transaction cap
+///
+/// Programs cannot see a transaction's signature, so it is identified by the
+/// slot and every instruction it contains, read from the instructions sysvar.
+/// Those are the same for a top-level call and for CPIs made by a composing
+/// program, so all the transfers a transaction triggers share one counter.
+/// Two identical transactions landing in the same slot share it as well.
+pub(crate) fn tx_fingerprint(instructions: &AccountInfo) -> Result<u64> {
+    let mut message = Clock::get()?.slot.to_le_bytes().to_vec();
+    let mut index = 0;
+    while let Ok(instruction) = load_instruction_at_checked(index, instructions) {
+        message.extend_from_slice(instruction.program_id.as_ref());
+        for meta in &instruction.accounts {
+            message.extend_from_slice(meta.pubkey.as_ref());
+            message.extend_from_slice(&[meta.is_signer as u8, meta.is_writable as u8]);
+        }
+        message.extend_from_slice(&(instruction.data.len() as u32).to_le_bytes());
+        message.extend_from_slice(&instruction.data);
+        index += 1;
+    }
+
+    let hash = hashv(&[&message]).to_bytes();
+    let mut fingerprint = [0u8; 8];
+    fingerprint.copy_from_slice(&hash[..8]);
+    Ok(u64::from_le_bytes(fingerprint))
+}
+
+/// Get the rolling checksum over all recorded transfers (view function)
+pub fn handle_get_checksum(ctx: Context<GetState>) -> Result<[u8; 32]> {
+    let checksum = ctx.accounts.state.checksum;
+    msg!("Checksum: {:?}", checksum);
+    Ok(checksum)
+}
+
+/// Transfer SOL, emitting `FirstTransferBetween` if the pair has never transacted
+pub fn handle_transfer_sol_paired(ctx: Context<TransferSolPaired>, amount: u64) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.recipient.key(),
+        amount,
+    );
+
+    msg!("=== SOL Transfer (paired) ===");
+    msg!("Amount: {} lamports", amount);
+    msg!("To: {}", ctx.accounts.recipient.key());
+
+    // A freshly created marker is still zeroed
+    let pair = &mut ctx.accounts.pair;
+    if pair.sender == Pubkey::default() {
+        let timestamp = Clock::get()?.unix_timestamp;
+        pair.sender = ctx.accounts.sender.key();
+        pair.recipient = ctx.accounts.recipient.key();
+        pair.first_transfer_ts = timestamp;
+        pair.bump = ctx.bumps.pair;
+
+        emit!(FirstTransferBetween {
+            sender: pair.sender,
+            recipient: pair.recipient,
+            amount,
+            timestamp,
+        });
+        msg!("First transfer between this pair");
+    }
+
+    Ok(())
+}
+
+/// Set the guardian who must co-sign the sender's guarded transfers to new recipients
+///
+/// Replacing an existing guardian needs that guardian's co-signature, so the
+/// protection can't be switched off with the sender's key alone.
+pub fn handle_set_guardian(ctx: Context<SetGuardian>, guardian: Pubkey) -> Result<()> {
+    // The default key can never co-sign, so it would lock the sender out
+    require!(guardian != Pubkey::default(), TransferError::InvalidConfig);
+
+    let config = &mut ctx.accounts.guardian_config;
+    if config.guardian != Pubkey::default() {
+        require!(
+            ctx.accounts
+                .current_guardian
+                .as_ref()
+                .is_some_and(|signer| signer.key() == config.guardian),
+            TransferError::GuardianRequired
+        );
+    }
+
+    config.sender = ctx.accounts.sender.key();
+    config.guardian = guardian;
+    config.bump = ctx.bumps.guardian_config;
+
+    msg!("Guardian for {} set: {}", config.sender, guardian);
+
+    Ok(())
+}
+
+/// Transfer SOL, requiring the guardian's co-signature the first time a recipient is paid
+pub fn handle_transfer_with_guardian(
+    ctx: Context<TransferWithGuardian>,
+    amount: u64,
+) -> Result<()> {
+    let co_signers: Vec<Pubkey> = ctx.accounts.guardian.iter().map(|g| g.key()).collect();
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &co_signers,
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        false,
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    // A freshly created marker is still zeroed
+    let first_transfer = ctx.accounts.approved.sender == Pubkey::default();
+    if first_transfer {
+        let guardian = ctx.accounts.guardian_config.guardian;
+        require!(
+            ctx.accounts
+                .guardian
+                .as_ref()
+                .is_some_and(|signer| signer.key() == guardian),
+            TransferError::GuardianRequired
+        );
+
+        let approved = &mut ctx.accounts.approved;
+        approved.sender = ctx.accounts.sender.key();
+        approved.recipient = ctx.accounts.recipient.key();
+        approved.approved_at = Clock::get()?.unix_timestamp;
+        approved.bump = ctx.bumps.approved;
+    }
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.recipient.key(),
+        amount,
+    );
+
+    msg!("=== SOL Transfer (guardian) ===");
+    msg!("Amount: {} lamports", amount);
+    msg!("To: {}", ctx.accounts.recipient.key());
+    if first_transfer {
+        msg!("New recipient approved by guardian");
+    }
+
+    Ok(())
+}
+
+/// Set the mint that token-gated transfers require recipients to hold
+pub fn handle_set_token_gate(ctx: Context<SetTokenGate>, mint: Pubkey) -> Result<()> {
+    ctx.accounts.state.record_config_change(Clock::get()?.unix_timestamp)?;
+
+    let gate = &mut ctx.accounts.token_gate;
+    gate.mint = mint;
+    gate.bump = ctx.bumps.token_gate;
+
+    msg!("Token gate mint set to {}", mint);
+
+    Ok(())
+}
+
+/// Transfer SOL only to a recipient holding a token of the configured mint
+pub fn handle_transfer_token_gated(ctx: Context<TransferTokenGated>, amount: u64) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+
+    let required_mint = ctx.accounts.token_gate.mint;
+    let holding = token::read_token_account(&ctx.accounts.recipient_token)?;
+    require!(
+        holding.owner == ctx.accounts.recipient.key()
+            && holding.mint == required_mint
+            && holding.amount > 0,
+        TransferError::MissingRequiredToken
+    );
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.recipient.key(),
+        amount,
+    );
+
+    msg!("=== SOL Transfer (token-gated) ===");
+    msg!("Amount: {} lamports", amount);
+    msg!("To: {} (holds {})", ctx.accounts.recipient.key(), required_mint);
+
+    Ok(())
+}
+
+/// Transfer SOL at most once per external event id within its TTL
+pub fn handle_transfer_idempotent(
+    ctx: Context<TransferIdempotent>,
+    amount: u64,
+    event_id: [u8; 32],
+    ttl_secs: u32,
+) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(ttl_secs > 0, TransferError::InvalidTimeout);
+
+    // An expired marker that nobody cleaned up yet is simply reused
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        now >= ctx.accounts.marker.expires_at,
+        TransferError::DuplicateEventId
+    );
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.recipient.key(),
+        amount,
+    );
+
+    let marker = &mut ctx.accounts.marker;
+    marker.sender = ctx.accounts.sender.key();
+    marker.event_id = event_id;
+    marker.expires_at = now.saturating_add(ttl_secs as i64);
+    marker.bump = ctx.bumps.marker;
+
+    msg!("=== SOL Transfer (idempotent) ===");
+    msg!("Amount: {} lamports", amount);
+    msg!("To: {}", ctx.accounts.recipient.key());
+    msg!("Event id blocked until {}", marker.expires_at);
+
+    Ok(())
+}
+
+/// Close an expired idempotency marker, returning its rent to the sender
+pub fn handle_cleanup_idempotency(ctx: Context<CleanupIdempotency>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        now >= ctx.accounts.marker.expires_at,
+        TransferError::MarkerNotExpired
+    );
+
+    msg!("Idempotency marker {} cleaned up", ctx.accounts.marker.key());
+
+    // Remaining rent goes back to the sender, any dust to the treasury
+    close_account(
+        &ctx.accounts.marker,
+        &ctx.accounts.sender,
+        &ctx.accounts.fee_config,
+        &ctx.accounts.treasury,
+    )
+}
+
+/// Configure the bonding curve while no units are outstanding
+pub fn handle_set_curve(ctx: Context<SetCurve>, base_price: u64, slope: u64) -> Result<()> {
+    ctx.accounts.state.record_config_change(Clock::get()?.unix_timestamp)?;
+    let curve = &mut ctx.accounts.curve;
+    require!(curve.supply == 0, TransferError::InvalidConfig);
+
+    curve.base_price = base_price;
+    curve.slope = slope;
+    curve.bump = ctx.bumps.curve;
+
+    msg!("Curve set: base price {} lamports, slope {}", base_price, slope);
+
+    Ok(())
+}
+
+/// Buy units from the bonding curve, paying SOL into its reserve
+pub fn handle_buy_curve(ctx: Context<BuyCurve>, units: u64) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.buyer.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    require!(units > 0, TransferError::InvalidAmount);
+
+    let curve = &ctx.accounts.curve;
+    let cost = curve.cost(curve.supply, units)?;
+    let supply = curve.supply.checked_add(units).ok_or(TransferError::MathOverflow)?;
+    require!(
+        ctx.accounts.buyer.lamports() >= cost,
+        TransferError::InsufficientFunds
+    );
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.buyer.to_account_info(),
+        &ctx.accounts.curve.to_account_info(),
+        cost,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.buyer.key(),
+        &ctx.accounts.curve.key(),
+        cost,
+    );
+
+    ctx.accounts.curve.supply = supply;
+    let position = &mut ctx.accounts.position;
+    position.owner = ctx.accounts.buyer.key();
+    position.units += units;
+    position.bump = ctx.bumps.position;
+
+    msg!("=== Curve Buy ===");
+    msg!("Units: {} for {} lamports", units, cost);
+    msg!("Next unit price: {} lamports", ctx.accounts.curve.price()?);
+
+    Ok(())
+}
+
+/// Sell units back to the bonding curve, paid out of its reserve
+pub fn handle_sell_curve(ctx: Context<SellCurve>, units: u64) -> Result<()> {
+    require!(units > 0, TransferError::InvalidAmount);
+    require!(
+        units <= ctx.accounts.position.units,
+        TransferError::InsufficientFunds
+    );
+
+    // Units are priced on the way down exactly as they were on the way up
+    let curve = &mut ctx.accounts.curve;
+    curve.supply -= units;
+    let proceeds = curve.cost(curve.supply, units)?;
+    ctx.accounts.position.units -= units;
+
+    move_lamports(
+        &ctx.accounts.curve.to_account_info(),
+        &ctx.accounts.owner.to_account_info(),
+        proceeds,
+    )?;
+
+    msg!("=== Curve Sell ===");
+    msg!("Units: {} for {} lamports", units, proceeds);
+    msg!("Next unit price: {} lamports", ctx.accounts.curve.price()?);
+
+    Ok(())
+}
+
+/// Get the price of the next unit on the bonding curve (view function)
+pub fn handle_get_price(ctx: Context<GetCurve>) -> Result<u64> {
+    let price = ctx.accounts.curve.price()?;
+    msg!("Curve price: {} lamports", price);
+    Ok(price)
+}
+
+/// Lock SOL in an escrow anyone holding the matching secret can claim
+pub fn handle_escrow_password(
+    ctx: Context<EscrowPassword>,
+    amount: u64,
+    key_hash: [u8; 32],
+) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    let escrow = &mut ctx.accounts.escrow;
+    escrow.sender = ctx.accounts.sender.key();
+    escrow.amount = amount;
+    escrow.key_hash = key_hash;
+    escrow.bump = ctx.bumps.escrow;
+
+    // Move the escrowed amount on top of the rent already held by the PDA
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.escrow.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.escrow.key(),
+        amount,
+    );
+
+    msg!("=== Password Escrow ===");
+    msg!("Locked: {} lamports", amount);
+    msg!("Escrow: {}", ctx.accounts.escrow.key());
+
+    Ok(())
+}
+
+/// Claim a password escrow by presenting its secret
+pub fn handle_claim_password(ctx: Context<ClaimPassword>, secret: Vec<u8>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let escrow = &mut ctx.accounts.escrow;
+    require!(escrow.accepts_claim_at(now), TransferError::ClaimRateLimited);
+
+    if hashv(&[&secret]).to_bytes() != escrow.key_hash {
+        // Succeed so the attempt is recorded and the cooldown applies
+        escrow.failed_attempts = escrow.failed_attempts.saturating_add(1);
+        escrow.last_failed_ts = now;
+        msg!("Incorrect secret ({} failed attempts)", escrow.failed_attempts);
+        return Ok(());
+    }
+
+    let amount = escrow.amount;
+    move_lamports(
+        &escrow.to_account_info(),
+        &ctx.accounts.claimer.to_account_info(),
+        amount,
+    )?;
+
+    msg!("=== Password Escrow Claimed ===");
+    msg!("Claimed: {} lamports", amount);
+    msg!("By: {}", ctx.accounts.claimer.key());
+
+    // Remaining rent goes back to the sender, any dust to the treasury
+    close_account(
+        &ctx.accounts.escrow,
+        &ctx.accounts.sender,
+        &ctx.accounts.fee_config,
+        &ctx.accounts.treasury,
+    )
+}
+
+/// Transfer SOL and count it in the current day's size histogram
+pub fn handle_transfer_with_histogram(
+    ctx: Context<TransferWithHistogram>,
+    amount: u64,
+    day: i64,
+) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(
+        day == utc_day(Clock::get()?.unix_timestamp),
+        TransferError::InvalidDay
+    );
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.recipient.key(),
+        amount,
+    );
+
+    let histogram = &mut ctx.accounts.histogram;
+    histogram.day = day;
+    histogram.bump = ctx.bumps.histogram;
+    histogram.record_transfer(amount);
+
+    msg!("=== SOL Transfer (histogram day {}) ===", day);
+    msg!("Amount: {} lamports (bucket {})", amount, Histogram::bucket_for(amount));
+    msg!("To: {}", ctx.accounts.recipient.key());
+
+    Ok(())
+}
+
+/// Get a day's transfer size histogram (view function)
+pub fn handle_get_histogram(
+    ctx: Context<GetHistogram>,
+    day: i64,
+) -> Result<[u64; HISTOGRAM_BUCKETS]> {
+    let buckets = ctx.accounts.histogram.buckets;
+    msg!("Histogram for day {}: {:?}", day, buckets);
+    Ok(buckets)
+}
+
+/// Set how many prior transfers `transfer_with_history` requires
+pub fn handle_set_min_history(ctx: Context<UpdateState>, min_history: u32) -> Result<()> {
+    ctx.accounts.state.record_config_change(Clock::get()?.unix_timestamp)?;
+    ctx.accounts.state.min_history = min_history;
+
+    msg!("Minimum sender history: {} transfers", min_history);
+
+    Ok(())
+}
+
+/// Transfer SOL only if the sender already made enough tracked transfers
+pub fn handle_transfer_with_history(ctx: Context<TransferWithHistory>, amount: u64) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(
+        ctx.accounts.sender_stats.transfer_count >= ctx.accounts.state.min_history as u64,
+        TransferError::InsufficientHistory
+    );
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.recipient.key(),
+        amount,
+    );
+
+    let stats = &mut ctx.accounts.sender_stats;
+    stats.sender = ctx.accounts.sender.key();
+    stats.bump = ctx.bumps.sender_stats;
+    // Already in the program totals, so nothing is left pending for `flush_stats`
+    stats.record_lifetime(amount, Clock::get()?.unix_timestamp);
+
+    msg!("=== SOL Transfer (history {}) ===", stats.transfer_count - 1);
+    msg!("Amount: {} lamports", amount);
+    msg!("To: {}", ctx.accounts.recipient.key());
+
+    Ok(())
+}
+
+/// Issue an invoice made of line items
+pub fn handle_create_invoice(
+    ctx: Context<CreateInvoice>,
+    invoice_id: u64,
+    line_items: Vec<u64>,
+) -> Result<()> {
+    require!(
+        line_items.len() <= MAX_LINE_ITEMS,
+        TransferError::TooManyLineItems
+    );
+    require!(
+        !line_items.is_empty() && line_items.iter().all(|&item| item > 0),
+        TransferError::InvalidAmount
+    );
+
+    let invoice = &mut ctx.accounts.invoice;
+    invoice.issuer = ctx.accounts.issuer.key();
+    invoice.invoice_id = invoice_id;
+    invoice.line_items = line_items;
+    invoice.bump = ctx.bumps.invoice;
+    let total = invoice.total()?;
+
+    msg!("=== Invoice {} Issued ===", invoice_id);
+    msg!("Line items: {:?}", invoice.line_items);
+    msg!("Total: {} lamports", total);
+
+    Ok(())
+}
+
+/// Pay an invoice in full with a single exact transfer
+pub fn handle_pay_invoice(ctx: Context<PayInvoice>, amount: u64) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.payer.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.issuer)?;
+    let invoice = &ctx.accounts.invoice;
+    require!(!invoice.paid, TransferError::InvoiceAlreadyPaid);
+
+    let total = invoice.total()?;
+    require!(amount >= total, TransferError::InvoiceUnderpaid);
+    require!(amount <= total, TransferError::InvoiceOverpaid);
+    require!(
+        ctx.accounts.payer.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.payer.to_account_info(),
+        &ctx.accounts.issuer.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.payer.key(),
+        &ctx.accounts.issuer.key(),
+        amount,
+    );
+
+    let invoice = &mut ctx.accounts.invoice;
+    invoice.paid = true;
+    invoice.payer = ctx.accounts.payer.key();
+
+    msg!("=== Invoice {} Paid ===", invoice.invoice_id);
+    msg!("Amount: {} lamports", amount);
+    msg!("To: {}", invoice.issuer);
+
+    Ok(())
+}
+
+/// Transfer SOL into the recipient's wSOL token account and sync it
+pub fn handle_transfer_as_wsol(ctx: Context<TransferAsWsol>, amount: u64) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+
+    let wsol = token::read_token_account(&ctx.accounts.recipient_token)?;
+    require!(
+        wsol.mint == token::NATIVE_MINT && wsol.owner == ctx.accounts.recipient.key(),
+        TransferError::InvalidTokenAccount
+    );
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.recipient_token.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.recipient_token.key(),
+        amount,
+    );
+    token::sync_native(&ctx.accounts.recipient_token)?;
+
+    msg!("=== SOL Transfer (as wSOL) ===");
+    msg!("Amount: {} lamports", amount);
+    msg!("To: {} ({})", ctx.accounts.recipient.key(), ctx.accounts.recipient_token.key());
+
+    Ok(())
+}
+
+/// Appoint the emergency council (once)
+pub fn handle_set_emergency_council(
+    ctx: Context<SetEmergencyCouncil>,
+    members: Vec<Pubkey>,
+    threshold: u8,
+) -> Result<()> {
+    ctx.accounts.state.record_config_change(Clock::get()?.unix_timestamp)?;
+    require!(
+        members.len() <= MAX_EMERGENCY_MEMBERS
+            && threshold > 0
+            && threshold as usize <= members.len(),
+        TransferError::InvalidConfig
+    );
+    require!(
+        members
+            .iter()
+            .enumerate()
+            .all(|(i, member)| !members[..i].contains(member)),
+        TransferError::InvalidConfig
+    );
+
+    let council = &mut ctx.accounts.council;
+    council.members = members;
+    council.threshold = threshold;
+    council.bump = ctx.bumps.council;
+
+    msg!(
+        "Emergency council set: {} of {} members",
+        threshold,
+        council.members.len()
+    );
+
+    Ok(())
+}
+
+/// Force-unpause transfers with signatures from the emergency council
+pub fn handle_emergency_unpause(ctx: Context<EmergencyUnpause>) -> Result<()> {
+    let council = &ctx.accounts.council;
+    let signers = council.count_signers(ctx.remaining_accounts);
+    require!(
+        signers >= council.threshold as usize,
+        TransferError::EmergencyThresholdNotMet
+    );
+
+    ctx.accounts.state.paused = false;
+
+    msg!("Transfers unpaused by {} emergency signers", signers);
+
+    Ok(())
+}
+
+/// Register the program notified of the caller's incoming notified transfers
+pub fn handle_set_notification_program(
+    ctx: Context<SetNotificationProgram>,
+    program: Pubkey,
+    best_effort: bool,
+) -> Result<()> {
+    let config = &mut ctx.accounts.notification_config;
+    config.recipient = ctx.accounts.recipient.key();
+    config.program = program;
+    config.best_effort = best_effort;
+    config.bump = ctx.bumps.notification_config;
+
+    msg!("Recipient {} notifies {} (best effort: {})", config.recipient, program, best_effort);
+
+    Ok(())
+}
+
+/// Transfer SOL, then notify the recipient's registered program through CPI
+pub fn handle_transfer_with_notification(
+    ctx: Context<TransferWithNotification>,
+    amount: u64,
+) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.recipient.key(),
+        amount,
+    );
+
+    msg!("=== SOL Transfer (notified) ===");
+    msg!("Amount: {} lamports", amount);
+    msg!("To: {}", ctx.accounts.recipient.key());
+
+    // Address is already checked against the recipient by the seeds constraint
+    let info = ctx.accounts.notification_config.to_account_info();
+    if info.owner != &crate::ID || info.data_is_empty() {
+        msg!("No notification program registered");
+        return Ok(());
+    }
+    let config = NotificationConfig::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+
+    // Only problems detectable before the CPI can be skipped: a failing CPI
+    // always aborts the whole transaction
+    let program = &ctx.accounts.notification_program;
+    if program.key() != config.program || !program.executable {
+        require!(config.best_effort, TransferError::InvalidNotificationProgram);
+        msg!("Notification skipped: {} is not the registered program", program.key());
+        return Ok(());
+    }
+
+    let payload = format!("transfer {} {}", ctx.accounts.recipient.key(), amount);
+    let ix = Instruction {
+        program_id: config.program,
+        accounts: vec![],
+        data: payload.into_bytes(),
+    };
+    invoke(&ix, &[program.to_account_info()])?;
+
+    msg!("Notified {}", config.program);
+
+    Ok(())
+}
+
+/// Transfer SOL and push its amount into the moving-average window
+pub fn handle_transfer_with_average(ctx: Context<TransferWithAverage>, amount: u64) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.recipient.key(),
+        amount,
+    );
+
+    let window = &mut ctx.accounts.moving_average;
+    window.bump = ctx.bumps.moving_average;
+    window.record_transfer(amount);
+
+    msg!("=== SOL Transfer (averaged) ===");
+    msg!("Amount: {} lamports", amount);
+    msg!("To: {}", ctx.accounts.recipient.key());
+    msg!("Moving average: {} lamports over {}", window.average(), window.count);
+
+    Ok(())
+}
+
+/// Get the average of the recent transfers in the window (view function)
+pub fn handle_get_moving_average(ctx: Context<GetMovingAverage>) -> Result<u64> {
+    let average = ctx.accounts.moving_average.average();
+    msg!("Moving average: {} lamports", average);
+    Ok(average)
+}
+
+/// Return the sender's average gap between transfers (in seconds)
+///
+/// Returns 0 until the sender has made at least two transfers.
+pub fn handle_get_average_interval(ctx: Context<GetAverageInterval>) -> Result<u64> {
+    let stats = &ctx.accounts.sender_stats;
+    let average = stats.average_interval();
+    msg!(
+        "Average interval: {} seconds over {} gaps",
+        average,
+        stats.interval_count
+    );
+    Ok(average)
+}
+
+/// Escrow SOL for a recipient, cancellable by the sender for `grace_secs`
+pub fn handle_transfer_with_grace(
+    ctx: Context<TransferWithGrace>,
+    amount: u64,
+    grace_secs: u32,
+) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(grace_secs > 0, TransferError::InvalidTimeout);
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    let grace_ends_at = Clock::get()?.unix_timestamp + grace_secs as i64;
+
+    let grace = &mut ctx.accounts.grace_transfer;
+    grace.sender = ctx.accounts.sender.key();
+    grace.recipient = ctx.accounts.recipient.key();
+    grace.amount = amount;
+    grace.grace_ends_at = grace_ends_at;
+    grace.bump = ctx.bumps.grace_transfer;
+
+    // Move the escrowed amount on top of the rent already held by the PDA
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.grace_transfer.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.grace_transfer.key(),
+        amount,
+    );
+
+    msg!("=== Grace Transfer ===");
+    msg!("Escrowed: {} lamports", amount);
+    msg!("Recipient: {}", ctx.accounts.recipient.key());
+    msg!("Cancellable until: {}", grace_ends_at);
+
+    Ok(())
+}
+
+/// Cancel a grace transfer while its grace period is running
+pub fn handle_cancel_grace(ctx: Context<CancelGrace>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        now < ctx.accounts.grace_transfer.grace_ends_at,
+        TransferError::GracePeriodOver
+    );
+
+    // Returned explicitly so a small amount isn't mistaken for dust
+    move_lamports(
+        &ctx.accounts.grace_transfer.to_account_info(),
+        &ctx.accounts.sender.to_account_info(),
+        ctx.accounts.grace_transfer.amount,
+    )?;
+
+    msg!("=== Grace Transfer Cancelled ===");
+    msg!("Returned: {} lamports", ctx.accounts.grace_transfer.amount);
+
+    // Remaining rent goes back to the sender, any dust to the treasury
+    close_account(
+        &ctx.accounts.grace_transfer,
+        &ctx.accounts.sender,
+        &ctx.accounts.fee_config,
+        &ctx.accounts.treasury,
+    )
+}
+
+/// Claim a grace transfer once its grace period has ended
+pub fn handle_claim_grace(ctx: Context<ClaimGrace>) -> Result<()> {
+    let grace = &ctx.accounts.grace_transfer;
+    let now = Clock::get()?.unix_timestamp;
+    require!(now >= grace.grace_ends_at, TransferError::EscrowNotExpired);
+
+    move_lamports(
+        &grace.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        grace.amount,
+    )?;
+
+    msg!("=== Grace Transfer Claimed ===");
+    msg!("Claimed: {} lamports", grace.amount);
+    msg!("By: {}", ctx.accounts.recipient.key());
+
+    // Remaining rent goes back to the sender, any dust to the treasury
+    close_account(
+        &ctx.accounts.grace_transfer,
+        &ctx.accounts.sender,
+        &ctx.accounts.fee_config,
+        &ctx.accounts.treasury,
+    )
+}
+
+/// Pay each recipient in `remaining_accounts` the matching entry of `amounts`
+///
+/// With `summarize` a single `BatchSummary` replaces the per-recipient
+/// `TransferExecuted` events.
+pub fn handle_transfer_sol_batch<'info>(
+    ctx: Context<'_, '_, '_, 'info, TransferSolBatch<'info>>,
+    amounts: Vec<u64>,
+    summarize: bool,
+) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        u16::try_from(amounts.len()).unwrap_or(u16::MAX),
+        &ctx.accounts.instructions,
+    )?;
+    require!(!amounts.is_empty(), TransferError::InvalidAmount);
+    require!(
+        amounts.len() == ctx.remaining_accounts.len(),
+        TransferError::InvalidRecipient
+    );
+
+    let sender = ctx.accounts.sender.to_account_info();
+    let timestamp = Clock::get()?.unix_timestamp;
+    let mut total_amount: u64 = 0;
+
+    for (recipient, &amount) in ctx.remaining_accounts.iter().zip(amounts.iter()) {
+        require!(amount > 0, TransferError::InvalidAmount);
+        require!(sender.lamports() >= amount, TransferError::InsufficientFunds);
+        ctx.accounts.state.require_not_program(recipient)?;
+
+        system_transfer(&ctx.accounts.system_program, &sender, recipient, amount)?;
+        ctx.accounts.state.record_transfer(&sender.key(), &recipient.key(), amount);
+        total_amount = total_amount
+            .checked_add(amount)
+            .ok_or(TransferError::MathOverflow)?;
+
+        if !summarize {
+            emit!(TransferExecuted {
+                sender: sender.key(),
+                recipient: recipient.key(),
+                amount,
+                sender_balance_after: sender.lamports(),
+                timestamp,
+            });
+        }
+    }
+
+    if summarize {
+        let keys: Vec<&[u8]> = ctx.remaining_accounts.iter().map(|a| a.key.as_ref()).collect();
+        emit!(BatchSummary {
+            sender: sender.key(),
+            recipient_count: amounts.len() as u32,
+            total_amount,
+            recipients_hash: hashv(&keys).to_bytes(),
+            timestamp,
+        });
+    }
+
+    msg!("=== SOL Batch Transfer ===");
+    msg!("Recipients: {}", amounts.len());
+    msg!("Total: {} lamports", total_amount);
+
+    Ok(())
+}
+
+/// Limit how many rate-limited transfers the caller receives per window
+pub fn handle_set_recipient_rate(
+    ctx: Context<SetRecipientRate>,
+    max_per_window: u32,
+    window_secs: u32,
+) -> Result<()> {
+    require!(
+        max_per_window == 0 || window_secs > 0,
+        TransferError::InvalidConfig
+    );
+
+    let rate = &mut ctx.accounts.recipient_rate;
+    rate.recipient = ctx.accounts.recipient.key();
+    rate.max_per_window = max_per_window;
+    rate.window_secs = window_secs;
+    rate.bump = ctx.bumps.recipient_rate;
+
+    msg!(
+        "Recipient {} accepts {} transfers per {} seconds",
+        rate.recipient,
+        max_per_window,
+        window_secs
+    );
+
+    Ok(())
+}
+
+/// Transfer SOL, counted against the recipient's rate limit
+pub fn handle_transfer_rate_limited(ctx: Context<TransferRateLimited>, amount: u64) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    let rate = &mut ctx.accounts.recipient_rate;
+    rate.recipient = ctx.accounts.recipient.key();
+    rate.bump = ctx.bumps.recipient_rate;
+    rate.record_transfer(Clock::get()?.unix_timestamp)?;
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.recipient.key(),
+        amount,
+    );
+
+    msg!("=== SOL Transfer (recipient rate-limited) ===");
+    msg!("Amount: {} lamports", amount);
+    msg!("To: {}", ctx.accounts.recipient.key());
+    msg!("Received this window: {}", ctx.accounts.recipient_rate.count);
+
+    Ok(())
+}
+
+/// Add SOL to the share pool and mint proportional shares
+pub fn handle_contribute(ctx: Context<Contribute>, amount: u64) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.contributor.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(
+        ctx.accounts.contributor.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    let minted = ctx.accounts.pool.shares_for(amount)?;
+    require!(minted > 0, TransferError::InvalidAmount);
+    let total_shares = ctx
+        .accounts
+        .pool
+        .total_shares
+        .checked_add(minted)
+        .ok_or(TransferError::MathOverflow)?;
+    let value = ctx
+        .accounts
+        .pool
+        .value
+        .checked_add(amount)
+        .ok_or(TransferError::MathOverflow)?;
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.contributor.to_account_info(),
+        &ctx.accounts.pool.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.contributor.key(),
+        &ctx.accounts.pool.key(),
+        amount,
+    );
+
+    let pool = &mut ctx.accounts.pool;
+    pool.total_shares = total_shares;
+    pool.value = value;
+    pool.bump = ctx.bumps.pool;
+    let shares = &mut ctx.accounts.shares;
+    shares.owner = ctx.accounts.contributor.key();
+    shares.shares += minted;
+    shares.bump = ctx.bumps.shares;
+
+    msg!("=== Share Contribution ===");
+    msg!("Contributed: {} lamports for {} shares", amount, minted);
+    msg!("Total shares: {}", total_shares);
+
+    Ok(())
+}
+
+/// Redeem shares for their proportional part of the share pool
+pub fn handle_redeem_shares(ctx: Context<RedeemShares>, shares: u64) -> Result<()> {
+    require!(shares > 0, TransferError::InvalidAmount);
+    require!(
+        shares <= ctx.accounts.shares.shares,
+        TransferError::InsufficientFunds
+    );
+
+    let payout = ctx.accounts.pool.lamports_for(shares)?;
+
+    ctx.accounts.pool.total_shares -= shares;
+    ctx.accounts.pool.value -= payout;
+    ctx.accounts.shares.shares -= shares;
+
+    move_lamports(
+        &ctx.accounts.pool.to_account_info(),
+        &ctx.accounts.owner.to_account_info(),
+        payout,
+    )?;
+
+    msg!("=== Share Redemption ===");
+    msg!("Redeemed: {} shares for {} lamports", shares, payout);
+    msg!("Total shares: {}", ctx.accounts.pool.total_shares);
+
+    Ok(())
+}
+
+/// Transfer SOL and set aside `withhold_bps` of the amount in the sender's withholding PDA
+///
+/// The recipient receives the full `amount`; the withheld share is paid by
+/// the sender on top of it.
+pub fn handle_transfer_withhold(
+    ctx: Context<TransferWithhold>,
+    amount: u64,
+    withhold_bps: u16,
+) -> Result<()> {
+    // The payment and the withheld share are separate transfers
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        2,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(
+        withhold_bps <= MAX_WITHHOLD_BPS,
+        TransferError::WithholdingTooHigh
+    );
+
+    let withheld = bps_of(amount, withhold_bps);
+    let total = amount
+        .checked_add(withheld)
+        .ok_or(TransferError::MathOverflow)?;
+    require!(
+        ctx.accounts.sender.lamports() >= total,
+        TransferError::InsufficientFunds
+    );
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.recipient.key(),
+        amount,
+    );
+    if withheld > 0 {
+        system_transfer(
+            &ctx.accounts.system_program,
+            &ctx.accounts.sender.to_account_info(),
+            &ctx.accounts.withholding.to_account_info(),
+            withheld,
+        )?;
+        ctx.accounts.state.record_transfer(
+            &ctx.accounts.sender.key(),
+            &ctx.accounts.withholding.key(),
+            withheld,
+        );
+    }
+
+    let withholding = &mut ctx.accounts.withholding;
+    withholding.owner = ctx.accounts.sender.key();
+    withholding.balance += withheld;
+    withholding.total_withheld = withholding.total_withheld.saturating_add(withheld);
+    withholding.bump = ctx.bumps.withholding;
+
+    msg!("=== SOL Transfer (withholding) ===");
+    msg!("Amount: {} lamports", amount);
+    msg!("To: {}", ctx.accounts.recipient.key());
+    msg!("Withheld: {} lamports ({} bps)", withheld, withhold_bps);
+
+    Ok(())
+}
+
+/// Withdraw SOL the owner previously withheld
+pub fn handle_withdraw_withholding(ctx: Context<WithdrawWithholding>, amount: u64) -> Result<()> {
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(
+        amount <= ctx.accounts.withholding.balance,
+        TransferError::InsufficientFunds
+    );
+
+    ctx.accounts.withholding.balance -= amount;
+    move_lamports(
+        &ctx.accounts.withholding.to_account_info(),
+        &ctx.accounts.owner.to_account_info(),
+        amount,
+    )?;
+
+    msg!("=== Withholding Withdrawal ===");
+    msg!("Withdrawn: {} lamports", amount);
+    msg!("Remaining: {} lamports", ctx.accounts.withholding.balance);
+
+    Ok(())
+}
+
+/// Create a spending cap shared by a group of member senders
+pub fn handle_create_group_budget(
+    ctx: Context<CreateGroupBudget>,
+    group_id: u64,
+    members: Vec<Pubkey>,
+    cap: u64,
+) -> Result<()> {
+    require!(
+        !members.is_empty() && members.len() <= MAX_GROUP_MEMBERS,
+        TransferError::InvalidConfig
+    );
+
+    let budget = &mut ctx.accounts.group_budget;
+    budget.admin = ctx.accounts.admin.key();
+    budget.group_id = group_id;
+    budget.members = members;
+    budget.cap = cap;
+    budget.spent = 0;
+    budget.bump = ctx.bumps.group_budget;
+
+    msg!(
+        "Group budget {} created: {} lamports across {} members",
+        group_id,
+        cap,
+        budget.members.len()
+    );
+
+    Ok(())
+}
+
+/// Transfer SOL as a group member, charged against the group's shared cap
+pub fn handle_transfer_from_group(ctx: Context<TransferFromGroup>, amount: u64) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    ctx.accounts
+        .group_budget
+        .spend(&ctx.accounts.sender.key(), amount)?;
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.recipient.key(),
+        amount,
+    );
+
+    let budget = &ctx.accounts.group_budget;
+    msg!("=== SOL Transfer (group budget) ===");
+    msg!("Amount: {} lamports", amount);
+    msg!("To: {}", ctx.accounts.recipient.key());
+    msg!("Group spent: {} of {} lamports", budget.spent, budget.cap);
+
+    Ok(())
+}
+
+/// Transfer SOL into the daily window, snapshotting the previous day on rollover
+pub fn handle_transfer_with_daily_close(
+    ctx: Context<TransferWithDailyClose>,
+    amount: u64,
+) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    let today = utc_day(Clock::get()?.unix_timestamp);
+    let window = &ctx.accounts.daily_window;
+    if window.day != today && window.count > 0 {
+        // The snapshot is created here rather than by a constraint, so that
+        // only transfers that actually close a day pay for one
+        let day_bytes = window.day.to_le_bytes();
+        let bump = [ctx.bumps.snapshot];
+        let signer_seeds: &[&[u8]] = &[b"daily_snapshot", &day_bytes, &bump];
+        create_pda_account(
+            &ctx.accounts.system_program,
+            &ctx.accounts.sender.to_account_info(),
+            &ctx.accounts.snapshot.to_account_info(),
+            DailySnapshot::SIZE,
+            signer_seeds,
+        )?;
+
+        let snapshot = DailySnapshot {
+            day: window.day,
+            volume: window.volume,
+            count: window.count,
+            bump: ctx.bumps.snapshot,
+        };
+        snapshot.try_serialize(&mut &mut ctx.accounts.snapshot.try_borrow_mut_data()?[..])?;
+        msg!(
+            "Day {} closed: {} transfers, {} lamports",
+            snapshot.day,
+            snapshot.count,
+            snapshot.volume
+        );
+    }
+
+    let window = &mut ctx.accounts.daily_window;
+    if window.day != today {
+        window.day = today;
+        window.volume = 0;
+        window.count = 0;
+    }
+    window.bump = ctx.bumps.daily_window;
+    window.record_transfer(amount);
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.recipient.key(),
+        amount,
+    );
+
+    msg!("=== SOL Transfer (daily close) ===");
+    msg!("Amount: {} lamports", amount);
+    msg!("To: {}", ctx.accounts.recipient.key());
+
+    Ok(())
+}
+
+/// Message a recipient signs to acknowledge a transfer
+///
+/// It binds the acknowledgement to this program, both parties, the amount
+/// and the recipient's current acknowledgement nonce, so that it can be used
+/// only once, by one sender, for one amount.
+pub fn ack_message(sender: &Pubkey, recipient: &Pubkey, amount: u64, nonce: u64) -> Vec<u8> {
+    [
+        crate::ID.as_ref(),
+        sender.as_ref(),
+        recipient.as_ref(),
+        &amount.to_le_bytes(),
+        &nonce.to_le_bytes(),
+    ]
+    .concat()
+}
+
+/// Transfer SOL once the recipient's ed25519 acknowledgement is verified, consuming its nonce
+pub fn handle_transfer_signed_ack(
+    ctx: Context<TransferSignedAck>,
+    amount: u64,
+    signature: [u8; 64],
+    recipient_pubkey: Pubkey,
+) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require_keys_eq!(
+        ctx.accounts.recipient.key(),
+        recipient_pubkey,
+        TransferError::InvalidRecipient
+    );
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+    let nonce = ctx.accounts.ack_nonce.nonce;
+    ed25519::require_verified(
+        &ctx.accounts.instructions,
+        &recipient_pubkey,
+        &signature,
+        &ack_message(&ctx.accounts.sender.key(), &recipient_pubkey, amount, nonce),
+    )?;
+
+    let ack_nonce = &mut ctx.accounts.ack_nonce;
+    ack_nonce.recipient = recipient_pubkey;
+    ack_nonce.nonce = nonce.checked_add(1).ok_or(TransferError::MathOverflow)?;
+    ack_nonce.bump = ctx.bumps.ack_nonce;
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.recipient.key(),
+        amount,
+    );
+
+    msg!("=== SOL Transfer (signed acknowledgement) ===");
+    msg!("Amount: {} lamports", amount);
+    msg!("To: {}", recipient_pubkey);
+    msg!("Acknowledgement nonce: {}", nonce);
+
+    Ok(())
+}
+
+/// Hold SOL for a recipient for the configured challenge period
+pub fn handle_transfer_reversible(ctx: Context<TransferReversible>, amount: u64) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    let challenge_period_secs = ctx.accounts.state.challenge_period_secs;
+    require!(challenge_period_secs > 0, TransferError::InvalidConfig);
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    let challenge_ends_at = Clock::get()?.unix_timestamp + challenge_period_secs as i64;
+
+    let reversible = &mut ctx.accounts.reversible_transfer;
+    reversible.sender = ctx.accounts.sender.key();
+    reversible.recipient = ctx.accounts.recipient.key();
+    reversible.amount = amount;
+    reversible.challenge_ends_at = challenge_ends_at;
+    reversible.bump = ctx.bumps.reversible_transfer;
+
+    // Move the held amount on top of the rent already held by the PDA
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.reversible_transfer.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.reversible_transfer.key(),
+        amount,
+    );
+
+    msg!("=== Reversible Transfer ===");
+    msg!("Held: {} lamports", amount);
+    msg!("Recipient: {}", ctx.accounts.recipient.key());
+    msg!("Reversible until: {}", challenge_ends_at);
+
+    Ok(())
+}
+
+/// Reverse a transfer while its challenge period is running
+pub fn handle_reverse_transfer(ctx: Context<ReverseTransfer>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        now < ctx.accounts.reversible_transfer.challenge_ends_at,
+        TransferError::ChallengePeriodOver
+    );
+
+    // Returned explicitly so a small amount isn't mistaken for dust
+    move_lamports(
+        &ctx.accounts.reversible_transfer.to_account_info(),
+        &ctx.accounts.sender.to_account_info(),
+        ctx.accounts.reversible_transfer.amount,
+    )?;
+
+    msg!("=== Transfer Reversed ===");
+    msg!("Returned: {} lamports", ctx.accounts.reversible_transfer.amount);
+
+    // Remaining rent goes back to the sender, any dust to the treasury
+    close_account(
+        &ctx.accounts.reversible_transfer,
+        &ctx.accounts.sender,
+        &ctx.accounts.fee_config,
+        &ctx.accounts.treasury,
+    )
+}
+
+/// Finalize a reversible transfer once its challenge period has ended
+pub fn handle_finalize_transfer(ctx: Context<FinalizeTransfer>) -> Result<()> {
+    let reversible = &ctx.accounts.reversible_transfer;
+    let now = Clock::get()?.unix_timestamp;
+    require!(now >= reversible.challenge_ends_at, TransferError::EscrowNotExpired);
+
+    move_lamports(
+        &reversible.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        reversible.amount,
+    )?;
+
+    msg!("=== Reversible Transfer Finalized ===");
+    msg!("Paid: {} lamports", reversible.amount);
+    msg!("To: {}", ctx.accounts.recipient.key());
+
+    // Remaining rent goes back to the sender, any dust to the treasury
+    close_account(
+        &ctx.accounts.reversible_transfer,
+        &ctx.accounts.sender,
+        &ctx.accounts.fee_config,
+        &ctx.accounts.treasury,
+    )
+}
+
+/// Collect `amount_each` from every source in `remaining_accounts` into one recipient
+pub fn handle_collect_sol<'info>(
+    ctx: Context<'_, '_, '_, 'info, CollectSol<'info>>,
+    amount_each: u64,
+) -> Result<()> {
+    require!(amount_each > 0, TransferError::InvalidAmount);
+    // Each source is followed by its guardian setting PDA
+    require!(
+        !ctx.remaining_accounts.is_empty() && ctx.remaining_accounts.len().is_multiple_of(2),
+        TransferError::InvalidAmount
+    );
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+
+    // Validate every source up front so the error names the real problem
+    let sources: Vec<Pubkey> = ctx
+        .remaining_accounts
+        .iter()
+        .step_by(2)
+        .map(|source| source.key())
+        .collect();
+    for pair in ctx.remaining_accounts.chunks_exact(2) {
+        let (source, guardian_config) = (&pair[0], &pair[1]);
+        require!(source.is_signer, TransferError::Unauthorized);
+        // Any other account would hide the source's guardian
+        let (expected, _) =
+            Pubkey::find_program_address(&[b"guardian", source.key.as_ref()], &crate::ID);
+        require_keys_eq!(guardian_config.key(), expected, TransferError::GuardianRequired);
+        open_transfer(
+            &mut ctx.accounts.state,
+            source.key,
+            &sources,
+            false,
+            has_guardian(guardian_config),
+            1,
+            &ctx.accounts.instructions,
+        )?;
+        require!(
+            source.lamports() >= amount_each,
+            TransferError::InsufficientFunds
+        );
+    }
+
+    let recipient = ctx.accounts.recipient.to_account_info();
+    for source in ctx.remaining_accounts.iter().step_by(2) {
+        system_transfer(&ctx.accounts.system_program, source, &recipient, amount_each)?;
+        ctx.accounts.state.record_transfer(source.key, recipient.key, amount_each);
+    }
+
+    let total = amount_each
+        .checked_mul(sources.len() as u64)
+        .ok_or(TransferError::MathOverflow)?;
+
+    msg!("=== SOL Collection ===");
+    msg!("Sources: {} x {} lamports", sources.len(), amount_each);
+    msg!("Total: {} lamports", total);
+    msg!("To: {}", recipient.key());
+
+    Ok(())
+}
+
+/// Transfer SOL counted against the sender's daily cap
+///
+/// Transfers to a priority recipient bypass the cap and are not counted.
+pub fn handle_transfer_sol_daily_capped(
+    ctx: Context<TransferSolDailyCapped>,
+    amount: u64,
+) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    // The priority marker is a PDA; it only counts once this program owns it
+    let priority = &ctx.accounts.priority_recipient;
+    let is_priority = priority.owner == &crate::ID && !priority.data_is_empty();
+
+    let spend = &mut ctx.accounts.sender_daily;
+    spend.sender = ctx.accounts.sender.key();
+    spend.bump = ctx.bumps.sender_daily;
+    if !is_priority {
+        spend.spend(
+            amount,
+            utc_day(Clock::get()?.unix_timestamp),
+            ctx.accounts.state.sender_daily_cap,
+        )?;
+    }
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.recipient.key(),
+        amount,
+    );
+
+    msg!("=== SOL Transfer (daily capped) ===");
+    msg!("Amount: {} lamports", amount);
+    msg!("To: {}", ctx.accounts.recipient.key());
+    if is_priority {
+        msg!("Priority recipient: daily cap bypassed");
+    } else {
+        msg!("Spent today: {} lamports", ctx.accounts.sender_daily.spent);
+    }
+
+    Ok(())
+}
+
+/// Transfer SOL and add the recipient to the sender's bloom filter
+pub fn handle_transfer_with_bloom(ctx: Context<TransferWithBloom>, amount: u64) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.recipient.key(),
+        amount,
+    );
+
+    let bloom = &mut ctx.accounts.bloom;
+    bloom.sender = ctx.accounts.sender.key();
+    bloom.bump = ctx.bumps.bloom;
+    bloom.insert(&ctx.accounts.recipient.key());
+
+    msg!("=== SOL Transfer (bloom) ===");
+    msg!("Amount: {} lamports", amount);
+    msg!("To: {}", ctx.accounts.recipient.key());
+
+    Ok(())
+}
+
+/// Return whether the sender may have paid `recipient` before
+///
+/// `false` is exact; `true` may be a false positive.
+pub fn handle_possibly_paid(ctx: Context<PossiblyPaid>, recipient: Pubkey) -> Result<bool> {
+    let possibly_paid = ctx.accounts.bloom.possibly_contains(&recipient);
+    msg!("Possibly paid {}: {}", recipient, possibly_paid);
+    Ok(possibly_paid)
+}
+
+/// Create the transfer journal with no entries
+pub fn handle_init_journal(ctx: Context<InitJournal>) -> Result<()> {
+    let journal = &mut ctx.accounts.journal;
+    journal.cursor = 0;
+    journal.bump = ctx.bumps.journal;
+
+    msg!("Journal created: {}", journal.key());
+
+    Ok(())
+}
+
+/// Transfer SOL and append it to the journal
+///
+/// The `realloc` constraint has already grown the journal by one entry, with
+/// the sender paying the extra rent.
+pub fn handle_transfer_with_journal(ctx: Context<TransferWithJournal>, amount: u64) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(
+        ctx.accounts.sender.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+    require!(
+        ctx.accounts.journal.cursor < Journal::MAX_ENTRIES,
+        TransferError::JournalFull
+    );
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.recipient.key(),
+        amount,
+    );
+
+    let index = ctx.accounts.journal.cursor;
+    let entry = JournalEntry {
+        sender: ctx.accounts.sender.key(),
+        recipient: ctx.accounts.recipient.key(),
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    };
+    {
+        let journal = ctx.accounts.journal.to_account_info();
+        let mut data = journal.try_borrow_mut_data()?;
+        entry.serialize(&mut &mut data[Journal::entry_range(index)])?;
+    }
+    ctx.accounts.journal.cursor = index + 1;
+
+    msg!("=== SOL Transfer (journaled) ===");
+    msg!("Amount: {} lamports", amount);
+    msg!("To: {}", ctx.accounts.recipient.key());
+    msg!("Journal entry: {}", index);
+
+    Ok(())
+}
+
+/// Read the journal entry at `index` (view function)
+pub fn handle_read_journal_entry(
+    ctx: Context<ReadJournalEntry>,
+    index: u64,
+) -> Result<JournalEntry> {
+    require!(
+        index < ctx.accounts.journal.cursor,
+        TransferError::JournalIndexOutOfRange
+    );
+
+    let journal = ctx.accounts.journal.to_account_info();
+    let data = journal.try_borrow_data()?;
+    let entry = JournalEntry::deserialize(&mut &data[Journal::entry_range(index)])?;
+    msg!(
+        "Journal entry {}: {} lamports from {} to {}",
+        index,
+        entry.amount,
+        entry.sender,
+        entry.recipient
+    );
+    Ok(entry)
+}
+
+/// Transfer SOL, rejecting unconfirmed transfers of too large a share of the sender's balance
+pub fn handle_transfer_sol_guarded(
+    ctx: Context<TransferSolGuarded>,
+    amount: u64,
+    confirm: bool,
+) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.sender.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    ctx.accounts.state.require_not_program(&ctx.accounts.recipient)?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    let balance = ctx.accounts.sender.lamports();
+    require!(balance >= amount, TransferError::InsufficientFunds);
+    ctx.accounts.state.require_balance_fraction(amount, balance, confirm)?;
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.sender.to_account_info(),
+        &ctx.accounts.recipient.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.sender.key(),
+        &ctx.accounts.recipient.key(),
+        amount,
+    );
+
+    msg!("=== SOL Transfer (balance guarded) ===");
+    msg!("Amount: {} lamports of {}", amount, balance);
+    msg!("To: {}", ctx.accounts.recipient.key());
+    if confirm {
+        msg!("Confirmed by sender");
+    }
+
+    Ok(())
+}
+
+/// Deposit SOL into the caller's system-owned vault PDA
+pub fn handle_deposit_to_vault(ctx: Context<DepositToVault>, amount: u64) -> Result<()> {
+    open_transfer(
+        &mut ctx.accounts.state,
+        &ctx.accounts.owner.key(),
+        &[],
+        is_pause_exempt(&ctx.accounts.pause_exempt),
+        has_guardian(&ctx.accounts.guardian_config),
+        1,
+        &ctx.accounts.instructions,
+    )?;
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(
+        ctx.accounts.owner.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    system_transfer(
+        &ctx.accounts.system_program,
+        &ctx.accounts.owner.to_account_info(),
+        &ctx.accounts.vault.to_account_info(),
+        amount,
+    )?;
+    ctx.accounts.state.record_transfer(
+        &ctx.accounts.owner.key(),
+        &ctx.accounts.vault.key(),
+        amount,
+    );
+
+    msg!("=== Vault Deposit ===");
+    msg!("Deposited: {} lamports", amount);
+    msg!("Vault balance: {} lamports", ctx.accounts.vault.lamports());
+
+    Ok(())
+}
+
+/// Withdraw SOL from the caller's vault, signing for it with the supplied bump
+///
+/// The bump must be the canonical one; any other bump that happens to derive
+/// a valid PDA names a different vault and is rejected.
+pub fn handle_withdraw_from_vault(
+    ctx: Context<WithdrawFromVault>,
+    amount: u64,
+    bump: u8,
+) -> Result<()> {
+    require!(amount > 0, TransferError::InvalidAmount);
+    require!(
+        ctx.accounts.vault.lamports() >= amount,
+        TransferError::InsufficientFunds
+    );
+
+    let owner_key = ctx.accounts.owner.key();
+    require_canonical_bump(&[b"sol_vault", owner_key.as_ref()], bump)?;
+    let bump = [bump];
+    let signer_seeds: &[&[u8]] = &[b"sol_vault", owner_key.as_ref(), &bump];
+    system_program::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.vault.to_account_info(),
+                to: ctx.accounts.owner.to_account_info(),
+            },
+            &[signer_seeds],
+        ),
+        amount,
+    )?;
+
+    msg!("=== Vault Withdrawal ===");
+    msg!("Withdrawn: {} lamports", amount);
+    msg!("Vault balance: {} lamports", ctx.accounts.vault.lamports());
+
+    Ok(())
+}
+
+/// Require `bump` to be the canonical bump for `seeds`, returning the PDA
+///
+/// Anchor's `bump = <value>` constraint accepts any bump that derives an
+/// off-curve address, so every bump used to sign for a PDA goes through here
+/// to rule out bump-seed grinding.
+pub(crate) fn require_canonical_bump(seeds: &[&[u8]], bump: u8) -> Result<Pubkey> {
+    let (address, canonical) = Pubkey::find_program_address(seeds, &crate::ID);
+    require!(bump == canonical, TransferError::NonCanonicalBump);
+    Ok(address)
+}
+
+/// Transfer lamports out of a system-owned account through the system program
+pub(crate) fn system_transfer<'info>(
+    system_program: &Program<'info, System>,
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    let cpi_context = CpiContext::new(
+        system_program.to_account_info(),
+        system_program::Transfer {
+            from: from.clone(),
+            to: to.clone(),
+        },
+    );
+    system_program::transfer(cpi_context, amount)
+}
+
+/// Create a program-owned PDA of `space` bytes, funded by `payer`
+///
+/// `create_account` fails on an address that already holds lamports, and
+/// anyone can send lamports to a PDA before it is created. A pre-funded PDA
+/// is instead topped up to the rent-exempt minimum, then allocated and
+/// assigned, as Anchor's `init` does.
+pub(crate) fn create_pda_account<'info>(
+    system_program: &Program<'info, System>,
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    space: usize,
+    signer_seeds: &[&[u8]],
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space);
+    let program = system_program.to_account_info();
+    if account.lamports() == 0 {
+        return system_program::create_account(
+            CpiContext::new_with_signer(
+                program,
+                system_program::CreateAccount {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+                &[signer_seeds],
+            ),
+            rent,
+            space as u64,
+            &crate::ID,
+        );
+    }
+
+    let top_up = rent.saturating_sub(account.lamports());
+    if top_up > 0 {
+        system_transfer(system_program, payer, account, top_up)?;
+    }
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            program.clone(),
+            system_program::Allocate {
+                account_to_allocate: account.clone(),
+            },
+            &[signer_seeds],
+        ),
+        space as u64,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            program,
+            system_program::Assign {
+                account_to_assign: account.clone(),
+            },
+            &[signer_seeds],
+        ),
+        &crate::ID,
+    )
+}
+
+/// Close a program-owned account, returning its rent to `receiver`
+///
+/// Lamports above the rent-exempt minimum that are too few to keep an account
+/// alive on their own are dust and go to the treasury instead, as long as a
+/// fee configuration was passed and the treasury is rent-exempt after
+/// receiving them. Otherwise the whole balance goes to `receiver`.
+pub(crate) fn close_account<'info>(
+    account: &(impl AccountsClose<'info> + ToAccountInfo<'info>),
+    receiver: &AccountInfo<'info>,
+    fee_config: &Option<Account<'info, FeeConfig>>,
+    treasury: &Option<AccountInfo<'info>>,
+) -> Result<()> {
+    let info = account.to_account_info();
+    let rent = Rent::get()?;
+    let residual = info
+        .lamports()
+        .saturating_sub(rent.minimum_balance(info.data_len()));
+    let is_dust = residual > 0 && residual < rent.minimum_balance(0);
+    // The treasury is only verified when the fee configuration is passed
+    if let (Some(_), Some(treasury)) = (fee_config, treasury) {
+        if is_dust && treasury.lamports() + residual >= rent.minimum_balance(treasury.data_len()) {
+            move_lamports(&info, treasury, residual)?;
+            msg!("Dust of {} lamports sent to the treasury", residual);
+        }
+    }
+    account.close(receiver.clone())
+}
+
+/// Move lamports out of a program-owned account
+///
+/// The system program can only debit system-owned accounts, so PDAs owned by
+/// this program are debited directly.
+pub(crate) fn move_lamports(from: &AccountInfo, to: &AccountInfo, amount: u64) -> Result<()> {
+    **from.try_borrow_mut_lamports()? = from
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(TransferError::InsufficientFunds)?;
+    **to.try_borrow_mut_lamports()? = to
+        .lamports()
+        .checked_add(amount)
+        .ok_or(TransferError::InvalidAmount)?;
+    Ok(())
+}
+
+ 
+// Account Contexts
+ 
+
+/// Accounts required for program initialization
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    /// Program state account (PDA)
+    #[account(
+        init,
+        payer = authority,
+        space = ProgramState::SIZE,
+        seeds = [b"program_state"],
+        bump
+    )]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Authority initializing the program (pays for state account)
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to migrate the program state layout
+#[derive(Accounts)]
+pub struct MigrateState<'info> {
+    /// CHECK: Program state PDA; it may still have the v1 layout, so it is
+    /// validated in the handler
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: UncheckedAccount<'info>,
+    
+    /// Program authority (must sign, pays for the extra space)
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    /// System program for the rent top-up
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for SOL transfer
+#[derive(Accounts)]
+pub struct TransferSol<'info> {
+    /// Program state account recording totals and the pause flag (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// The sender account (must sign the transaction)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// The recipient account (receives SOL)
+    /// CHECK: This account is only used to receive SOL, no validation needed
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for balance query
+#[derive(Accounts)]
+pub struct GetBalance<'info> {
+    /// CHECK: Any account can have its balance queried
+    pub account: AccountInfo<'info>,
+}
+
+/// Accounts required to open a conditional escrow
+#[derive(Accounts)]
+pub struct TransferConditional<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Escrow account holding the funds until resolution (PDA)
+    #[account(
+        init,
+        payer = sender,
+        space = ConditionalEscrow::SIZE,
+        seeds = [b"conditional_escrow", sender.key().as_ref(), recipient.key().as_ref()],
+        bump
+    )]
+    pub escrow: Account<'info, ConditionalEscrow>,
+    
+    /// The sender funding the escrow (must sign the transaction)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// CHECK: Only stored in the escrow as the release destination
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Only stored in the escrow as the designated resolver
+    pub arbiter: AccountInfo<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// System program for account creation and funding
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to resolve a conditional escrow
+#[derive(Accounts)]
+pub struct ResolveConditional<'info> {
+    /// Escrow being resolved (closed back to the sender)
+    #[account(
+        mut,
+        seeds = [b"conditional_escrow", sender.key().as_ref(), recipient.key().as_ref()],
+        bump = escrow.bump,
+        has_one = sender,
+        has_one = recipient,
+        has_one = arbiter @ TransferError::Unauthorized
+    )]
+    pub escrow: Account<'info, ConditionalEscrow>,
+    
+    /// Arbiter designated when the escrow was opened (must sign)
+    pub arbiter: Signer<'info>,
+    
+    /// CHECK: Verified against the escrow; receives refunds and rent
+    #[account(mut)]
+    pub sender: AccountInfo<'info>,
+    
+    /// CHECK: Verified against the escrow; receives released funds
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// Fee configuration naming the treasury (PDA); without one, dust stays with the receiver
+    #[account(
+        seeds = [b"fee_config"],
+        bump = fee_config.bump,
+        has_one = treasury @ TransferError::InvalidRecipient
+    )]
+    pub fee_config: Option<Account<'info, FeeConfig>>,
+    
+    /// CHECK: Verified against the fee configuration; receives any dust left in the escrow
+    #[account(mut)]
+    pub treasury: Option<AccountInfo<'info>>,
+}
+
+/// Accounts required to refund an expired conditional escrow
+#[derive(Accounts)]
+pub struct ResolveConditionalDefault<'info> {
+    /// Escrow being refunded (closed back to the sender)
+    #[account(
+        mut,
+        seeds = [b"conditional_escrow", sender.key().as_ref(), escrow.recipient.as_ref()],
+        bump = escrow.bump,
+        has_one = sender
+    )]
+    pub escrow: Account<'info, ConditionalEscrow>,
+    
+    /// CHECK: Verified against the escrow; receives the refund and rent
+    #[account(mut)]
+    pub sender: AccountInfo<'info>,
+    
+    /// Fee configuration naming the treasury (PDA); without one, dust stays with the receiver
+    #[account(
+        seeds = [b"fee_config"],
+        bump = fee_config.bump,
+        has_one = treasury @ TransferError::InvalidRecipient
+    )]
+    pub fee_config: Option<Account<'info, FeeConfig>>,
+    
+    /// CHECK: Verified against the fee configuration; receives any dust left in the escrow
+    #[account(mut)]
+    pub treasury: Option<AccountInfo<'info>>,
+}
+
+/// Accounts required to open a timelock escrow
+#[derive(Accounts)]
+pub struct EscrowTimelock<'info> {
+    /// Program state account holding the lock duration limit (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Timelock holding the funds until unlock (PDA)
+    #[account(
+        init,
+        payer = sender,
+        space = Timelock::SIZE,
+        seeds = [b"timelock", sender.key().as_ref(), recipient.key().as_ref()],
+        bump
+    )]
+    pub timelock: Account<'info, Timelock>,
+    
+    /// The sender account (must sign, funds the timelock)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// CHECK: Only recorded in the timelock; receives funds on release
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for account creation and funding)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to release a timelock escrow
+#[derive(Accounts)]
+pub struct ReleaseTimelock<'info> {
+    /// Timelock being released (closed back to the sender)
+    #[account(
+        mut,
+        seeds = [b"timelock", sender.key().as_ref(), recipient.key().as_ref()],
+        bump = timelock.bump,
+        has_one = sender,
+        has_one = recipient
+    )]
+    pub timelock: Account<'info, Timelock>,
+    
+    /// CHECK: Verified against the timelock; receives the rent
+    #[account(mut)]
+    pub sender: AccountInfo<'info>,
+    
+    /// CHECK: Verified against the timelock; receives the locked funds
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// Fee configuration naming the treasury (PDA); without one, dust stays with the receiver
+    #[account(
+        seeds = [b"fee_config"],
+        bump = fee_config.bump,
+        has_one = treasury @ TransferError::InvalidRecipient
+    )]
+    pub fee_config: Option<Account<'info, FeeConfig>>,
+    
+    /// CHECK: Verified against the fee configuration; receives any dust left in the timelock
+    #[account(mut)]
+    pub treasury: Option<AccountInfo<'info>>,
+}
+
+/// Accounts required for authority-only state updates
+#[derive(Accounts)]
+pub struct UpdateState<'info> {
+    /// Program state account (PDA)
+    #[account(
+        mut,
+        seeds = [b"program_state"],
+        bump,
+        has_one = authority @ TransferError::Unauthorized
+    )]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Program authority (must sign)
+    pub authority: Signer<'info>,
+}
+
+/// Accounts required to freeze a recipient
+#[derive(Accounts)]
+pub struct FreezeRecipient<'info> {
+    /// Program state account (PDA)
+    #[account(
+        mut,
+        seeds = [b"program_state"],
+        bump,
+        has_one = authority @ TransferError::Unauthorized
+    )]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Freeze marker for the recipient (PDA)
+    #[account(
+        init,
+        payer = authority,
+        space = FrozenRecipient::SIZE,
+        seeds = [b"frozen_recipient", recipient.key().as_ref()],
+        bump
+    )]
+    pub frozen: Account<'info, FrozenRecipient>,
+    
+    /// CHECK: Only used as the seed of the freeze marker
+    pub recipient: AccountInfo<'info>,
+    
+    /// Program authority (pays for the marker)
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to unfreeze a recipient
+#[derive(Accounts)]
+pub struct UnfreezeRecipient<'info> {
+    /// Program state account (PDA)
+    #[account(
+        mut,
+        seeds = [b"program_state"],
+        bump,
+        has_one = authority @ TransferError::Unauthorized
+    )]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Freeze marker being removed (rent returned to the authority)
+    #[account(
+        mut,
+        seeds = [b"frozen_recipient", recipient.key().as_ref()],
+        bump = frozen.bump
+    )]
+    pub frozen: Account<'info, FrozenRecipient>,
+    
+    /// CHECK: Only used as the seed of the freeze marker
+    pub recipient: AccountInfo<'info>,
+    
+    /// Program authority (receives the marker rent)
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    /// Fee configuration naming the treasury (PDA); without one, dust stays with the receiver
+    #[account(
+        seeds = [b"fee_config"],
+        bump = fee_config.bump,
+        has_one = treasury @ TransferError::InvalidRecipient
+    )]
+    pub fee_config: Option<Account<'info, FeeConfig>>,
+    
+    /// CHECK: Verified against the fee configuration; receives any dust left in the marker
+    #[account(mut)]
+    pub treasury: Option<AccountInfo<'info>>,
+}
+
+/// Accounts required to exempt a sender from the global pause
+#[derive(Accounts)]
+pub struct AddPauseExemption<'info> {
+    /// Program state account (PDA)
+    #[account(
+        mut,
+        seeds = [b"program_state"],
+        bump,
+        has_one = authority @ TransferError::Unauthorized
+    )]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Exemption marker for the sender (PDA)
+    #[account(
+        init,
+        payer = authority,
+        space = PauseExempt::SIZE,
+        seeds = [b"pause_exempt", sender.key().as_ref()],
+        bump
+    )]
+    pub exempt: Account<'info, PauseExempt>,
+    
+    /// CHECK: Only used as the seed of the exemption marker
+    pub sender: AccountInfo<'info>,
+    
+    /// Program authority (pays for the marker)
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to remove a pause exemption
+#[derive(Accounts)]
+pub struct RemovePauseExemption<'info> {
+    /// Program state account (PDA)
+    #[account(
+        mut,
+        seeds = [b"program_state"],
+        bump,
+        has_one = authority @ TransferError::Unauthorized
+    )]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Exemption marker being removed (rent returned to the authority)
+    #[account(
+        mut,
+        seeds = [b"pause_exempt", sender.key().as_ref()],
+        bump = exempt.bump
+    )]
+    pub exempt: Account<'info, PauseExempt>,
+    
+    /// CHECK: Only used as the seed of the exemption marker
+    pub sender: AccountInfo<'info>,
+    
+    /// Program authority (receives the marker rent)
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    /// Fee configuration naming the treasury (PDA); without one, dust stays with the receiver
+    #[account(
+        seeds = [b"fee_config"],
+        bump = fee_config.bump,
+        has_one = treasury @ TransferError::InvalidRecipient
+    )]
+    pub fee_config: Option<Account<'info, FeeConfig>>,
+    
+    /// CHECK: Verified against the fee configuration; receives any dust left in the marker
+    #[account(mut)]
+    pub treasury: Option<AccountInfo<'info>>,
+}
+
+/// Accounts required to give a recipient priority
+#[derive(Accounts)]
+pub struct AddPriorityRecipient<'info> {
+    /// Program state account (PDA)
+    #[account(
+        mut,
+        seeds = [b"program_state"],
+        bump,
+        has_one = authority @ TransferError::Unauthorized
+    )]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Priority marker for the recipient (PDA)
+    #[account(
+        init,
+        payer = authority,
+        space = PriorityRecipient::SIZE,
+        seeds = [b"priority_recipient", recipient.key().as_ref()],
+        bump
+    )]
+    pub priority: Account<'info, PriorityRecipient>,
+    
+    /// CHECK: Only used as the seed of the priority marker
+    pub recipient: AccountInfo<'info>,
+    
+    /// Program authority (pays for the marker)
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to remove a recipient's priority
+#[derive(Accounts)]
+pub struct RemovePriorityRecipient<'info> {
+    /// Program state account (PDA)
+    #[account(
+        mut,
+        seeds = [b"program_state"],
+        bump,
+        has_one = authority @ TransferError::Unauthorized
+    )]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Priority marker being removed (rent returned to the authority)
+    #[account(
+        mut,
+        seeds = [b"priority_recipient", recipient.key().as_ref()],
+        bump = priority.bump
+    )]
+    pub priority: Account<'info, PriorityRecipient>,
+    
+    /// CHECK: Only used as the seed of the priority marker
+    pub recipient: AccountInfo<'info>,
+    
+    /// Program authority (receives the marker rent)
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    /// Fee configuration naming the treasury (PDA); without one, dust stays with the receiver
+    #[account(
+        seeds = [b"fee_config"],
+        bump = fee_config.bump,
+        has_one = treasury @ TransferError::InvalidRecipient
+    )]
+    pub fee_config: Option<Account<'info, FeeConfig>>,
+    
+    /// CHECK: Verified against the fee configuration; receives any dust left in the marker
+    #[account(mut)]
+    pub treasury: Option<AccountInfo<'info>>,
+}
+
+/// Accounts required for SOL transfer with fallback routing
+#[derive(Accounts)]
+pub struct TransferSolWithFallback<'info> {
+    /// Program state account holding the fallback recipient (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// The sender account (must sign the transaction)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// The primary recipient
+    /// CHECK: This account is only used to receive SOL, no validation needed
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Freeze marker PDA for the recipient; may not exist
+    #[account(seeds = [b"frozen_recipient", recipient.key().as_ref()], bump)]
+    pub frozen: UncheckedAccount<'info>,
+    
+    /// CHECK: Compared against the configured fallback before receiving SOL
+    #[account(mut)]
+    pub fallback: AccountInfo<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for an ordered SOL transfer
+#[derive(Accounts)]
+pub struct TransferSolOrdered<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Incoming payment nonce of the recipient (PDA, created on first use)
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = RecipientNonce::SIZE,
+        seeds = [b"recipient_nonce", recipient.key().as_ref()],
+        bump
+    )]
+    pub recipient_nonce: Account<'info, RecipientNonce>,
+    
+    /// The sender account (must sign the transaction)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// The recipient account (receives SOL)
+    /// CHECK: This account is only used to receive SOL, no validation needed
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for SOL transfer with batched stats
+#[derive(Accounts)]
+pub struct TransferSolBatched<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Per-sender stats accumulator (PDA, created on first use)
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = SenderStats::SIZE,
+        seeds = [b"sender_stats", sender.key().as_ref()],
+        bump
+    )]
+    pub sender_stats: Account<'info, SenderStats>,
+    
+    /// The sender account (must sign the transaction)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// The recipient account (receives SOL)
+    /// CHECK: This account is only used to receive SOL, no validation needed
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to flush a sender's pending stats
+#[derive(Accounts)]
+pub struct FlushStats<'info> {
+    /// Program state account receiving the totals (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Sender stats being flushed (PDA)
+    #[account(
+        mut,
+        seeds = [b"sender_stats", sender_stats.sender.as_ref()],
+        bump = sender_stats.bump
+    )]
+    pub sender_stats: Account<'info, SenderStats>,
+}
+
+/// Accounts required to configure fees
+#[derive(Accounts)]
+pub struct SetFee<'info> {
+    /// Program state account (PDA)
+    #[account(
+        mut,
+        seeds = [b"program_state"],
+        bump,
+        has_one = authority @ TransferError::Unauthorized
+    )]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Fee configuration (PDA, created on first use)
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = FeeConfig::SIZE,
+        seeds = [b"fee_config"],
+        bump
+    )]
+    pub fee_config: Account<'info, FeeConfig>,
+    
+    /// Program authority (pays for the fee configuration)
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to update an existing fee configuration
+#[derive(Accounts)]
+pub struct UpdateFeeConfig<'info> {
+    /// Program state account (PDA)
+    #[account(
+        mut,
+        seeds = [b"program_state"],
+        bump,
+        has_one = authority @ TransferError::Unauthorized
+    )]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Fee configuration (PDA)
+    #[account(mut, seeds = [b"fee_config"], bump = fee_config.bump)]
+    pub fee_config: Account<'info, FeeConfig>,
+    
+    /// Program authority (must sign)
+    pub authority: Signer<'info>,
+}
+
+/// Accounts required for a fee-charging token transfer
+#[derive(Accounts)]
+pub struct TransferTokenWithFee<'info> {
+    /// Program state account tracking fees against the lifetime cap (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Fee configuration, tracking rounded-away fees (PDA)
+    #[account(mut, seeds = [b"fee_config"], bump = fee_config.bump)]
+    pub fee_config: Account<'info, FeeConfig>,
+    
+    /// Owner of the source token account (must sign)
+    pub sender: Signer<'info>,
+    
+    /// CHECK: Mint of the transferred token; validated against every token account
+    #[account(mut, owner = token::TOKEN_PROGRAM_ID @ TransferError::InvalidTokenAccount)]
+    pub mint: UncheckedAccount<'info>,
+    
+    /// CHECK: Sender's token account; parsed and validated in the handler
+    #[account(mut)]
+    pub sender_token: UncheckedAccount<'info>,
+    
+    /// CHECK: Recipient's token account; parsed and validated in the handler
+    #[account(mut)]
+    pub recipient_token: UncheckedAccount<'info>,
+    
+    /// CHECK: Treasury token account; must be owned by the configured treasury
+    #[account(mut)]
+    pub treasury_token: UncheckedAccount<'info>,
+    
+    /// CHECK: Cold wallet token account; required only when fees are swept past the cap
+    #[account(mut)]
+    pub cold_token: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Rebate vault, a token account owned by the fee config; required only when a
+    /// rebate is paid
+    #[account(mut)]
+    pub rebate_token: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: SPL Token program
+    #[account(address = token::TOKEN_PROGRAM_ID)]
+    pub token_program: UncheckedAccount<'info>,
+}
+
+/// Accounts required for a recorded SOL transfer
+#[derive(Accounts)]
+pub struct TransferSolRecorded<'info> {
+    /// Program state account holding the record cap (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Merkle accumulator over all transfer records (PDA, created on first use)
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = TransferAccumulator::SIZE,
+        seeds = [b"transfer_accumulator"],
+        bump
+    )]
+    pub accumulator: Account<'info, TransferAccumulator>,
+    
+    /// Record of this transfer, keyed by its leaf index (PDA)
+    #[account(
+        init,
+        payer = sender,
+        space = TransferRecord::SIZE,
+        seeds = [b"transfer_record", accumulator.leaf_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub record: Account<'info, TransferRecord>,
+    
+    /// Head of the sender's record chain (PDA, created on first use)
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = SenderChain::SIZE,
+        seeds = [b"sender_chain", sender.key().as_ref()],
+        bump
+    )]
+    pub chain: Account<'info, SenderChain>,
+    
+    /// The sender account (must sign and pays for the record)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// The recipient account (receives SOL)
+    /// CHECK: This account is only used to receive SOL, no validation needed
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to close a transfer record
+#[derive(Accounts)]
+pub struct CloseRecord<'info> {
+    /// Record being closed (rent returned to the sender)
+    #[account(
+        mut,
+        seeds = [b"transfer_record", record.index.to_le_bytes().as_ref()],
+        bump = record.bump,
+        has_one = sender @ TransferError::Unauthorized
+    )]
+    pub record: Account<'info, TransferRecord>,
+    
+    /// Head of the sender's record chain (PDA)
+    #[account(
+        mut,
+        seeds = [b"sender_chain", sender.key().as_ref()],
+        bump = chain.bump
+    )]
+    pub chain: Account<'info, SenderChain>,
+    
+    /// Sender of the recorded transfer (must sign, receives the rent)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// Fee configuration naming the treasury (PDA); without one, dust stays with the receiver
+    #[account(
+        seeds = [b"fee_config"],
+        bump = fee_config.bump,
+        has_one = treasury @ TransferError::InvalidRecipient
+    )]
+    pub fee_config: Option<Account<'info, FeeConfig>>,
+    
+    /// CHECK: Verified against the fee configuration; receives any dust left in the record
+    #[account(mut)]
+    pub treasury: Option<AccountInfo<'info>>,
+}
+
+/// Accounts required to configure allowed amounts
+#[derive(Accounts)]
+pub struct SetAllowedAmounts<'info> {
+    /// Program state account (PDA)
+    #[account(
+        mut,
+        seeds = [b"program_state"],
+        bump,
+        has_one = authority @ TransferError::Unauthorized
+    )]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Allowed amounts list (PDA, created on first use)
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = AllowedAmounts::SIZE,
+        seeds = [b"allowed_amounts"],
+        bump
+    )]
+    pub allowed_amounts: Account<'info, AllowedAmounts>,
+    
+    /// Program authority (pays for the list)
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for a fixed-amount SOL transfer
+#[derive(Accounts)]
+pub struct TransferSolFixed<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Allowed amounts list (PDA)
+    #[account(seeds = [b"allowed_amounts"], bump = allowed_amounts.bump)]
+    pub allowed_amounts: Account<'info, AllowedAmounts>,
+    
+    /// The sender account (must sign the transaction)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// The recipient account (receives SOL)
+    /// CHECK: This account is only used to receive SOL, no validation needed
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for a SOL transfer reporting its priority fee
+#[derive(Accounts)]
+pub struct TransferSolWithPriorityFee<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Priority fee statistics (PDA, created on first use)
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = PriorityFeeStats::SIZE,
+        seeds = [b"priority_fee_stats"],
+        bump
+    )]
+    pub priority_fee_stats: Account<'info, PriorityFeeStats>,
+    
+    /// The sender account (must sign the transaction)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// The recipient account (receives SOL)
+    /// CHECK: This account is only used to receive SOL, no validation needed
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to register recipient settings
+#[derive(Accounts)]
+pub struct RegisterRecipient<'info> {
+    /// Recipient stats (PDA, created on first registration)
+    #[account(
+        init_if_needed,
+        payer = recipient,
+        space = RecipientStats::SIZE,
+        seeds = [b"recipient_stats", recipient.key().as_ref()],
+        bump
+    )]
+    pub recipient_stats: Account<'info, RecipientStats>,
+    
+    /// Recipient registering its settings (must sign, pays for the account)
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+    
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for a category-checked SOL transfer
+#[derive(Accounts)]
+pub struct TransferToCategory<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Stats of the recipient, holding its registered category (PDA)
+    #[account(
+        mut,
+        seeds = [b"recipient_stats", recipient.key().as_ref()],
+        bump = recipient_stats.bump
+    )]
+    pub recipient_stats: Account<'info, RecipientStats>,
+    
+    /// The sender account (must sign the transaction)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// The recipient account (receives SOL)
+    /// CHECK: This account is only used to receive SOL, no validation needed
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to set a recipient's KYC flag
+#[derive(Accounts)]
+pub struct SetKycVerified<'info> {
+    /// Program state account (PDA)
+    #[account(
+        mut,
+        seeds = [b"program_state"],
+        bump,
+        has_one = authority @ TransferError::Unauthorized
+    )]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Recipient stats (PDA, created if the recipient never registered)
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = RecipientStats::SIZE,
+        seeds = [b"recipient_stats", recipient.key().as_ref()],
+        bump
+    )]
+    pub recipient_stats: Account<'info, RecipientStats>,
+    
+    /// CHECK: Only used as the seed of the recipient stats
+    pub recipient: AccountInfo<'info>,
+    
+    /// Program authority (pays for the stats account if needed)
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for a KYC-checked SOL transfer
+#[derive(Accounts)]
+pub struct TransferKyc<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Stats of the recipient, holding its KYC flag (PDA)
+    #[account(
+        mut,
+        seeds = [b"recipient_stats", recipient.key().as_ref()],
+        bump = recipient_stats.bump
+    )]
+    pub recipient_stats: Account<'info, RecipientStats>,
+    
+    /// The sender account (must sign the transaction)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// The recipient account (receives SOL)
+    /// CHECK: This account is only used to receive SOL, no validation needed
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to refund a recorded transfer
+#[derive(Accounts)]
+pub struct RefundTransfer<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Record of the transfer being refunded (PDA)
+    #[account(
+        mut,
+        seeds = [b"transfer_record", record.index.to_le_bytes().as_ref()],
+        bump = record.bump,
+        has_one = sender,
+        has_one = recipient @ TransferError::Unauthorized
+    )]
+    pub record: Account<'info, TransferRecord>,
+    
+    /// Original recipient returning the funds (must sign)
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+    
+    /// CHECK: Verified against the record; receives the refund
+    #[account(mut)]
+    pub sender: AccountInfo<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for read-only state queries
+#[derive(Accounts)]
+pub struct GetState<'info> {
+    /// Program state account (PDA)
+    #[account(seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+}
+
+/// Accounts required for a SOL transfer with a receipt
+#[derive(Accounts)]
+#[instruction(amount: u64, nonce: u64)]
+pub struct TransferWithReceipt<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Receipt for this transfer (PDA derived from its details)
+    #[account(
+        init,
+        payer = sender,
+        space = Receipt::SIZE,
+        seeds = [
+            b"receipt",
+            Receipt::seed_hash(&sender.key(), &recipient.key(), amount, nonce).as_ref()
+        ],
+        bump
+    )]
+    pub receipt: Account<'info, Receipt>,
+    
+    /// The sender account (must sign and pays for the receipt)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// The recipient account (receives SOL)
+    /// CHECK: This account is only used to receive SOL, no validation needed
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to verify a receipt
+#[derive(Accounts)]
+#[instruction(sender: Pubkey, recipient: Pubkey, amount: u64, nonce: u64)]
+pub struct VerifyReceipt<'info> {
+    /// CHECK: Receipt PDA for the given details; may not exist
+    #[account(
+        seeds = [
+            b"receipt",
+            Receipt::seed_hash(&sender, &recipient, amount, nonce).as_ref()
+        ],
+        bump
+    )]
+    pub receipt: UncheckedAccount<'info>,
+}
+
+/// Accounts required for a two-party net settlement
+#[derive(Accounts)]
+pub struct NetSettle<'info> {
+    /// Program state account recording totals and the pause flag (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// First party (must sign)
+    #[account(mut)]
+    pub party_a: Signer<'info>,
+    
+    /// Second party (must sign)
+    #[account(mut)]
+    pub party_b: Signer<'info>,
+    
+    /// CHECK: Guardian setting PDA for the party a; may not exist
+    #[account(seeds = [b"guardian", party_a.key().as_ref()], bump)]
+    pub party_a_guardian: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the party b; may not exist
+    #[account(seeds = [b"guardian", party_b.key().as_ref()], bump)]
+    pub party_b_guardian: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to configure the loyalty program
+#[derive(Accounts)]
+pub struct SetLoyaltyConfig<'info> {
+    /// Program state account (PDA)
+    #[account(
+        mut,
+        seeds = [b"program_state"],
+        bump,
+        has_one = authority @ TransferError::Unauthorized
+    )]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Loyalty configuration and redemption vault (PDA, created on first use)
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = LoyaltyConfig::SIZE,
+        seeds = [b"loyalty_config"],
+        bump
+    )]
+    pub loyalty_config: Account<'info, LoyaltyConfig>,
+    
+    /// Program authority (pays for the loyalty configuration)
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for a SOL transfer earning loyalty points
+#[derive(Accounts)]
+pub struct TransferWithPoints<'info> {
+    /// Program state account recording totals and the pause flag (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Loyalty configuration holding the accrual rate (PDA)
+    #[account(seeds = [b"loyalty_config"], bump = loyalty_config.bump)]
+    pub loyalty_config: Account<'info, LoyaltyConfig>,
+    
+    /// Sender's loyalty points (PDA, created on first use)
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = LoyaltyPoints::SIZE,
+        seeds = [b"loyalty_points", sender.key().as_ref()],
+        bump
+    )]
+    pub points: Account<'info, LoyaltyPoints>,
+    
+    /// The sender account (must sign the transaction)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// The recipient account (receives SOL)
+    /// CHECK: This account is only used to receive SOL, no validation needed
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to redeem loyalty points
+#[derive(Accounts)]
+pub struct RedeemPoints<'info> {
+    /// Loyalty configuration; its lamports above rent fund redemptions (PDA)
+    #[account(mut, seeds = [b"loyalty_config"], bump = loyalty_config.bump)]
+    pub loyalty_config: Account<'info, LoyaltyConfig>,
+    
+    /// Points being redeemed (PDA)
+    #[account(
+        mut,
+        seeds = [b"loyalty_points", owner.key().as_ref()],
+        bump = points.bump,
+        has_one = owner @ TransferError::Unauthorized
+    )]
+    pub points: Account<'info, LoyaltyPoints>,
+    
+    /// Owner of the points (must sign, receives the SOL)
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+/// Accounts required for a SOL transfer tagged with a region
+#[derive(Accounts)]
+#[instruction(amount: u64, region: u8)]
+pub struct TransferWithRegion<'info> {
+    /// Program state account recording totals and the pause flag (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Stats of the reported region (PDA, created on first use)
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = RegionStats::SIZE,
+        seeds = [b"region_stats", [region].as_ref()],
+        bump,
+        constraint = region < REGION_COUNT @ TransferError::InvalidRegion
+    )]
+    pub region_stats: Account<'info, RegionStats>,
+    
+    /// The sender account (must sign the transaction)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// The recipient account (receives SOL)
+    /// CHECK: This account is only used to receive SOL, no validation needed
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for a SOL-for-token swap
+#[derive(Accounts)]
+pub struct SwapSolForToken<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Party paying SOL and receiving tokens (must sign)
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    /// Party paying tokens and receiving SOL (must sign)
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    
+    /// CHECK: Mint of the swapped token; validated against both token accounts
+    #[account(owner = token::TOKEN_PROGRAM_ID @ TransferError::InvalidTokenAccount)]
+    pub mint: UncheckedAccount<'info>,
+    
+    /// CHECK: Seller's token account; parsed and validated in the handler
+    #[account(mut)]
+    pub seller_token: UncheckedAccount<'info>,
+    
+    /// CHECK: Buyer's token account; parsed and validated in the handler
+    #[account(mut)]
+    pub buyer_token: UncheckedAccount<'info>,
+    
+    /// CHECK: SPL Token program
+    #[account(address = token::TOKEN_PROGRAM_ID)]
+    pub token_program: UncheckedAccount<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the buyer; may not exist
+    #[account(seeds = [b"pause_exempt", buyer.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the buyer; may not exist
+    #[account(seeds = [b"guardian", buyer.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to create a payment stream
+#[derive(Accounts)]
+pub struct CreateStream<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Stream holding the deposit (PDA)
+    #[account(
+        init,
+        payer = sender,
+        space = Stream::SIZE,
+        seeds = [b"stream", sender.key().as_ref(), recipient.key().as_ref()],
+        bump
+    )]
+    pub stream: Account<'info, Stream>,
+    
+    /// The sender account (must sign, funds the stream)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// CHECK: Only recorded in the stream; withdraws from it later
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for account creation and funding)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to withdraw from a payment stream
+#[derive(Accounts)]
+pub struct WithdrawStream<'info> {
+    /// Stream being withdrawn from (PDA)
+    #[account(
+        mut,
+        seeds = [b"stream", stream.sender.as_ref(), recipient.key().as_ref()],
+        bump = stream.bump,
+        has_one = recipient @ TransferError::Unauthorized
+    )]
+    pub stream: Account<'info, Stream>,
+    
+    /// Recipient of the stream (must sign, receives the accrued amount)
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+}
+
+/// Accounts required to cancel a payment stream
+#[derive(Accounts)]
+pub struct CancelStream<'info> {
+    /// Stream being cancelled (closed back to the sender)
+    #[account(
+        mut,
+        seeds = [b"stream", sender.key().as_ref(), recipient.key().as_ref()],
+        bump = stream.bump,
+        has_one = sender @ TransferError::Unauthorized,
+        has_one = recipient
+    )]
+    pub stream: Account<'info, Stream>,
+    
+    /// Sender of the stream (must sign, receives the refund and rent)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// CHECK: Verified against the stream; receives the accrued amount
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// Fee configuration naming the treasury (PDA); without one, dust stays with the receiver
+    #[account(
+        seeds = [b"fee_config"],
+        bump = fee_config.bump,
+        has_one = treasury @ TransferError::InvalidRecipient
+    )]
+    pub fee_config: Option<Account<'info, FeeConfig>>,
+    
+    /// CHECK: Verified against the fee configuration; receives any dust left in the stream
+    #[account(mut)]
+    pub treasury: Option<AccountInfo<'info>>,
+}
+
+/// Accounts required for a SOL transfer that tracks sender-recipient pairs
+#[derive(Accounts)]
+pub struct TransferSolPaired<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Marker of the sender-recipient pair (PDA, created on their first transfer)
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = PairMarker::SIZE,
+        seeds = [b"pair", sender.key().as_ref(), recipient.key().as_ref()],
+        bump
+    )]
+    pub pair: Account<'info, PairMarker>,
+    
+    /// The sender account (must sign and pays for the marker)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// The recipient account (receives SOL)
+    /// CHECK: This account is only used to receive SOL, no validation needed
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to set a sender's guardian
+#[derive(Accounts)]
+pub struct SetGuardian<'info> {
+    /// Sender's guardian setting (PDA, created on first use)
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = SenderGuardian::SIZE,
+        seeds = [b"guardian", sender.key().as_ref()],
+        bump
+    )]
+    pub guardian_config: Account<'info, SenderGuardian>,
+    
+    /// The sender being protected (must sign, pays for the setting)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// Current guardian; must co-sign to replace an existing guardian
+    pub current_guardian: Option<Signer<'info>>,
+    
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for a SOL transfer co-signed by a guardian for new recipients
+#[derive(Accounts)]
+pub struct TransferWithGuardian<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Sender's guardian setting (PDA)
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump = guardian_config.bump)]
+    pub guardian_config: Account<'info, SenderGuardian>,
+    
+    /// Marker of the approved recipient (PDA, created on the first transfer)
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = ApprovedRecipient::SIZE,
+        seeds = [b"approved_recipient", sender.key().as_ref(), recipient.key().as_ref()],
+        bump
+    )]
+    pub approved: Account<'info, ApprovedRecipient>,
+    
+    /// The sender account (must sign and pays for the marker)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// The recipient account (receives SOL)
+    /// CHECK: This account is only used to receive SOL, no validation needed
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// Guardian co-signature; required only on the first transfer to a recipient
+    pub guardian: Option<Signer<'info>>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to set the token gate mint
+#[derive(Accounts)]
+pub struct SetTokenGate<'info> {
+    /// Program state account (PDA)
+    #[account(
+        mut,
+        seeds = [b"program_state"],
+        bump,
+        has_one = authority @ TransferError::Unauthorized
+    )]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Token gate configuration (PDA, created on first use)
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = TokenGate::SIZE,
+        seeds = [b"token_gate"],
+        bump
+    )]
+    pub token_gate: Account<'info, TokenGate>,
+    
+    /// Program authority (pays for the token gate)
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for a token-gated SOL transfer
+#[derive(Accounts)]
+pub struct TransferTokenGated<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Token gate configuration holding the required mint (PDA)
+    #[account(seeds = [b"token_gate"], bump = token_gate.bump)]
+    pub token_gate: Account<'info, TokenGate>,
+    
+    /// The sender account (must sign the transaction)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// The recipient account (receives SOL)
+    /// CHECK: This account is only used to receive SOL, no validation needed
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Recipient's token account; parsed and validated in the handler
+    pub recipient_token: UncheckedAccount<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for an idempotent SOL transfer
+#[derive(Accounts)]
+#[instruction(amount: u64, event_id: [u8; 32])]
+pub struct TransferIdempotent<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Marker of the event id (PDA, created on its first transfer)
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = IdempotencyMarker::SIZE,
+        seeds = [b"idempotency", sender.key().as_ref(), event_id.as_ref()],
+        bump
+    )]
+    pub marker: Account<'info, IdempotencyMarker>,
+    
+    /// The sender account (must sign and pays for the marker)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// The recipient account (receives SOL)
+    /// CHECK: This account is only used to receive SOL, no validation needed
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to clean up an expired idempotency marker
+#[derive(Accounts)]
+pub struct CleanupIdempotency<'info> {
+    /// Marker being closed (rent returned to the sender)
+    #[account(
+        mut,
+        seeds = [b"idempotency", sender.key().as_ref(), marker.event_id.as_ref()],
+        bump = marker.bump,
+        has_one = sender
+    )]
+    pub marker: Account<'info, IdempotencyMarker>,
+    
+    /// CHECK: Verified against the marker; receives the rent
+    #[account(mut)]
+    pub sender: AccountInfo<'info>,
+    
+    /// Fee configuration naming the treasury (PDA); without one, dust stays with the receiver
+    #[account(
+        seeds = [b"fee_config"],
+        bump = fee_config.bump,
+        has_one = treasury @ TransferError::InvalidRecipient
+    )]
+    pub fee_config: Option<Account<'info, FeeConfig>>,
+    
+    /// CHECK: Verified against the fee configuration; receives any dust left in the marker
+    #[account(mut)]
+    pub treasury: Option<AccountInfo<'info>>,
+}
+
+/// Accounts required to configure the bonding curve
+#[derive(Accounts)]
+pub struct SetCurve<'info> {
+    /// Program state account (PDA)
+    #[account(
+        mut,
+        seeds = [b"program_state"],
+        bump,
+        has_one = authority @ TransferError::Unauthorized
+    )]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Bonding curve and its reserve (PDA, created on first use)
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = Curve::SIZE,
+        seeds = [b"curve"],
+        bump
+    )]
+    pub curve: Account<'info, Curve>,
+    
+    /// Program authority (pays for the curve account)
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to buy units from the bonding curve
+#[derive(Accounts)]
+pub struct BuyCurve<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Bonding curve receiving the payment (PDA)
+    #[account(mut, seeds = [b"curve"], bump = curve.bump)]
+    pub curve: Account<'info, Curve>,
+    
+    /// Buyer's units (PDA, created on first buy)
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = CurvePosition::SIZE,
+        seeds = [b"curve_position", buyer.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, CurvePosition>,
+    
+    /// Buyer paying SOL (must sign)
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the buyer; may not exist
+    #[account(seeds = [b"pause_exempt", buyer.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the buyer; may not exist
+    #[account(seeds = [b"guardian", buyer.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to sell units back to the bonding curve
+#[derive(Accounts)]
+pub struct SellCurve<'info> {
+    /// Bonding curve paying out of its reserve (PDA)
+    #[account(mut, seeds = [b"curve"], bump = curve.bump)]
+    pub curve: Account<'info, Curve>,
+    
+    /// Seller's units (PDA)
+    #[account(
+        mut,
+        seeds = [b"curve_position", owner.key().as_ref()],
+        bump = position.bump,
+        has_one = owner @ TransferError::Unauthorized
+    )]
+    pub position: Account<'info, CurvePosition>,
+    
+    /// Owner of the units (must sign, receives the SOL)
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+/// Accounts required to read the bonding curve
+#[derive(Accounts)]
+pub struct GetCurve<'info> {
+    /// Bonding curve (PDA)
+    #[account(seeds = [b"curve"], bump = curve.bump)]
+    pub curve: Account<'info, Curve>,
+}
+
+/// Accounts required to create a password escrow
+#[derive(Accounts)]
+#[instruction(amount: u64, key_hash: [u8; 32])]
+pub struct EscrowPassword<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Escrow holding the funds until claimed (PDA)
+    #[account(
+        init,
+        payer = sender,
+        space = PasswordEscrow::SIZE,
+        seeds = [b"password_escrow", key_hash.as_ref()],
+        bump
+    )]
+    pub escrow: Account<'info, PasswordEscrow>,
+    
+    /// The sender account (must sign, funds the escrow)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for account creation and funding)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to claim a password escrow
+#[derive(Accounts)]
+pub struct ClaimPassword<'info> {
+    /// Escrow being claimed (closed back to the sender on success)
+    #[account(
+        mut,
+        seeds = [b"password_escrow", escrow.key_hash.as_ref()],
+        bump = escrow.bump,
+        has_one = sender
+    )]
+    pub escrow: Account<'info, PasswordEscrow>,
+    
+    /// Claimer presenting the secret (must sign, receives the funds)
+    #[account(mut)]
+    pub claimer: Signer<'info>,
+    
+    /// CHECK: Verified against the escrow; receives the rent
+    #[account(mut)]
+    pub sender: AccountInfo<'info>,
+    
+    /// Fee configuration naming the treasury (PDA); without one, dust stays with the receiver
+    #[account(
+        seeds = [b"fee_config"],
+        bump = fee_config.bump,
+        has_one = treasury @ TransferError::InvalidRecipient
+    )]
+    pub fee_config: Option<Account<'info, FeeConfig>>,
+    
+    /// CHECK: Verified against the fee configuration; receives any dust left in the escrow
+    #[account(mut)]
+    pub treasury: Option<AccountInfo<'info>>,
+}
+
+/// Accounts required for a SOL transfer counted in the daily histogram
+#[derive(Accounts)]
+#[instruction(amount: u64, day: i64)]
+pub struct TransferWithHistogram<'info> {
+    /// Program state account recording totals and the pause flag (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Histogram of the given day (PDA, created on the day's first transfer)
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = Histogram::SIZE,
+        seeds = [b"histogram", day.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub histogram: Account<'info, Histogram>,
+    
+    /// The sender account (must sign the transaction)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// The recipient account (receives SOL)
+    /// CHECK: This account is only used to receive SOL, no validation needed
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to read a day's histogram
+#[derive(Accounts)]
+#[instruction(day: i64)]
+pub struct GetHistogram<'info> {
+    /// Histogram of the given day (PDA)
+    #[account(seeds = [b"histogram", day.to_le_bytes().as_ref()], bump = histogram.bump)]
+    pub histogram: Account<'info, Histogram>,
+}
+
+/// Accounts required for a history-gated SOL transfer
+#[derive(Accounts)]
+pub struct TransferWithHistory<'info> {
+    /// Program state account holding the required history (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Per-sender stats holding the transfer count (PDA, created on first use)
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = SenderStats::SIZE,
+        seeds = [b"sender_stats", sender.key().as_ref()],
+        bump
+    )]
+    pub sender_stats: Account<'info, SenderStats>,
+    
+    /// The sender account (must sign the transaction)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// The recipient account (receives SOL)
+    /// CHECK: This account is only used to receive SOL, no validation needed
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to issue an invoice
+#[derive(Accounts)]
+#[instruction(invoice_id: u64)]
+pub struct CreateInvoice<'info> {
+    /// Invoice being issued (PDA)
+    #[account(
+        init,
+        payer = issuer,
+        space = Invoice::SIZE,
+        seeds = [b"invoice", issuer.key().as_ref(), invoice_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub invoice: Account<'info, Invoice>,
+    
+    /// Payee issuing the invoice (must sign, pays for the account)
+    #[account(mut)]
+    pub issuer: Signer<'info>,
+    
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to pay an invoice
+#[derive(Accounts)]
+pub struct PayInvoice<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Invoice being paid (PDA)
+    #[account(
+        mut,
+        seeds = [
+            b"invoice",
+            issuer.key().as_ref(),
+            invoice.invoice_id.to_le_bytes().as_ref()
+        ],
+        bump = invoice.bump,
+        has_one = issuer
+    )]
+    pub invoice: Account<'info, Invoice>,
+    
+    /// The paying account (must sign the transaction)
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    /// CHECK: Verified against the invoice; receives the payment
+    #[account(mut)]
+    pub issuer: AccountInfo<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the payer; may not exist
+    #[account(seeds = [b"pause_exempt", payer.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the payer; may not exist
+    #[account(seeds = [b"guardian", payer.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to transfer SOL as wSOL
+#[derive(Accounts)]
+pub struct TransferAsWsol<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// The sender account (must sign the transaction)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// CHECK: Owner of the wSOL token account; only compared against it
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Recipient's wSOL token account; parsed and validated in the handler
+    #[account(mut)]
+    pub recipient_token: UncheckedAccount<'info>,
+    
+    /// CHECK: SPL Token program
+    #[account(address = token::TOKEN_PROGRAM_ID)]
+    pub token_program: UncheckedAccount<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to appoint the emergency council
+#[derive(Accounts)]
+pub struct SetEmergencyCouncil<'info> {
+    /// Program state account (PDA)
+    #[account(
+        mut,
+        seeds = [b"program_state"],
+        bump,
+        has_one = authority @ TransferError::Unauthorized
+    )]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Emergency council (PDA, can only be created once)
+    #[account(
+        init,
+        payer = authority,
+        space = EmergencyCouncil::SIZE,
+        seeds = [b"emergency_council"],
+        bump
+    )]
+    pub council: Account<'info, EmergencyCouncil>,
+    
+    /// Program authority (pays for the council account)
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for an emergency unpause
+///
+/// Signing council members are passed as remaining accounts.
+#[derive(Accounts)]
+pub struct EmergencyUnpause<'info> {
+    /// Program state account being unpaused (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Emergency council (PDA)
+    #[account(seeds = [b"emergency_council"], bump = council.bump)]
+    pub council: Account<'info, EmergencyCouncil>,
+}
+
+/// Accounts required to register a notification program
+#[derive(Accounts)]
+pub struct SetNotificationProgram<'info> {
+    /// Recipient's notification setting (PDA, created on first use)
+    #[account(
+        init_if_needed,
+        payer = recipient,
+        space = NotificationConfig::SIZE,
+        seeds = [b"notification_config", recipient.key().as_ref()],
+        bump
+    )]
+    pub notification_config: Account<'info, NotificationConfig>,
+    
+    /// Recipient registering the program (must sign, pays for the account)
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+    
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for a SOL transfer that notifies the recipient's program
+#[derive(Accounts)]
+pub struct TransferWithNotification<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// The sender account (must sign the transaction)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// The recipient account (receives SOL)
+    /// CHECK: This account is only used to receive SOL, no validation needed
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Recipient's notification setting PDA; may not exist
+    #[account(seeds = [b"notification_config", recipient.key().as_ref()], bump)]
+    pub notification_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Compared against the registered notification program in the handler
+    pub notification_program: UncheckedAccount<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for a SOL transfer tracked in the moving average
+#[derive(Accounts)]
+pub struct TransferWithAverage<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Window of recent transfer amounts (PDA, created on first use)
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = MovingAverage::SIZE,
+        seeds = [b"moving_average"],
+        bump
+    )]
+    pub moving_average: Account<'info, MovingAverage>,
+    
+    /// The sender account (must sign the transaction)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// The recipient account (receives SOL)
+    /// CHECK: This account is only used to receive SOL, no validation needed
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to read the moving average
+#[derive(Accounts)]
+pub struct GetMovingAverage<'info> {
+    /// Window of recent transfer amounts (PDA)
+    #[account(seeds = [b"moving_average"], bump = moving_average.bump)]
+    pub moving_average: Account<'info, MovingAverage>,
+}
+
+/// Accounts required to escrow a cancellable grace transfer
+#[derive(Accounts)]
+pub struct TransferWithGrace<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Grace transfer holding the funds (PDA)
+    #[account(
+        init,
+        payer = sender,
+        space = GraceTransfer::SIZE,
+        seeds = [b"grace", sender.key().as_ref(), recipient.key().as_ref()],
+        bump
+    )]
+    pub grace_transfer: Account<'info, GraceTransfer>,
+    
+    /// The sender account (must sign, funds the escrow)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// CHECK: Only recorded in the escrow; claims once the grace period ends
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for account creation and funding)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for the sender to cancel a grace transfer
+#[derive(Accounts)]
+pub struct CancelGrace<'info> {
+    /// Grace transfer being cancelled (closed back to the sender)
+    #[account(
+        mut,
+        seeds = [b"grace", sender.key().as_ref(), recipient.key().as_ref()],
+        bump = grace_transfer.bump,
+        has_one = sender,
+        has_one = recipient
+    )]
+    pub grace_transfer: Account<'info, GraceTransfer>,
+    
+    /// The sender who funded the escrow (must sign)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// CHECK: Verified against the escrow; only used for the PDA seeds
+    pub recipient: AccountInfo<'info>,
+    
+    /// Fee configuration naming the treasury (PDA); without one, dust stays with the receiver
+    #[account(
+        seeds = [b"fee_config"],
+        bump = fee_config.bump,
+        has_one = treasury @ TransferError::InvalidRecipient
+    )]
+    pub fee_config: Option<Account<'info, FeeConfig>>,
+    
+    /// CHECK: Verified against the fee configuration; receives any dust left in the escrow
+    #[account(mut)]
+    pub treasury: Option<AccountInfo<'info>>,
+}
+
+/// Accounts required for the recipient to claim a grace transfer
+#[derive(Accounts)]
+pub struct ClaimGrace<'info> {
+    /// Grace transfer being claimed (closed back to the sender)
+    #[account(
+        mut,
+        seeds = [b"grace", sender.key().as_ref(), recipient.key().as_ref()],
+        bump = grace_transfer.bump,
+        has_one = sender,
+        has_one = recipient
+    )]
+    pub grace_transfer: Account<'info, GraceTransfer>,
+    
+    /// CHECK: Verified against the escrow; receives the rent
+    #[account(mut)]
+    pub sender: AccountInfo<'info>,
+    
+    /// The recipient claiming the funds (must sign)
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+    
+    /// Fee configuration naming the treasury (PDA); without one, dust stays with the receiver
+    #[account(
+        seeds = [b"fee_config"],
+        bump = fee_config.bump,
+        has_one = treasury @ TransferError::InvalidRecipient
+    )]
+    pub fee_config: Option<Account<'info, FeeConfig>>,
+    
+    /// CHECK: Verified against the fee configuration; receives any dust left in the escrow
+    #[account(mut)]
+    pub treasury: Option<AccountInfo<'info>>,
+}
+
+/// Accounts required for a SOL transfer to several recipients
+///
+/// Recipients are passed as writable `remaining_accounts`, one per amount.
+#[derive(Accounts)]
+pub struct TransferSolBatch<'info> {
+    /// Program state account tracking totals (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// The sender account (must sign the transaction)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for a recipient to set its rate limit
+#[derive(Accounts)]
+pub struct SetRecipientRate<'info> {
+    /// Recipient rate limit (PDA, created on first use)
+    #[account(
+        init_if_needed,
+        payer = recipient,
+        space = RecipientRate::SIZE,
+        seeds = [b"recipient_rate", recipient.key().as_ref()],
+        bump
+    )]
+    pub recipient_rate: Account<'info, RecipientRate>,
+    
+    /// Recipient setting its limit (must sign, pays for the account)
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+    
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for a SOL transfer checked against the recipient's rate limit
+#[derive(Accounts)]
+pub struct TransferRateLimited<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Recipient rate limit (PDA, created unlimited on first use)
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = RecipientRate::SIZE,
+        seeds = [b"recipient_rate", recipient.key().as_ref()],
+        bump
+    )]
+    pub recipient_rate: Account<'info, RecipientRate>,
+    
+    /// The sender account (must sign the transaction)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// The recipient account (receives SOL)
+    /// CHECK: This account is only used to receive SOL, no validation needed
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to contribute SOL to the share pool
+#[derive(Accounts)]
+pub struct Contribute<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Share pool receiving the SOL (PDA, created on first contribution)
+    #[account(
+        init_if_needed,
+        payer = contributor,
+        space = SharePool::SIZE,
+        seeds = [b"share_pool"],
+        bump
+    )]
+    pub pool: Account<'info, SharePool>,
+    
+    /// Contributor's shares (PDA, created on first contribution)
+    #[account(
+        init_if_needed,
+        payer = contributor,
+        space = Shares::SIZE,
+        seeds = [b"shares", contributor.key().as_ref()],
+        bump
+    )]
+    pub shares: Account<'info, Shares>,
+    
+    /// Contributor paying SOL (must sign)
+    #[account(mut)]
+    pub contributor: Signer<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the contributor; may not exist
+    #[account(seeds = [b"pause_exempt", contributor.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the contributor; may not exist
+    #[account(seeds = [b"guardian", contributor.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to redeem shares from the share pool
+#[derive(Accounts)]
+pub struct RedeemShares<'info> {
+    /// Share pool paying out (PDA)
+    #[account(mut, seeds = [b"share_pool"], bump = pool.bump)]
+    pub pool: Account<'info, SharePool>,
+    
+    /// Owner's shares (PDA)
+    #[account(
+        mut,
+        seeds = [b"shares", owner.key().as_ref()],
+        bump = shares.bump,
+        has_one = owner @ TransferError::Unauthorized
+    )]
+    pub shares: Account<'info, Shares>,
+    
+    /// Owner of the shares (must sign, receives the SOL)
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+/// Accounts required to transfer SOL with tax withholding
+#[derive(Accounts)]
+pub struct TransferWithhold<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Sender's withholding (PDA, created on first use)
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = TaxWithholding::SIZE,
+        seeds = [b"tax_withholding", sender.key().as_ref()],
+        bump
+    )]
+    pub withholding: Account<'info, TaxWithholding>,
+    
+    /// The sender account (must sign, pays the amount and the withheld share)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// CHECK: This account is only used to receive SOL, no validation needed
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to withdraw withheld SOL
+#[derive(Accounts)]
+pub struct WithdrawWithholding<'info> {
+    /// Owner's withholding (PDA)
+    #[account(
+        mut,
+        seeds = [b"tax_withholding", owner.key().as_ref()],
+        bump = withholding.bump,
+        has_one = owner @ TransferError::Unauthorized
+    )]
+    pub withholding: Account<'info, TaxWithholding>,
+    
+    /// Owner of the withholding (must sign, receives the SOL)
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+/// Accounts required to create a group budget
+#[derive(Accounts)]
+#[instruction(group_id: u64)]
+pub struct CreateGroupBudget<'info> {
+    /// Group budget being created (PDA)
+    #[account(
+        init,
+        payer = admin,
+        space = GroupBudget::SIZE,
+        seeds = [b"group_budget", admin.key().as_ref(), group_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub group_budget: Account<'info, GroupBudget>,
+    
+    /// Admin creating the group (must sign, pays for the account)
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for a SOL transfer charged to a group budget
+#[derive(Accounts)]
+pub struct TransferFromGroup<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Group budget the transfer is charged to (PDA)
+    #[account(
+        mut,
+        seeds = [
+            b"group_budget",
+            group_budget.admin.as_ref(),
+            group_budget.group_id.to_le_bytes().as_ref()
+        ],
+        bump = group_budget.bump
+    )]
+    pub group_budget: Account<'info, GroupBudget>,
+    
+    /// The sending group member (must sign the transaction)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// The recipient account (receives SOL)
+    /// CHECK: This account is only used to receive SOL, no validation needed
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for a SOL transfer tracked in the daily window
+#[derive(Accounts)]
+pub struct TransferWithDailyClose<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Totals of the current day (PDA, created on first use)
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = DailyWindow::SIZE,
+        seeds = [b"daily_window"],
+        bump
+    )]
+    pub daily_window: Account<'info, DailyWindow>,
+    
+    /// CHECK: Snapshot PDA for the window's recorded day; only created when that day is closed
+    #[account(
+        mut,
+        seeds = [b"daily_snapshot", daily_window.day.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub snapshot: UncheckedAccount<'info>,
+    
+    /// The sender account (must sign the transaction, pays for a new snapshot)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// The recipient account (receives SOL)
+    /// CHECK: This account is only used to receive SOL, no validation needed
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for a SOL transfer acknowledged by the recipient's signature
+#[derive(Accounts)]
+pub struct TransferSignedAck<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Acknowledgement nonce of the recipient (PDA, created on first use)
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = RecipientNonce::SIZE,
+        seeds = [b"ack_nonce", recipient.key().as_ref()],
+        bump
+    )]
+    pub ack_nonce: Account<'info, RecipientNonce>,
+    
+    /// The sender account (must sign the transaction)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// The recipient account (receives SOL)
+    /// CHECK: Compared against the key that signed the acknowledgement
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to read the Ed25519 verification
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to hold a reversible transfer
+#[derive(Accounts)]
+pub struct TransferReversible<'info> {
+    /// Program state account holding the challenge period (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Reversible transfer holding the funds (PDA)
+    #[account(
+        init,
+        payer = sender,
+        space = ReversibleTransfer::SIZE,
+        seeds = [b"reversible", sender.key().as_ref(), recipient.key().as_ref()],
+        bump
+    )]
+    pub reversible_transfer: Account<'info, ReversibleTransfer>,
+    
+    /// The sender account (must sign, funds the hold)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// CHECK: Only recorded in the hold; finalizes once the challenge period ends
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for account creation and funding)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for the sender to reverse a transfer
+#[derive(Accounts)]
+pub struct ReverseTransfer<'info> {
+    /// Reversible transfer being reversed (closed back to the sender)
+    #[account(
+        mut,
+        seeds = [b"reversible", sender.key().as_ref(), recipient.key().as_ref()],
+        bump = reversible_transfer.bump,
+        has_one = sender,
+        has_one = recipient
+    )]
+    pub reversible_transfer: Account<'info, ReversibleTransfer>,
+    
+    /// The sender who funded the hold (must sign)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// CHECK: Verified against the hold; only used for the PDA seeds
+    pub recipient: AccountInfo<'info>,
+    
+    /// Fee configuration naming the treasury (PDA); without one, dust stays with the receiver
+    #[account(
+        seeds = [b"fee_config"],
+        bump = fee_config.bump,
+        has_one = treasury @ TransferError::InvalidRecipient
+    )]
+    pub fee_config: Option<Account<'info, FeeConfig>>,
+    
+    /// CHECK: Verified against the fee configuration; receives any dust left in the hold
+    #[account(mut)]
+    pub treasury: Option<AccountInfo<'info>>,
+}
+
+/// Accounts required for the recipient to finalize a reversible transfer
+#[derive(Accounts)]
+pub struct FinalizeTransfer<'info> {
+    /// Reversible transfer being finalized (closed back to the sender)
+    #[account(
+        mut,
+        seeds = [b"reversible", sender.key().as_ref(), recipient.key().as_ref()],
+        bump = reversible_transfer.bump,
+        has_one = sender,
+        has_one = recipient
+    )]
+    pub reversible_transfer: Account<'info, ReversibleTransfer>,
+    
+    /// CHECK: Verified against the hold; receives the rent
+    #[account(mut)]
+    pub sender: AccountInfo<'info>,
+    
+    /// The recipient finalizing the transfer (must sign)
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+    
+    /// Fee configuration naming the treasury (PDA); without one, dust stays with the receiver
+    #[account(
+        seeds = [b"fee_config"],
+        bump = fee_config.bump,
+        has_one = treasury @ TransferError::InvalidRecipient
+    )]
+    pub fee_config: Option<Account<'info, FeeConfig>>,
+    
+    /// CHECK: Verified against the fee configuration; receives any dust left in the hold
+    #[account(mut)]
+    pub treasury: Option<AccountInfo<'info>>,
+}
+
+/// Accounts required for a SOL transfer checked against the recipient's policy
+#[derive(Accounts)]
+pub struct TransferWithPolicy<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Stats of the recipient, holding its acceptance policy (PDA)
+    #[account(
+        mut,
+        seeds = [b"recipient_stats", recipient.key().as_ref()],
+        bump = recipient_stats.bump
+    )]
+    pub recipient_stats: Account<'info, RecipientStats>,
+    
+    /// The sender account (must sign the transaction)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// The recipient account (receives SOL)
+    /// CHECK: This account is only used to receive SOL, no validation needed
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to collect SOL from several sources
+///
+/// Sources are passed as writable, signing `remaining_accounts`, each followed
+/// by its guardian setting PDA.
+#[derive(Accounts)]
+pub struct CollectSol<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// The recipient account (receives SOL from every source)
+    /// CHECK: This account is only used to receive SOL, no validation needed
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to read a sender's average interval between transfers
+#[derive(Accounts)]
+pub struct GetAverageInterval<'info> {
+    /// Per-sender stats (PDA)
+    #[account(
+        seeds = [b"sender_stats", sender_stats.sender.as_ref()],
+        bump = sender_stats.bump
+    )]
+    pub sender_stats: Account<'info, SenderStats>,
+}
+
+/// Accounts required for a SOL transfer recorded in the sender's bloom filter
+#[derive(Accounts)]
+pub struct TransferWithBloom<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Sender's recipient bloom filter (PDA, created on first use)
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = RecipientBloom::SIZE,
+        seeds = [b"recipient_bloom", sender.key().as_ref()],
+        bump
+    )]
+    pub bloom: Account<'info, RecipientBloom>,
+    
+    /// The sender account (must sign the transaction)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// The recipient account (receives SOL)
+    /// CHECK: This account is only used to receive SOL, no validation needed
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to query a sender's recipient bloom filter
+#[derive(Accounts)]
+pub struct PossiblyPaid<'info> {
+    /// Sender's recipient bloom filter (PDA)
+    #[account(seeds = [b"recipient_bloom", bloom.sender.as_ref()], bump = bloom.bump)]
+    pub bloom: Account<'info, RecipientBloom>,
+}
+
+/// Accounts required to create the transfer journal
+#[derive(Accounts)]
+pub struct InitJournal<'info> {
+    /// Transfer journal (PDA)
+    #[account(
+        init,
+        payer = payer,
+        space = Journal::SIZE,
+        seeds = [b"journal"],
+        bump
+    )]
+    pub journal: Account<'info, Journal>,
+    
+    /// Pays for the journal header
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for a SOL transfer appended to the journal
+#[derive(Accounts)]
+pub struct TransferWithJournal<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Transfer journal (PDA), grown by one entry
+    #[account(
+        mut,
+        seeds = [b"journal"],
+        bump = journal.bump,
+        realloc = Journal::space_for((journal.cursor + 1).min(Journal::MAX_ENTRIES)),
+        realloc::payer = sender,
+        realloc::zero = false
+    )]
+    pub journal: Account<'info, Journal>,
+    
+    /// The sender account (must sign, pays the journal's extra rent)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// The recipient account (receives SOL)
+    /// CHECK: This account is only used to receive SOL, no validation needed
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to read a journal entry
+#[derive(Accounts)]
+pub struct ReadJournalEntry<'info> {
+    /// Transfer journal (PDA)
+    #[account(seeds = [b"journal"], bump = journal.bump)]
+    pub journal: Account<'info, Journal>,
+}
+
+/// Accounts required to deposit SOL into a vault
+#[derive(Accounts)]
+pub struct DepositToVault<'info> {
+    /// Program state account gating transfers (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Owner's vault (system-owned PDA)
+    #[account(mut, seeds = [b"sol_vault", owner.key().as_ref()], bump)]
+    pub vault: SystemAccount<'info>,
+    
+    /// Owner of the vault (must sign, pays the deposit)
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the owner; may not exist
+    #[account(seeds = [b"pause_exempt", owner.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the owner; may not exist
+    #[account(seeds = [b"guardian", owner.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to withdraw SOL from a vault
+#[derive(Accounts)]
+#[instruction(amount: u64, bump: u8)]
+pub struct WithdrawFromVault<'info> {
+    /// Owner's vault (system-owned PDA); the bump is checked to be canonical in the handler
+    #[account(mut, seeds = [b"sol_vault", owner.key().as_ref()], bump = bump)]
+    pub vault: SystemAccount<'info>,
+    
+    /// Owner of the vault (must sign, receives the SOL)
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for a SOL transfer counted against the sender's daily cap
+#[derive(Accounts)]
+pub struct TransferSolDailyCapped<'info> {
+    /// Program state account holding the daily cap (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// Sender's spend in the current UTC day (PDA, created on first use)
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = SenderDailySpend::SIZE,
+        seeds = [b"sender_daily", sender.key().as_ref()],
+        bump
+    )]
+    pub sender_daily: Account<'info, SenderDailySpend>,
+    
+    /// The sender account (must sign the transaction)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// The recipient account (receives SOL)
+    /// CHECK: This account is only used to receive SOL, no validation needed
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Priority marker PDA for the recipient; may not exist
+    #[account(seeds = [b"priority_recipient", recipient.key().as_ref()], bump)]
+    pub priority_recipient: UncheckedAccount<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for a SOL transfer guarded by the balance fraction
+#[derive(Accounts)]
+pub struct TransferSolGuarded<'info> {
+    /// Program state account holding the maximum fraction (PDA)
+    #[account(mut, seeds = [b"program_state"], bump)]
+    pub state: Account<'info, ProgramState>,
+    
+    /// The sender account (must sign the transaction)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// The recipient account (receives SOL)
+    /// CHECK: This account is only used to receive SOL, no validation needed
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    /// CHECK: Pause exemption marker PDA for the sender; may not exist
+    #[account(seeds = [b"pause_exempt", sender.key().as_ref()], bump)]
+    pub pause_exempt: UncheckedAccount<'info>,
+    
+    /// CHECK: Guardian setting PDA for the sender; may not exist
+    #[account(seeds = [b"guardian", sender.key().as_ref()], bump)]
+    pub guardian_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar, used to check for relayer signatures
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    /// Solana System Program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
 }
@@ -4,15 +4,54 @@
 //! Built with the Anchor framework.
 //!
 //! ## Modules
+//! - `ed25519` - Checks of Ed25519 program signature verifications
 //! - `errors` - Custom error codes
+//! - `events` - Log formats for indexers
 //! - `state` - Account state structures  
 //! - `instructions` - Instruction handlers
+//! - `token` - Minimal SPL Token CPI helpers
+//!
+//! ## Transfer gate
+//! Every instruction that moves SOL out of a signer's wallet first goes
+//! through the same checks, so no variant bypasses them:
+//! - the configuration is finalized (`NotInitialized`)
+//! - transfers are not paused, unless the sender holds a pause exemption
+//!   (`ProgramPaused`)
+//! - the time is within business hours (`OutsideBusinessHours`)
+//! - when a sole signer is required, nobody but the sender and the
+//!   instruction's own co-signers signs the transaction's instructions
+//!   (`RelayerNotAllowed`)
+//! - recipients are not executable, unless that is allowed (`RecipientIsProgram`)
+//! - when memos are required, the transaction carries one (`MemoRequired`)
+//! - senders with a guardian move SOL only through `transfer_with_guardian`
+//!   (`GuardianRequired`)
+//! - the transaction stays within `max_transfers_per_tx`, counting each
+//!   payment of a batch (`TooManyTransfersInTransaction`); `transfer_sol_batched`
+//!   counts against the sender's `SenderStats` instead, so each sender gets
+//!   the cap to themselves there
+//!
+//! `refund_transfer` goes through the gate too, but a refund is let through
+//! a pause and past the refunding recipient's guardian.
+//!
+//! Each movement is then recorded in the `ProgramState` totals and checksum,
+//! so it counts towards the volume milestone; `transfer_sol_batched` defers
+//! this to `flush_stats`.
+//!
+//! ## Closing accounts
+//! Every account the program closes returns its rent to the party that paid
+//! it. These instructions optionally take the fee configuration and its
+//! treasury: when both are passed, dust above the rent goes to the treasury,
+//! otherwise everything goes back to the payer, so closing never waits on
+//! `set_fee`.
 
 use anchor_lang::prelude::*;
 
+pub mod ed25519;
 pub mod errors;
+pub mod events;
 pub mod instructions;
 pub mod state;
+pub mod token;
 
 use instructions::*;
 
@@ -28,21 +67,57 @@ pub mod coin_transfer {
     /// Initialize the program
     /// 
     /// Creates a program state account to track transfer statistics.
-    /// Should only be called once by the program authority.
+    /// Should only be called once by the program authority. Transfers stay
+    /// disabled until the configuration is finalized with `finalize_config`.
     pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
         instructions::handle_initialize(ctx)
     }
 
+    /// Grow a program state account created with the v1 layout (authority only)
+    /// 
+    /// Appends `ProgramState::V2_EXTENSION` zeroed bytes, topping up rent from
+    /// the authority. A no-op on accounts that already use the current layout.
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the program authority
+    /// * `InvalidConfig` - If the account has neither the v1 nor the current size
+    pub fn migrate_state(ctx: Context<MigrateState>) -> Result<()> {
+        instructions::handle_migrate_state(ctx)
+    }
+
+    /// Validate the settings and enable transfers (authority only)
+    /// 
+    /// Call once the program is configured after `initialize`; transfers are
+    /// rejected with `NotInitialized` until then.
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the program authority
+    /// * `InvalidConfig` - If a required setting is missing or inconsistent
+    pub fn finalize_config(ctx: Context<UpdateState>) -> Result<()> {
+        instructions::handle_finalize_config(ctx)
+    }
+
     /// Transfer SOL from sender to recipient
     /// 
     /// # Arguments
     /// * `ctx` - The context containing sender, recipient, and system program
     /// * `amount` - The amount of lamports (1 SOL = 1,000,000,000 lamports)
     /// 
+    /// # Returns
+    /// * The sender's balance in lamports after the transfer (also emitted
+    ///   in the `TransferExecuted` event)
+    /// 
     /// # Errors
     /// * `InvalidAmount` - If amount is 0
     /// * `InsufficientFunds` - If sender doesn't have enough SOL
-    pub fn transfer_sol(ctx: Context<TransferSol>, amount: u64) -> Result<()> {
+    /// * `NotInitialized` - If the configuration has not been finalized
+    /// * `ProgramPaused` - If transfers are paused and the sender is not exempt
+    /// * `OutsideBusinessHours` - If called outside business hours by a non-authority
+    /// * `RelayerNotAllowed` - If a sole signer is required and someone else signed
+    /// * `TooManyTransfersInTransaction` - If the transaction exceeds the per-transaction cap
+    /// * `RecipientIsProgram` - If the recipient is executable and that isn't allowed
+    /// * `MemoRequired` - If memos are required and the transaction carries none
+    pub fn transfer_sol(ctx: Context<TransferSol>, amount: u64) -> Result<u64> {
         instructions::handle_transfer_sol(ctx, amount)
     }
 
@@ -56,4 +131,1753 @@ pub mod coin_transfer {
     pub fn get_balance(ctx: Context<GetBalance>) -> Result<u64> {
         instructions::handle_get_balance(ctx)
     }
+
+    /// Escrow SOL until a designated arbiter resolves the transfer
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the escrow, sender, recipient and arbiter
+    /// * `amount` - The amount of lamports to escrow
+    /// * `timeout_secs` - Seconds the arbiter has before anyone may refund the sender
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InvalidTimeout` - If `timeout_secs` is not positive
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    pub fn transfer_conditional(
+        ctx: Context<TransferConditional>,
+        amount: u64,
+        timeout_secs: i64,
+    ) -> Result<()> {
+        instructions::handle_transfer_conditional(ctx, amount, timeout_secs)
+    }
+
+    /// Resolve a conditional escrow
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the escrow and the signing arbiter
+    /// * `success` - `true` releases to the recipient, `false` refunds the sender
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the escrow's arbiter
+    /// * `InvalidRecipient` - If `treasury` is not the configured treasury
+    pub fn resolve_conditional(ctx: Context<ResolveConditional>, success: bool) -> Result<()> {
+        instructions::handle_resolve_conditional(ctx, success)
+    }
+
+    /// Refund the sender of a conditional escrow the arbiter never resolved
+    /// 
+    /// Permissionless once the escrow's deadline has passed.
+    /// 
+    /// # Errors
+    /// * `EscrowNotExpired` - If the deadline has not been reached
+    /// * `InvalidRecipient` - If `treasury` is not the configured treasury
+    pub fn resolve_conditional_default(ctx: Context<ResolveConditionalDefault>) -> Result<()> {
+        instructions::handle_resolve_conditional_default(ctx)
+    }
+
+    /// Lock SOL for a recipient until a given time
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the timelock, sender and recipient
+    /// * `amount` - The amount of lamports to lock
+    /// * `unlock_ts` - Unix timestamp from which the funds can be released
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    /// * `InvalidTimeout` - If `unlock_ts` is not in the future
+    /// * `LockTooLong` - If `unlock_ts` is further away than `max_lock_secs`
+    pub fn escrow_timelock(
+        ctx: Context<EscrowTimelock>,
+        amount: u64,
+        unlock_ts: i64,
+    ) -> Result<()> {
+        instructions::handle_escrow_timelock(ctx, amount, unlock_ts)
+    }
+
+    /// Release an unlocked timelock to its recipient (callable by anyone)
+    /// 
+    /// # Errors
+    /// * `EscrowNotExpired` - If the unlock time has not been reached
+    /// * `InvalidRecipient` - If `treasury` is not the configured treasury
+    pub fn release_timelock(ctx: Context<ReleaseTimelock>) -> Result<()> {
+        instructions::handle_release_timelock(ctx)
+    }
+
+    /// Set the UTC business hours during which transfers are accepted (authority only)
+    /// 
+    /// Transfers are accepted from `open_hour` up to (not including) `close_hour`,
+    /// wrapping past midnight when `open_hour > close_hour`. Equal hours disable
+    /// the window. The authority may transfer at any time.
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the program authority
+    /// * `ConfigCooldownActive` - If the previous config change is within the cooldown
+    /// * `InvalidConfig` - If either hour is not in 0-23
+    pub fn set_business_hours(
+        ctx: Context<UpdateState>,
+        open_hour: u8,
+        close_hour: u8,
+    ) -> Result<()> {
+        instructions::handle_set_business_hours(ctx, open_hour, close_hour)
+    }
+
+    /// Set the longest a timelock escrow may lock funds (authority only)
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the program state and authority
+    /// * `max_lock_secs` - Maximum lock duration in seconds (0 disables the limit)
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the program authority
+    /// * `ConfigCooldownActive` - If the previous config change is within the cooldown
+    pub fn set_max_lock_secs(ctx: Context<UpdateState>, max_lock_secs: u32) -> Result<()> {
+        instructions::handle_set_max_lock_secs(ctx, max_lock_secs)
+    }
+
+    /// Set the fallback recipient for bounced transfers (authority only)
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the program state and authority
+    /// * `fallback` - Address that receives transfers aimed at frozen recipients
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the program authority
+    /// * `ConfigCooldownActive` - If the previous config change is within the cooldown
+    pub fn set_fallback_recipient(ctx: Context<UpdateState>, fallback: Pubkey) -> Result<()> {
+        instructions::handle_set_fallback_recipient(ctx, fallback)
+    }
+
+    /// Freeze a recipient (authority only)
+    pub fn freeze_recipient(ctx: Context<FreezeRecipient>) -> Result<()> {
+        instructions::handle_freeze_recipient(ctx)
+    }
+
+    /// Unfreeze a previously frozen recipient (authority only)
+    pub fn unfreeze_recipient(ctx: Context<UnfreezeRecipient>) -> Result<()> {
+        instructions::handle_unfreeze_recipient(ctx)
+    }
+
+    /// Exempt a sender from the global pause (authority only)
+    pub fn add_pause_exemption(ctx: Context<AddPauseExemption>) -> Result<()> {
+        instructions::handle_add_pause_exemption(ctx)
+    }
+
+    /// Remove a sender's pause exemption (authority only)
+    pub fn remove_pause_exemption(ctx: Context<RemovePauseExemption>) -> Result<()> {
+        instructions::handle_remove_pause_exemption(ctx)
+    }
+
+    /// Transfer SOL, falling back to the configured recipient if needed
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing sender, recipient, freeze marker and fallback
+    /// * `amount` - The amount of lamports
+    /// * `allow_fallback` - Route to the fallback instead of failing when the recipient is frozen
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    /// * `RecipientFrozen` - If the recipient is frozen and fallback is not allowed
+    /// * `InvalidRecipient` - If the fallback account doesn't match the configured one
+    pub fn transfer_sol_with_fallback(
+        ctx: Context<TransferSolWithFallback>,
+        amount: u64,
+        allow_fallback: bool,
+    ) -> Result<()> {
+        instructions::handle_transfer_sol_with_fallback(ctx, amount, allow_fallback)
+    }
+
+    /// Transfer SOL with a recipient nonce to enforce payment ordering
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing sender, recipient and recipient nonce
+    /// * `amount` - The amount of lamports
+    /// * `expected_nonce` - The recipient's current nonce; incremented on success
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    /// * `InvalidNonce` - If `expected_nonce` is stale or ahead
+    pub fn transfer_sol_ordered(
+        ctx: Context<TransferSolOrdered>,
+        amount: u64,
+        expected_nonce: u64,
+    ) -> Result<()> {
+        instructions::handle_transfer_sol_ordered(ctx, amount, expected_nonce)
+    }
+
+    /// Transfer SOL, recording stats in the sender's PDA instead of `ProgramState`
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing sender, recipient and sender stats
+    /// * `amount` - The amount of lamports
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    pub fn transfer_sol_batched(ctx: Context<TransferSolBatched>, amount: u64) -> Result<()> {
+        instructions::handle_transfer_sol_batched(ctx, amount)
+    }
+
+    /// Flush a sender's accumulated stats into the global totals
+    /// 
+    /// Permissionless, so any crank can keep `ProgramState` current.
+    pub fn flush_stats(ctx: Context<FlushStats>) -> Result<()> {
+        instructions::handle_flush_stats(ctx)
+    }
+
+    /// Configure the transfer fee (authority only)
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the program state, fee config and authority
+    /// * `fee_bps` - Fee in basis points of the transferred amount
+    /// * `burn_bps` - Share of the fee burned, in basis points of the fee
+    /// * `treasury` - Owner of the accounts receiving the treasury share
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the program authority
+    /// * `ConfigCooldownActive` - If the previous config change is within the cooldown
+    /// * `InvalidFeeConfig` - If either basis point value exceeds 10000
+    pub fn set_fee(
+        ctx: Context<SetFee>,
+        fee_bps: u16,
+        burn_bps: u16,
+        treasury: Pubkey,
+    ) -> Result<()> {
+        instructions::handle_set_fee(ctx, fee_bps, burn_bps, treasury)
+    }
+
+    /// Transfer SPL tokens, charging a fee split between treasury and burn
+    /// 
+    /// Fractions of a unit lost to rounding accrue in `FeeConfig::pending_fees`
+    /// and are charged as a one-unit fee once they add up to a whole unit.
+    /// Once `lifetime_fee_cap` worth of fees has been collected, transfers are fee-free.
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the token accounts, mint, fee config and state
+    /// * `amount` - Gross amount in token base units; the recipient gets it minus the fee
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InsufficientFunds` - If the sender's token balance is too low
+    /// * `InvalidTokenAccount` - If a token account has the wrong mint or owner
+    /// * `ColdTokenRequired` - If the fee crosses the hot treasury cap and no cold
+    ///   wallet token account was provided
+    /// * `RebateTokenRequired` - If the transfer is rebated and no rebate vault was provided
+    pub fn transfer_token_with_fee(ctx: Context<TransferTokenWithFee>, amount: u64) -> Result<()> {
+        instructions::handle_transfer_token_with_fee(ctx, amount)
+    }
+
+    /// Transfer SOL, logging a compact binary record instead of text logs
+    /// 
+    /// See [`events::CompactTransfer`] for the byte layout.
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    pub fn transfer_sol_compact(ctx: Context<TransferSol>, amount: u64) -> Result<()> {
+        instructions::handle_transfer_sol_compact(ctx, amount)
+    }
+
+    /// Configure the fee discount for well-funded senders (authority only)
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the program state, fee config and authority
+    /// * `discount_min_balance` - Sender lamports required for the discount (0 disables it)
+    /// * `discount_fee_bps` - Fee in basis points charged to qualifying senders
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the program authority
+    /// * `ConfigCooldownActive` - If the previous config change is within the cooldown
+    /// * `InvalidFeeConfig` - If `discount_fee_bps` exceeds 10000
+    pub fn set_fee_discount(
+        ctx: Context<UpdateFeeConfig>,
+        discount_min_balance: u64,
+        discount_fee_bps: u16,
+    ) -> Result<()> {
+        instructions::handle_set_fee_discount(ctx, discount_min_balance, discount_fee_bps)
+    }
+
+    /// Configure the fee rebate for large token transfers (authority only)
+    /// 
+    /// Transfers above `rebate_threshold` are charged the full fee, and
+    /// `rebate_bps` of it is then paid back to the sender out of the rebate
+    /// vault, a token account of the mint owned by the fee config PDA that the
+    /// authority keeps funded.
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the program state, fee config and authority
+    /// * `rebate_threshold` - Base units a transfer must exceed to be rebated (0 disables it)
+    /// * `rebate_bps` - Share of the fee rebated, in basis points of the fee
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the program authority
+    /// * `ConfigCooldownActive` - If the previous config change is within the cooldown
+    /// * `InvalidFeeConfig` - If `rebate_bps` exceeds 10000
+    pub fn set_fee_rebate(
+        ctx: Context<UpdateFeeConfig>,
+        rebate_threshold: u64,
+        rebate_bps: u16,
+    ) -> Result<()> {
+        instructions::handle_set_fee_rebate(ctx, rebate_threshold, rebate_bps)
+    }
+
+    /// Configure the hot treasury cap and the cold wallet fees above it go to (authority only)
+    /// 
+    /// Whenever a fee would push the treasury token account past the cap, the
+    /// excess is sent to the cold wallet's token account in the same transfer.
+    /// Balances already above the cap are left alone, as the program cannot
+    /// move tokens out of the treasury.
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the program state, fee config and authority
+    /// * `hot_treasury_cap` - Most the treasury token account may hold (0 disables sweeping)
+    /// * `cold_wallet` - Owner of the token accounts receiving swept fees
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the program authority
+    /// * `ConfigCooldownActive` - If the previous config change is within the cooldown
+    /// * `InvalidFeeConfig` - If a cap is set without a cold wallet
+    pub fn set_cold_sweep(
+        ctx: Context<UpdateFeeConfig>,
+        hot_treasury_cap: u64,
+        cold_wallet: Pubkey,
+    ) -> Result<()> {
+        instructions::handle_set_cold_sweep(ctx, hot_treasury_cap, cold_wallet)
+    }
+
+    /// Transfer SOL, creating a `TransferRecord` linked to the sender's previous one
+    /// and updating the Merkle root
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing sender, recipient, record and accumulator
+    /// * `amount` - The amount of lamports
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    /// * `AccumulatorFull` - If the accumulator tree has no room left
+    /// * `TooManyRecords` - If the sender already has `max_records` open records
+    pub fn transfer_sol_recorded(ctx: Context<TransferSolRecorded>, amount: u64) -> Result<()> {
+        instructions::handle_transfer_sol_recorded(ctx, amount)
+    }
+
+    /// Set how many transfer records a sender may keep open (authority only)
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the program state and authority
+    /// * `max_records` - Maximum open records per sender (0 disables the cap)
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the program authority
+    /// * `ConfigCooldownActive` - If the previous config change is within the cooldown
+    pub fn set_max_records(ctx: Context<UpdateState>, max_records: u32) -> Result<()> {
+        instructions::handle_set_max_records(ctx, max_records)
+    }
+
+    /// Close one of the sender's transfer records, freeing a slot under the cap
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the record's sender
+    /// * `InvalidRecipient` - If `treasury` is not the configured treasury
+    pub fn close_record(ctx: Context<CloseRecord>) -> Result<()> {
+        instructions::handle_close_record(ctx)
+    }
+
+    /// Transfer SOL with an explicit amount denomination
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing sender, recipient, and system program
+    /// * `amount` - The amount, in the unit selected by `denom`
+    /// * `denom` - `0` for lamports, `1` for whole SOL
+    /// 
+    /// # Errors
+    /// * `InvalidDenomination` - If `denom` is not 0 or 1
+    /// * `InvalidAmount` - If the amount is 0 or overflows when converted to lamports
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    pub fn transfer_sol_denominated(
+        ctx: Context<TransferSol>,
+        amount: u64,
+        denom: u8,
+    ) -> Result<()> {
+        instructions::handle_transfer_sol_denominated(ctx, amount, denom)
+    }
+
+    /// Set the fixed amounts accepted by `transfer_sol_fixed` (authority only)
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the program state, list and authority
+    /// * `amounts` - Permitted amounts in lamports (at most 16)
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the program authority
+    /// * `ConfigCooldownActive` - If the previous config change is within the cooldown
+    /// * `TooManyAllowedAmounts` - If more than 16 amounts are given
+    /// * `InvalidAmount` - If any amount is 0
+    pub fn set_allowed_amounts(ctx: Context<SetAllowedAmounts>, amounts: Vec<u64>) -> Result<()> {
+        instructions::handle_set_allowed_amounts(ctx, amounts)
+    }
+
+    /// Transfer SOL in one of the configured fixed amounts
+    /// 
+    /// # Errors
+    /// * `AmountNotAllowed` - If amount is not in the allowed list
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    pub fn transfer_sol_fixed(ctx: Context<TransferSolFixed>, amount: u64) -> Result<()> {
+        instructions::handle_transfer_sol_fixed(ctx, amount)
+    }
+
+    /// Transfer SOL and record a self-reported priority fee for analytics
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing sender, recipient and priority fee stats
+    /// * `amount` - The amount of lamports
+    /// * `priority_fee` - Priority fee the client paid; recorded only, never charged
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    pub fn transfer_sol_with_priority_fee(
+        ctx: Context<TransferSolWithPriorityFee>,
+        amount: u64,
+        priority_fee: u64,
+    ) -> Result<()> {
+        instructions::handle_transfer_sol_with_priority_fee(ctx, amount, priority_fee)
+    }
+
+    /// Register the signing recipient with a routing category
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the recipient and its stats account
+    /// * `category` - Category tag senders can require
+    pub fn register_recipient(ctx: Context<RegisterRecipient>, category: u8) -> Result<()> {
+        instructions::handle_register_recipient(ctx, category)
+    }
+
+    /// Set the smallest transfer the signing recipient accepts, guarding against dust spam
+    /// 
+    /// Enforced by the instructions that load the recipient's stats
+    /// (`transfer_to_category`, `transfer_kyc`).
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the recipient and its stats account
+    /// * `min_incoming` - Minimum amount in lamports (0 accepts any amount)
+    pub fn set_min_incoming(ctx: Context<RegisterRecipient>, min_incoming: u64) -> Result<()> {
+        instructions::handle_set_min_incoming(ctx, min_incoming)
+    }
+
+    /// Transfer SOL to a recipient registered under the expected category
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing sender, recipient and recipient stats
+    /// * `amount` - The amount of lamports
+    /// * `expected_category` - Category the recipient must have registered
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `CategoryMismatch` - If the recipient's category differs
+    /// * `BelowRecipientMinimum` - If amount is below the recipient's minimum
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    pub fn transfer_to_category(
+        ctx: Context<TransferToCategory>,
+        amount: u64,
+        expected_category: u8,
+    ) -> Result<()> {
+        instructions::handle_transfer_to_category(ctx, amount, expected_category)
+    }
+
+    /// Mark a recipient as KYC-verified or unverified (authority only)
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the program state, recipient stats and authority
+    /// * `verified` - Whether the recipient passed KYC
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the program authority
+    /// * `ConfigCooldownActive` - If the previous config change is within the cooldown
+    pub fn set_kyc_verified(ctx: Context<SetKycVerified>, verified: bool) -> Result<()> {
+        instructions::handle_set_kyc_verified(ctx, verified)
+    }
+
+    /// Transfer SOL only to a recipient the authority has KYC-verified
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing sender, recipient and recipient stats
+    /// * `amount` - The amount of lamports
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `RecipientNotVerified` - If the recipient is not KYC-verified
+    /// * `BelowRecipientMinimum` - If amount is below the recipient's minimum
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    pub fn transfer_kyc(ctx: Context<TransferKyc>, amount: u64) -> Result<()> {
+        instructions::handle_transfer_kyc(ctx, amount)
+    }
+
+    /// Refund part or all of a recorded transfer (signed by its recipient)
+    /// 
+    /// Partial refunds may be repeated until the original amount is returned.
+    /// Refunds go through while transfers are paused and are never held for
+    /// the recipient's guardian.
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the transfer record, recipient and sender
+    /// * `amount` - The amount of lamports to return
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `AlreadyRefunded` - If the refund would exceed the original amount
+    /// * `Unauthorized` - If the signer is not the record's recipient
+    pub fn refund_transfer(ctx: Context<RefundTransfer>, amount: u64) -> Result<()> {
+        instructions::handle_refund_transfer(ctx, amount)
+    }
+
+    /// Pause or unpause transfers (authority only)
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the program authority
+    pub fn set_paused(ctx: Context<UpdateState>, paused: bool) -> Result<()> {
+        instructions::handle_set_paused(ctx, paused)
+    }
+
+    /// Set the total volume at which transfers auto-pause (authority only)
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the program state and authority
+    /// * `milestone_volume` - Total volume in lamports that triggers the pause (0 disables it)
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the program authority
+    /// * `ConfigCooldownActive` - If the previous config change is within the cooldown
+    pub fn set_milestone_volume(ctx: Context<UpdateState>, milestone_volume: u64) -> Result<()> {
+        instructions::handle_set_milestone_volume(ctx, milestone_volume)
+    }
+
+    /// Set the SOL/USD rate used for USD volume tracking (authority only)
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the program state and authority
+    /// * `sol_usd_rate` - Price of 1 SOL in micro-USD (USD * 10^6)
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the program authority
+    /// * `ConfigCooldownActive` - If the previous config change is within the cooldown
+    pub fn set_sol_usd_rate(ctx: Context<UpdateState>, sol_usd_rate: u64) -> Result<()> {
+        instructions::handle_set_sol_usd_rate(ctx, sol_usd_rate)
+    }
+
+    /// Get the total transfer volume in USD
+    /// 
+    /// # Returns
+    /// * The USD volume in micro-USD
+    pub fn get_usd_volume(ctx: Context<GetState>) -> Result<u64> {
+        instructions::handle_get_usd_volume(ctx)
+    }
+
+    /// Transfer SOL and create a receipt PDA the recipient can derive
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing sender, recipient and receipt
+    /// * `amount` - The amount of lamports
+    /// * `nonce` - Sender-chosen value making the receipt address unique
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    pub fn transfer_with_receipt(
+        ctx: Context<TransferWithReceipt>,
+        amount: u64,
+        nonce: u64,
+    ) -> Result<()> {
+        instructions::handle_transfer_with_receipt(ctx, amount, nonce)
+    }
+
+    /// Check whether a receipt exists for the given transfer details
+    /// 
+    /// # Returns
+    /// * `true` if a matching receipt exists at the derived address
+    pub fn verify_receipt(
+        ctx: Context<VerifyReceipt>,
+        sender: Pubkey,
+        recipient: Pubkey,
+        amount: u64,
+        nonce: u64,
+    ) -> Result<bool> {
+        instructions::handle_verify_receipt(ctx, sender, recipient, amount, nonce)
+    }
+
+    /// Settle mutual debts between two parties, moving only the net amount
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing both parties (both must sign)
+    /// * `a_to_b` - Lamports party A owes party B
+    /// * `b_to_a` - Lamports party B owes party A
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If both amounts are 0
+    /// * `InsufficientFunds` - If the net payer doesn't have enough SOL
+    /// * `NotInitialized` - If the configuration has not been finalized
+    /// * `ProgramPaused` - If transfers are paused
+    pub fn net_settle(ctx: Context<NetSettle>, a_to_b: u64, b_to_a: u64) -> Result<()> {
+        instructions::handle_net_settle(ctx, a_to_b, b_to_a)
+    }
+
+    /// Configure loyalty point accrual and redemption (authority only)
+    /// 
+    /// The loyalty configuration account doubles as the redemption vault:
+    /// fund it with a plain SOL transfer to its address.
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the program state, loyalty config and authority
+    /// * `points_per_lamport` - Points earned per lamport transferred
+    /// * `redeem_points_per_lamport` - Points spent per lamport redeemed
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the program authority
+    /// * `ConfigCooldownActive` - If the previous config change is within the cooldown
+    /// * `InvalidConfig` - If `redeem_points_per_lamport` is 0
+    pub fn set_loyalty_config(
+        ctx: Context<SetLoyaltyConfig>,
+        points_per_lamport: u64,
+        redeem_points_per_lamport: u64,
+    ) -> Result<()> {
+        instructions::handle_set_loyalty_config(ctx, points_per_lamport, redeem_points_per_lamport)
+    }
+
+    /// Transfer SOL and credit the sender with loyalty points
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing sender, recipient and the points account
+    /// * `amount` - The amount of lamports
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    /// * `NotInitialized` - If the configuration has not been finalized
+    /// * `ProgramPaused` - If transfers are paused
+    pub fn transfer_with_points(ctx: Context<TransferWithPoints>, amount: u64) -> Result<()> {
+        instructions::handle_transfer_with_points(ctx, amount)
+    }
+
+    /// Redeem loyalty points for SOL from the loyalty vault
+    /// 
+    /// Only points backing whole lamports are spent; the remainder stays.
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the points account, its owner and the vault
+    /// * `points` - Number of points to redeem
+    /// 
+    /// # Errors
+    /// * `InsufficientPoints` - If the owner holds fewer points
+    /// * `InvalidAmount` - If the points are worth less than one lamport
+    /// * `InsufficientFunds` - If the vault can't cover the payout
+    pub fn redeem_points(ctx: Context<RedeemPoints>, points: u64) -> Result<()> {
+        instructions::handle_redeem_points(ctx, points)
+    }
+
+    /// Transfer SOL and aggregate it under a self-reported region
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing sender, recipient and the region stats
+    /// * `amount` - The amount of lamports
+    /// * `region` - Region code in `0..REGION_COUNT`
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    /// * `InvalidRegion` - If the region code is out of range
+    pub fn transfer_with_region(
+        ctx: Context<TransferWithRegion>,
+        amount: u64,
+        region: u8,
+    ) -> Result<()> {
+        instructions::handle_transfer_with_region(ctx, amount, region)
+    }
+
+    /// Atomically swap SOL for SPL tokens between two co-signing parties
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing both parties, the mint and their token accounts
+    /// * `sol_amount` - Lamports the buyer pays the seller
+    /// * `token_amount` - Tokens (base units) the seller pays the buyer
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If either amount is 0
+    /// * `InsufficientFunds` - If either party can't cover its leg
+    /// * `InvalidTokenAccount` - If a token account has the wrong mint
+    /// * `Unauthorized` - If the seller doesn't own the source token account
+    pub fn swap_sol_for_token(
+        ctx: Context<SwapSolForToken>,
+        sol_amount: u64,
+        token_amount: u64,
+    ) -> Result<()> {
+        instructions::handle_swap_sol_for_token(ctx, sol_amount, token_amount)
+    }
+
+    /// Deposit SOL into a stream that pays the recipient over time
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the stream, sender and recipient
+    /// * `deposit` - Total lamports to stream
+    /// * `rate_per_sec` - Lamports accruing to the recipient each second
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If the deposit or rate is 0
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    pub fn create_stream(ctx: Context<CreateStream>, deposit: u64, rate_per_sec: u64) -> Result<()> {
+        instructions::handle_create_stream(ctx, deposit, rate_per_sec)
+    }
+
+    /// Withdraw everything accrued on a stream so far (recipient only)
+    /// 
+    /// Accrual is `rate_per_sec` times the seconds since the stream started,
+    /// capped at the deposit.
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the stream's recipient
+    /// * `NothingToWithdraw` - If nothing accrued since the last withdrawal
+    pub fn withdraw_stream(ctx: Context<WithdrawStream>) -> Result<()> {
+        instructions::handle_withdraw_stream(ctx)
+    }
+
+    /// Cancel a stream (sender only)
+    /// 
+    /// Pays the recipient everything accrued but not yet withdrawn, refunds the
+    /// unaccrued remainder to the sender and closes the stream.
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the stream's sender
+    /// * `InvalidRecipient` - If `treasury` is not the configured treasury
+    pub fn cancel_stream(ctx: Context<CancelStream>) -> Result<()> {
+        instructions::handle_cancel_stream(ctx)
+    }
+
+    /// Require transfers to be signed by the sender alone (authority only)
+    /// 
+    /// Enforced by every instruction that moves SOL out of a signer's wallet.
+    /// This is a check on the signers of the transaction's instructions, not on
+    /// its fee payer: a relayer that signs any instruction is rejected, but
+    /// one that only pays the fee, referenced by no instruction, is invisible
+    /// to the program and passes.
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the program state and authority
+    /// * `required` - Whether transactions with other signers are rejected
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the program authority
+    /// * `ConfigCooldownActive` - If the previous config change is within the cooldown
+    pub fn set_require_sole_signer(ctx: Context<UpdateState>, required: bool) -> Result<()> {
+        instructions::handle_set_require_sole_signer(ctx, required)
+    }
+
+    /// Cap how many transfers a single transaction may make through this program (authority only)
+    /// 
+    /// Counts every SOL-moving instruction, whether called directly or through CPI
+    /// by a composing program. `transfer_sol_batched` keeps its count in the
+    /// sender's stats so it never locks the state, capping each sender separately.
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the program state and authority
+    /// * `max_transfers` - Most transfers per transaction (0 disables the cap)
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the program authority
+    /// * `ConfigCooldownActive` - If the previous config change is within the cooldown
+    pub fn set_max_transfers_per_tx(ctx: Context<UpdateState>, max_transfers: u16) -> Result<()> {
+        instructions::handle_set_max_transfers_per_tx(ctx, max_transfers)
+    }
+
+    /// Get the rolling checksum over every recorded transfer
+    /// 
+    /// Each transfer folds `sha256(checksum || sender || recipient || amount)` into
+    /// the previous value, so indexers can replay the transfer log and compare.
+    /// 
+    /// # Returns
+    /// * The current 32-byte checksum
+    pub fn get_checksum(ctx: Context<GetState>) -> Result<[u8; 32]> {
+        instructions::handle_get_checksum(ctx)
+    }
+
+    /// Transfer SOL and emit `FirstTransferBetween` on a pair's first transfer
+    /// 
+    /// A marker PDA per sender-recipient pair records that they have transacted,
+    /// so later transfers between the same pair don't emit the event.
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing sender, recipient and pair marker
+    /// * `amount` - The amount of lamports
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    pub fn transfer_sol_paired(ctx: Context<TransferSolPaired>, amount: u64) -> Result<()> {
+        instructions::handle_transfer_sol_paired(ctx, amount)
+    }
+
+    /// Set the mint that `transfer_token_gated` recipients must hold (authority only)
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the program state, token gate and authority
+    /// * `mint` - Mint recipients must hold a nonzero balance of
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the program authority
+    /// * `ConfigCooldownActive` - If the previous config change is within the cooldown
+    pub fn set_token_gate(ctx: Context<SetTokenGate>, mint: Pubkey) -> Result<()> {
+        instructions::handle_set_token_gate(ctx, mint)
+    }
+
+    /// Transfer SOL only to a recipient holding a token of the mint set by `set_token_gate`
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the token gate, sender, recipient and the recipient's
+    ///   token account
+    /// * `amount` - The amount of lamports
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InvalidTokenAccount` - If the provided account is not an SPL token account
+    /// * `MissingRequiredToken` - If the token account is not the recipient's, has another
+    ///   mint, or is empty
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    pub fn transfer_token_gated(ctx: Context<TransferTokenGated>, amount: u64) -> Result<()> {
+        instructions::handle_transfer_token_gated(ctx, amount)
+    }
+
+    /// Transfer SOL at most once per external event id within a TTL
+    /// 
+    /// A marker PDA seeded by the sender and `event_id` blocks repeats of the
+    /// transfer until it expires; `cleanup_idempotency` then reclaims its rent.
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing sender, recipient and idempotency marker
+    /// * `amount` - The amount of lamports
+    /// * `event_id` - External event id, e.g. a hash of an off-chain order id
+    /// * `ttl_secs` - Seconds during which the event id is rejected as a duplicate
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InvalidTimeout` - If `ttl_secs` is 0
+    /// * `DuplicateEventId` - If the event id was used and has not expired
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    pub fn transfer_idempotent(
+        ctx: Context<TransferIdempotent>,
+        amount: u64,
+        event_id: [u8; 32],
+        ttl_secs: u32,
+    ) -> Result<()> {
+        instructions::handle_transfer_idempotent(ctx, amount, event_id, ttl_secs)
+    }
+
+    /// Close an expired idempotency marker, returning its rent to the sender
+    /// 
+    /// Anyone may call this once the marker's TTL has passed.
+    /// 
+    /// # Errors
+    /// * `MarkerNotExpired` - If the marker's TTL has not passed yet
+    /// * `InvalidRecipient` - If `treasury` is not the configured treasury
+    pub fn cleanup_idempotency(ctx: Context<CleanupIdempotency>) -> Result<()> {
+        instructions::handle_cleanup_idempotency(ctx)
+    }
+
+    /// Configure the linear bonding curve (authority only)
+    /// 
+    /// The `n`-th unit costs `base_price + slope * n` lamports. The curve can
+    /// only be reconfigured while no units are outstanding.
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the program state, curve and authority
+    /// * `base_price` - Price of the first unit in lamports
+    /// * `slope` - Lamports the price rises by per unit outstanding
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the program authority
+    /// * `ConfigCooldownActive` - If the previous config change is within the cooldown
+    /// * `InvalidConfig` - If units are outstanding
+    pub fn set_curve(ctx: Context<SetCurve>, base_price: u64, slope: u64) -> Result<()> {
+        instructions::handle_set_curve(ctx, base_price, slope)
+    }
+
+    /// Buy units from the bonding curve, paying SOL into its reserve
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the curve, buyer and buyer's position
+    /// * `units` - Number of units to buy
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If units is 0
+    /// * `MathOverflow` - If the cost does not fit in a u64
+    /// * `InsufficientFunds` - If the buyer can't pay the cost
+    pub fn buy_curve(ctx: Context<BuyCurve>, units: u64) -> Result<()> {
+        instructions::handle_buy_curve(ctx, units)
+    }
+
+    /// Sell units back to the bonding curve at the prices they were bought for
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the curve, owner and owner's position
+    /// * `units` - Number of units to sell
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If units is 0
+    /// * `InsufficientFunds` - If the owner holds fewer units
+    pub fn sell_curve(ctx: Context<SellCurve>, units: u64) -> Result<()> {
+        instructions::handle_sell_curve(ctx, units)
+    }
+
+    /// Get the price of the next unit on the bonding curve
+    /// 
+    /// # Returns
+    /// * The price in lamports
+    pub fn get_price(ctx: Context<GetCurve>) -> Result<u64> {
+        instructions::handle_get_price(ctx)
+    }
+
+    /// Lock SOL in an escrow claimable by whoever presents the matching secret
+    /// 
+    /// Meant for off-chain handoff, e.g. a link carrying the secret. The secret
+    /// is revealed by the claim transaction, so it should be single-use.
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the sender and escrow
+    /// * `amount` - The amount of lamports
+    /// * `key_hash` - SHA-256 of the secret; also seeds the escrow PDA
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    pub fn escrow_password(
+        ctx: Context<EscrowPassword>,
+        amount: u64,
+        key_hash: [u8; 32],
+    ) -> Result<()> {
+        instructions::handle_escrow_password(ctx, amount, key_hash)
+    }
+
+    /// Claim a password escrow by presenting its secret
+    /// 
+    /// An incorrect secret does not fail the transaction: it is recorded, and
+    /// the escrow refuses further claims for `PASSWORD_CLAIM_COOLDOWN_SECS`.
+    /// On success the escrow closes: its rent returns to the sender and any
+    /// dust above the rent goes to the treasury, if the fee configuration is passed.
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the escrow, claimer, sender and treasury
+    /// * `secret` - Secret whose SHA-256 must equal the escrow's `key_hash`
+    /// 
+    /// # Errors
+    /// * `ClaimRateLimited` - If called during the cooldown after an incorrect secret
+    /// * `InvalidRecipient` - If `treasury` is not the configured treasury
+    pub fn claim_password(ctx: Context<ClaimPassword>, secret: Vec<u8>) -> Result<()> {
+        instructions::handle_claim_password(ctx, secret)
+    }
+
+    /// Transfer SOL and count it in the day's transfer size histogram
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing sender, recipient and the day's histogram
+    /// * `amount` - The amount of lamports
+    /// * `day` - Current UTC day (unix timestamp / 86400); seeds the histogram PDA
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InvalidDay` - If `day` is not the current UTC day
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    pub fn transfer_with_histogram(
+        ctx: Context<TransferWithHistogram>,
+        amount: u64,
+        day: i64,
+    ) -> Result<()> {
+        instructions::handle_transfer_with_histogram(ctx, amount, day)
+    }
+
+    /// Get the transfer counts per size range for a UTC day
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the day's histogram
+    /// * `day` - UTC day (unix timestamp / 86400)
+    /// 
+    /// # Returns
+    /// * Transfers below 0.1, 1 and 10 SOL, and of 10 SOL or more
+    pub fn get_histogram(
+        ctx: Context<GetHistogram>,
+        day: i64,
+    ) -> Result<[u64; state::HISTOGRAM_BUCKETS]> {
+        instructions::handle_get_histogram(ctx, day)
+    }
+
+    /// Set how many prior tracked transfers `transfer_with_history` requires (authority only)
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the program state and authority
+    /// * `min_history` - Required `SenderStats::transfer_count` (0 disables the check)
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the program authority
+    /// * `ConfigCooldownActive` - If the previous config change is within the cooldown
+    pub fn set_min_history(ctx: Context<UpdateState>, min_history: u32) -> Result<()> {
+        instructions::handle_set_min_history(ctx, min_history)
+    }
+
+    /// Transfer SOL only if the sender's stats show enough prior transfers
+    /// 
+    /// History is the transfer count in `SenderStats`, built up through
+    /// `transfer_sol_batched` and this instruction itself.
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing sender, recipient and sender stats
+    /// * `amount` - The amount of lamports
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InsufficientHistory` - If the sender has fewer transfers than `min_history`
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    pub fn transfer_with_history(ctx: Context<TransferWithHistory>, amount: u64) -> Result<()> {
+        instructions::handle_transfer_with_history(ctx, amount)
+    }
+
+    /// Issue an invoice made of line items, payable by anyone
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the issuer and invoice
+    /// * `invoice_id` - Issuer-chosen invoice number
+    /// * `line_items` - Amount of each line item in lamports
+    /// 
+    /// # Errors
+    /// * `TooManyLineItems` - If there are more than `MAX_LINE_ITEMS` items
+    /// * `InvalidAmount` - If there are no items or an item is 0
+    /// * `MathOverflow` - If the total does not fit in a u64
+    pub fn create_invoice(
+        ctx: Context<CreateInvoice>,
+        invoice_id: u64,
+        line_items: Vec<u64>,
+    ) -> Result<()> {
+        instructions::handle_create_invoice(ctx, invoice_id, line_items)
+    }
+
+    /// Pay an invoice with a transfer of exactly its total
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the invoice, payer and issuer
+    /// * `amount` - The amount of lamports; must equal the sum of the line items
+    /// 
+    /// # Errors
+    /// * `InvoiceAlreadyPaid` - If the invoice was already paid
+    /// * `InvoiceUnderpaid` - If amount is below the total
+    /// * `InvoiceOverpaid` - If amount is above the total
+    /// * `InsufficientFunds` - If payer doesn't have enough SOL
+    pub fn pay_invoice(ctx: Context<PayInvoice>, amount: u64) -> Result<()> {
+        instructions::handle_pay_invoice(ctx, amount)
+    }
+
+    /// Transfer SOL to a recipient as wrapped SOL
+    /// 
+    /// The lamports go into the recipient's wSOL token account, and `sync_native`
+    /// makes its token balance reflect them.
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing sender, recipient and the recipient's wSOL account
+    /// * `amount` - The amount of lamports
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InvalidTokenAccount` - If the token account is not the recipient's wSOL account
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    pub fn transfer_as_wsol(ctx: Context<TransferAsWsol>, amount: u64) -> Result<()> {
+        instructions::handle_transfer_as_wsol(ctx, amount)
+    }
+
+    /// Appoint the emergency council that can force-unpause transfers (authority only, once)
+    /// 
+    /// The council is separate from the authority and cannot be replaced, so
+    /// it keeps working if the authority key is compromised.
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the program state, council and authority
+    /// * `members` - Distinct council keys, at most `MAX_EMERGENCY_MEMBERS`
+    /// * `threshold` - Members that must sign an emergency unpause
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the program authority
+    /// * `ConfigCooldownActive` - If the previous config change is within the cooldown
+    /// * `InvalidConfig` - If the members or threshold are invalid
+    pub fn set_emergency_council(
+        ctx: Context<SetEmergencyCouncil>,
+        members: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        instructions::handle_set_emergency_council(ctx, members, threshold)
+    }
+
+    /// Force-unpause transfers with a threshold of emergency council signatures
+    /// 
+    /// Signing council members are passed as remaining accounts.
+    /// 
+    /// # Errors
+    /// * `EmergencyThresholdNotMet` - If fewer than `threshold` members signed
+    pub fn emergency_unpause(ctx: Context<EmergencyUnpause>) -> Result<()> {
+        instructions::handle_emergency_unpause(ctx)
+    }
+
+    /// Register the program notified of the signing recipient's incoming notified transfers
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the recipient and its notification setting
+    /// * `program` - Program invoked after each `transfer_with_notification`
+    /// * `best_effort` - Skip the notification, rather than fail, if the wrong or a
+    ///   non-executable program is provided
+    pub fn set_notification_program(
+        ctx: Context<SetNotificationProgram>,
+        program: Pubkey,
+        best_effort: bool,
+    ) -> Result<()> {
+        instructions::handle_set_notification_program(ctx, program, best_effort)
+    }
+
+    /// Transfer SOL, then CPI into the recipient's registered notification program
+    /// 
+    /// The program is invoked with no accounts and the UTF-8 payload
+    /// `transfer <recipient> <amount>`. A notification program that fails once
+    /// invoked still reverts the transfer, as the runtime cannot recover from a
+    /// failed CPI.
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing sender, recipient and notification accounts
+    /// * `amount` - The amount of lamports
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    /// * `InvalidNotificationProgram` - If the provided program is not the registered
+    ///   one and the recipient did not opt into best effort
+    pub fn transfer_with_notification(
+        ctx: Context<TransferWithNotification>,
+        amount: u64,
+    ) -> Result<()> {
+        instructions::handle_transfer_with_notification(ctx, amount)
+    }
+
+    /// Transfer SOL and add its amount to the moving-average window
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing sender, recipient and the moving-average window
+    /// * `amount` - The amount of lamports
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    pub fn transfer_with_average(ctx: Context<TransferWithAverage>, amount: u64) -> Result<()> {
+        instructions::handle_transfer_with_average(ctx, amount)
+    }
+
+    /// Get the average size of the last `MOVING_AVERAGE_WINDOW` averaged transfers
+    /// 
+    /// Averages over fewer transfers while the window is still filling.
+    /// 
+    /// # Returns
+    /// * The average in lamports, rounded down (0 before any transfer)
+    pub fn get_moving_average(ctx: Context<GetMovingAverage>) -> Result<u64> {
+        instructions::handle_get_moving_average(ctx)
+    }
+
+    /// Allow or forbid sending SOL to executable (program) accounts (authority only)
+    /// 
+    /// Enforced by every instruction that moves SOL out of a signer's wallet;
+    /// forbidden by default.
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the program state and authority
+    /// * `allowed` - Whether executable recipients are accepted
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the program authority
+    /// * `ConfigCooldownActive` - If the previous config change is within the cooldown
+    pub fn set_allow_executable(ctx: Context<UpdateState>, allowed: bool) -> Result<()> {
+        instructions::handle_set_allow_executable(ctx, allowed)
+    }
+
+    /// Escrow SOL for a recipient with a grace period during which the sender may cancel
+    /// 
+    /// One grace transfer per sender/recipient pair may be open at a time.
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing sender, recipient and the escrow account
+    /// * `amount` - The amount of lamports to escrow
+    /// * `grace_secs` - Seconds during which the sender can cancel
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InvalidTimeout` - If `grace_secs` is 0
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    pub fn transfer_with_grace(
+        ctx: Context<TransferWithGrace>,
+        amount: u64,
+        grace_secs: u32,
+    ) -> Result<()> {
+        instructions::handle_transfer_with_grace(ctx, amount, grace_secs)
+    }
+
+    /// Cancel a grace transfer and refund the sender while the grace period is running
+    /// 
+    /// # Errors
+    /// * `GracePeriodOver` - If the grace period has already ended
+    /// * `InvalidRecipient` - If `treasury` is not the configured treasury
+    pub fn cancel_grace(ctx: Context<CancelGrace>) -> Result<()> {
+        instructions::handle_cancel_grace(ctx)
+    }
+
+    /// Claim a grace transfer once its grace period has ended
+    /// 
+    /// # Errors
+    /// * `EscrowNotExpired` - If the grace period is still running
+    /// * `InvalidRecipient` - If `treasury` is not the configured treasury
+    pub fn claim_grace(ctx: Context<ClaimGrace>) -> Result<()> {
+        instructions::handle_claim_grace(ctx)
+    }
+
+    /// Transfer SOL from the sender to several recipients in one instruction
+    /// 
+    /// Recipients are passed as writable `remaining_accounts`, paid in order.
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the sender and the recipients
+    /// * `amounts` - Lamports for each recipient, in the same order
+    /// * `summarize` - Emit one `BatchSummary` instead of a `TransferExecuted` per recipient
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If `amounts` is empty or any amount is 0
+    /// * `InvalidRecipient` - If the recipient count doesn't match `amounts`
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    pub fn transfer_sol_batch<'info>(
+        ctx: Context<'_, '_, '_, 'info, TransferSolBatch<'info>>,
+        amounts: Vec<u64>,
+        summarize: bool,
+    ) -> Result<()> {
+        instructions::handle_transfer_sol_batch(ctx, amounts, summarize)
+    }
+
+    /// Limit how many `transfer_rate_limited` transfers the caller receives per window
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the recipient and its rate limit account
+    /// * `max_per_window` - Most transfers accepted per window (0 disables the limit)
+    /// * `window_secs` - Window length in seconds
+    /// 
+    /// # Errors
+    /// * `InvalidConfig` - If a limit is set with a zero-length window
+    pub fn set_recipient_rate(
+        ctx: Context<SetRecipientRate>,
+        max_per_window: u32,
+        window_secs: u32,
+    ) -> Result<()> {
+        instructions::handle_set_recipient_rate(ctx, max_per_window, window_secs)
+    }
+
+    /// Transfer SOL, rejected once the recipient's rate limit for the window is reached
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing sender, recipient and its rate limit account
+    /// * `amount` - The amount of lamports
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    /// * `RecipientRateLimited` - If the recipient already received its limit this window
+    pub fn transfer_rate_limited(ctx: Context<TransferRateLimited>, amount: u64) -> Result<()> {
+        instructions::handle_transfer_rate_limited(ctx, amount)
+    }
+
+    /// Contribute SOL to the pooled vault and receive proportional shares
+    /// 
+    /// Shares are minted at the pool's contributed value per share; SOL sent
+    /// to the pool outside `contribute` is not counted towards that value.
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the contributor, its shares and the pool
+    /// * `amount` - The amount of lamports to contribute
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0 or too small to mint a share
+    /// * `InsufficientFunds` - If the contributor doesn't have enough SOL
+    /// * `MathOverflow` - If the share amount doesn't fit in a u64
+    pub fn contribute(ctx: Context<Contribute>, amount: u64) -> Result<()> {
+        instructions::handle_contribute(ctx, amount)
+    }
+
+    /// Redeem shares for their proportional part of the pooled vault
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the owner, its shares and the pool
+    /// * `shares` - The number of shares to redeem
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If shares is 0
+    /// * `InsufficientFunds` - If the owner holds fewer shares
+    /// * `Unauthorized` - If the signer does not own the shares
+    pub fn redeem_shares(ctx: Context<RedeemShares>, shares: u64) -> Result<()> {
+        instructions::handle_redeem_shares(ctx, shares)
+    }
+
+    /// Create a spending cap shared by a group of member senders
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the admin and the group budget account
+    /// * `group_id` - Identifier of the group, unique per admin
+    /// * `members` - Senders allowed to spend from the budget (up to `MAX_GROUP_MEMBERS`)
+    /// * `cap` - Combined lamports the members may transfer
+    /// 
+    /// # Errors
+    /// * `InvalidConfig` - If there are no members or too many
+    pub fn create_group_budget(
+        ctx: Context<CreateGroupBudget>,
+        group_id: u64,
+        members: Vec<Pubkey>,
+        cap: u64,
+    ) -> Result<()> {
+        instructions::handle_create_group_budget(ctx, group_id, members, cap)
+    }
+
+    /// Transfer SOL as a group member, charged against the group's shared cap
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the member, recipient and group budget
+    /// * `amount` - The amount of lamports
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    /// * `Unauthorized` - If the sender is not a member of the group
+    /// * `GroupBudgetExceeded` - If the transfer would exceed the group's cap
+    pub fn transfer_from_group(ctx: Context<TransferFromGroup>, amount: u64) -> Result<()> {
+        instructions::handle_transfer_from_group(ctx, amount)
+    }
+
+    /// Transfer SOL, counted in the current UTC day's totals
+    /// 
+    /// The first transfer of a new day writes the previous day's totals to a
+    /// `DailySnapshot` PDA before the daily window resets. `snapshot` must be
+    /// the snapshot PDA for the day the window currently records.
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing sender, recipient, the daily window and snapshot account
+    /// * `amount` - The amount of lamports
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    pub fn transfer_with_daily_close(
+        ctx: Context<TransferWithDailyClose>,
+        amount: u64,
+    ) -> Result<()> {
+        instructions::handle_transfer_with_daily_close(ctx, amount)
+    }
+
+    /// Set the most token fees the program will ever collect (authority only)
+    /// 
+    /// Fees are reduced so `total_fees_collected` never exceeds the cap; after
+    /// that `transfer_token_with_fee` charges nothing.
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the program state and authority
+    /// * `cap` - Lifetime fee cap in token base units (0 disables the cap)
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the program authority
+    /// * `ConfigCooldownActive` - If the previous config change is within the cooldown
+    pub fn set_lifetime_fee_cap(ctx: Context<UpdateState>, cap: u64) -> Result<()> {
+        instructions::handle_set_lifetime_fee_cap(ctx, cap)
+    }
+
+    /// Transfer SOL with the recipient's consent, proven by its signature over the transfer
+    /// 
+    /// The instruction right before this one must be an Ed25519 program
+    /// instruction verifying `signature` by `recipient_pubkey` over
+    /// `ack_message`: the program id, sender, recipient, amount and the
+    /// recipient's acknowledgement nonce, which this transfer consumes. The
+    /// recipient does not sign the transaction.
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing sender, recipient, its nonce and the instructions sysvar
+    /// * `amount` - The amount of lamports
+    /// * `signature` - Recipient's ed25519 signature over the acknowledgement message
+    /// * `recipient_pubkey` - Key that produced the signature
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InvalidRecipient` - If the recipient account is not `recipient_pubkey`
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    /// * `InvalidSignature` - If no matching Ed25519 verification precedes this instruction
+    pub fn transfer_signed_ack(
+        ctx: Context<TransferSignedAck>,
+        amount: u64,
+        signature: [u8; 64],
+        recipient_pubkey: Pubkey,
+    ) -> Result<()> {
+        instructions::handle_transfer_signed_ack(ctx, amount, signature, recipient_pubkey)
+    }
+
+    /// Set how long reversible transfers can be reversed (authority only)
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the program state and authority
+    /// * `secs` - Challenge period in seconds (0 disables reversible transfers)
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the program authority
+    /// * `ConfigCooldownActive` - If the previous config change is within the cooldown
+    pub fn set_challenge_period(ctx: Context<UpdateState>, secs: u32) -> Result<()> {
+        instructions::handle_set_challenge_period(ctx, secs)
+    }
+
+    /// Hold SOL for a recipient during the challenge period, reversible by the sender
+    /// 
+    /// One reversible transfer per sender/recipient pair may be open at a time.
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing sender, recipient and the hold account
+    /// * `amount` - The amount of lamports to hold
+    /// 
+    /// # Errors
+    /// * `InvalidConfig` - If no challenge period is configured
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    pub fn transfer_reversible(ctx: Context<TransferReversible>, amount: u64) -> Result<()> {
+        instructions::handle_transfer_reversible(ctx, amount)
+    }
+
+    /// Reverse a held transfer back to the sender during its challenge period
+    /// 
+    /// # Errors
+    /// * `ChallengePeriodOver` - If the challenge period has already ended
+    /// * `InvalidRecipient` - If `treasury` is not the configured treasury
+    pub fn reverse_transfer(ctx: Context<ReverseTransfer>) -> Result<()> {
+        instructions::handle_reverse_transfer(ctx)
+    }
+
+    /// Finalize a held transfer to the recipient once its challenge period has ended
+    /// 
+    /// # Errors
+    /// * `EscrowNotExpired` - If the challenge period is still running
+    /// * `InvalidRecipient` - If `treasury` is not the configured treasury
+    pub fn finalize_transfer(ctx: Context<FinalizeTransfer>) -> Result<()> {
+        instructions::handle_finalize_transfer(ctx)
+    }
+
+    /// Register the acceptance policy enforced by `transfer_with_policy` (called by the recipient)
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the recipient and its stats account
+    /// * `min_incoming` - Smallest transfer accepted in lamports (0 accepts any amount)
+    /// * `require_memo` - Whether transfers must carry a non-empty memo
+    /// * `require_kyc` - Whether the recipient must be KYC-verified to receive
+    pub fn set_acceptance_policy(
+        ctx: Context<RegisterRecipient>,
+        min_incoming: u64,
+        require_memo: bool,
+        require_kyc: bool,
+    ) -> Result<()> {
+        instructions::handle_set_acceptance_policy(ctx, min_incoming, require_memo, require_kyc)
+    }
+
+    /// Transfer SOL only if it satisfies every rule of the recipient's acceptance policy
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing sender, recipient and its stats account
+    /// * `amount` - The amount of lamports
+    /// * `memo` - Reference attached to the payment, logged when non-empty
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `BelowRecipientMinimum` - If amount is below the recipient's minimum
+    /// * `MemoRequired` - If the memo is empty but the recipient requires one
+    /// * `RecipientNotVerified` - If the recipient requires KYC but is not verified
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    pub fn transfer_with_policy(
+        ctx: Context<TransferWithPolicy>,
+        amount: u64,
+        memo: String,
+    ) -> Result<()> {
+        instructions::handle_transfer_with_policy(ctx, amount, memo)
+    }
+
+    /// Collect the same amount from several sources into one recipient, atomically
+    /// 
+    /// Sources are passed as writable `remaining_accounts` and must all sign;
+    /// each is followed by its guardian setting PDA.
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the recipient and the sources
+    /// * `amount_each` - Lamports taken from each source
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If `amount_each` is 0 or no sources are given
+    /// * `Unauthorized` - If a source has not signed
+    /// * `GuardianRequired` - If a source has a guardian or its guardian PDA is wrong
+    /// * `InsufficientFunds` - If a source doesn't have enough SOL
+    pub fn collect_sol<'info>(
+        ctx: Context<'_, '_, '_, 'info, CollectSol<'info>>,
+        amount_each: u64,
+    ) -> Result<()> {
+        instructions::handle_collect_sol(ctx, amount_each)
+    }
+
+    /// Get a sender's average time between consecutive transfers
+    /// 
+    /// Updated by every transfer that records `SenderStats`; the first
+    /// transfer only sets the baseline timestamp.
+    /// 
+    /// # Returns
+    /// * The average in seconds, rounded down (0 before the second transfer)
+    pub fn get_average_interval(ctx: Context<GetAverageInterval>) -> Result<u64> {
+        instructions::handle_get_average_interval(ctx)
+    }
+
+    /// Transfer SOL and set aside part of it in the sender's tax withholding PDA
+    /// 
+    /// The recipient receives the full `amount`; `amount * withhold_bps / 10_000`
+    /// is paid by the sender on top of it into the withholding PDA. Counts as
+    /// two transfers towards `max_transfers_per_tx`.
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the withholding, sender and recipient
+    /// * `amount` - Lamports sent to the recipient
+    /// * `withhold_bps` - Share of the amount withheld (basis points)
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `WithholdingTooHigh` - If `withhold_bps` exceeds `MAX_WITHHOLD_BPS`
+    /// * `InsufficientFunds` - If sender can't cover the amount plus the withheld share
+    pub fn transfer_withhold(
+        ctx: Context<TransferWithhold>,
+        amount: u64,
+        withhold_bps: u16,
+    ) -> Result<()> {
+        instructions::handle_transfer_withhold(ctx, amount, withhold_bps)
+    }
+
+    /// Withdraw SOL from the caller's tax withholding PDA
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InsufficientFunds` - If amount exceeds the withheld balance
+    pub fn withdraw_withholding(ctx: Context<WithdrawWithholding>, amount: u64) -> Result<()> {
+        instructions::handle_withdraw_withholding(ctx, amount)
+    }
+
+    /// Deposit SOL into the caller's system-owned vault PDA
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InsufficientFunds` - If owner doesn't have enough SOL
+    pub fn deposit_to_vault(ctx: Context<DepositToVault>, amount: u64) -> Result<()> {
+        instructions::handle_deposit_to_vault(ctx, amount)
+    }
+
+    /// Withdraw SOL from the caller's vault, which the program signs for with `bump`
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the vault and its owner
+    /// * `amount` - Lamports to withdraw
+    /// * `bump` - Bump of the vault PDA; must be the canonical one
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InsufficientFunds` - If the vault doesn't hold enough SOL
+    /// * `NonCanonicalBump` - If `bump` is not the canonical vault bump
+    pub fn withdraw_from_vault(
+        ctx: Context<WithdrawFromVault>,
+        amount: u64,
+        bump: u8,
+    ) -> Result<()> {
+        instructions::handle_withdraw_from_vault(ctx, amount, bump)
+    }
+
+    /// Set the most lamports a sender may send per UTC day through `transfer_sol_daily_capped` (authority only)
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the program state and authority
+    /// * `cap` - Daily cap in lamports (0 disables it)
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the program authority
+    /// * `ConfigCooldownActive` - If the previous config change is within the cooldown
+    pub fn set_sender_daily_cap(ctx: Context<UpdateState>, cap: u64) -> Result<()> {
+        instructions::handle_set_sender_daily_cap(ctx, cap)
+    }
+
+    /// Give a recipient priority so transfers to it bypass daily caps (authority only)
+    pub fn add_priority_recipient(ctx: Context<AddPriorityRecipient>) -> Result<()> {
+        instructions::handle_add_priority_recipient(ctx)
+    }
+
+    /// Remove a recipient's priority (authority only)
+    pub fn remove_priority_recipient(ctx: Context<RemovePriorityRecipient>) -> Result<()> {
+        instructions::handle_remove_priority_recipient(ctx)
+    }
+
+    /// Transfer SOL counted against the sender's daily cap
+    /// 
+    /// Transfers to a priority recipient bypass the cap and are not counted.
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the daily spend, sender and recipient
+    /// * `amount` - Amount of lamports to transfer
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    /// * `DailyCapExceeded` - If the transfer takes the sender past the daily cap
+    pub fn transfer_sol_daily_capped(
+        ctx: Context<TransferSolDailyCapped>,
+        amount: u64,
+    ) -> Result<()> {
+        instructions::handle_transfer_sol_daily_capped(ctx, amount)
+    }
+
+    /// Transfer SOL and add the recipient to the sender's recipient bloom filter
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    pub fn transfer_with_bloom(ctx: Context<TransferWithBloom>, amount: u64) -> Result<()> {
+        instructions::handle_transfer_with_bloom(ctx, amount)
+    }
+
+    /// Check whether a sender may have paid `recipient` through `transfer_with_bloom`
+    /// 
+    /// # Returns
+    /// * `false` if the recipient was certainly never paid; `true` if it may
+    ///   have been (false positives grow as the filter fills, see `RecipientBloom`)
+    pub fn possibly_paid(ctx: Context<PossiblyPaid>, recipient: Pubkey) -> Result<bool> {
+        instructions::handle_possibly_paid(ctx, recipient)
+    }
+
+    /// Set how many seconds must pass between config changes (authority only)
+    /// 
+    /// Every config setter records its change time and is rejected inside the
+    /// cooldown, including this one and the per-account settings such as
+    /// freezes, pause exemptions, priority recipients and KYC flags. Only
+    /// `set_paused` stays available, so the program can still be paused at once;
+    /// `finalize_config` sets nothing and is not counted either.
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the program state and authority
+    /// * `secs` - Cooldown in seconds (0 disables it)
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the program authority
+    /// * `ConfigCooldownActive` - If the previous config change is within the cooldown
+    pub fn set_config_cooldown(ctx: Context<UpdateState>, secs: u32) -> Result<()> {
+        instructions::handle_set_config_cooldown(ctx, secs)
+    }
+
+    /// Require SOL-moving instructions to come with a non-empty SPL Memo (authority only)
+    /// 
+    /// Enforced by every instruction that moves SOL out of a signer's wallet;
+    /// the memo may be anywhere in the transaction.
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the program state and authority
+    /// * `required` - Whether a memo is required
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the program authority
+    /// * `ConfigCooldownActive` - If the previous config change is within the cooldown
+    pub fn set_memo_required(ctx: Context<UpdateState>, required: bool) -> Result<()> {
+        instructions::handle_set_memo_required(ctx, required)
+    }
+
+    /// Create the append-only transfer journal
+    pub fn init_journal(ctx: Context<InitJournal>) -> Result<()> {
+        instructions::handle_init_journal(ctx)
+    }
+
+    /// Transfer SOL and append a fixed-size entry for it to the journal
+    /// 
+    /// The journal is reallocated by one entry per transfer; the sender pays
+    /// the extra rent.
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    /// * `JournalFull` - If the journal already holds `Journal::MAX_ENTRIES` entries
+    pub fn transfer_with_journal(ctx: Context<TransferWithJournal>, amount: u64) -> Result<()> {
+        instructions::handle_transfer_with_journal(ctx, amount)
+    }
+
+    /// Read the journal entry at `index`
+    /// 
+    /// # Errors
+    /// * `JournalIndexOutOfRange` - If no entry has been written at `index`
+    pub fn read_journal_entry(
+        ctx: Context<ReadJournalEntry>,
+        index: u64,
+    ) -> Result<state::JournalEntry> {
+        instructions::handle_read_journal_entry(ctx, index)
+    }
+
+    /// Set the largest share of their balance a sender may move unconfirmed (authority only)
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the program state and authority
+    /// * `max_fraction_bps` - Fraction in basis points (0 restores the 90% default)
+    /// 
+    /// # Errors
+    /// * `Unauthorized` - If the signer is not the program authority
+    /// * `ConfigCooldownActive` - If the previous config change is within the cooldown
+    /// * `InvalidConfig` - If `max_fraction_bps` exceeds 10000
+    pub fn set_max_fraction_bps(ctx: Context<UpdateState>, max_fraction_bps: u16) -> Result<()> {
+        instructions::handle_set_max_fraction_bps(ctx, max_fraction_bps)
+    }
+
+    /// Transfer SOL, guarding against accidentally draining the sender's balance
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the program state, sender and recipient
+    /// * `amount` - The amount of lamports to transfer
+    /// * `confirm` - Allow moving more than the configured fraction of the balance
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    /// * `ExceedsBalanceFraction` - If the amount is over the allowed fraction of the
+    ///   sender's balance and `confirm` is not set
+    pub fn transfer_sol_guarded(
+        ctx: Context<TransferSolGuarded>,
+        amount: u64,
+        confirm: bool,
+    ) -> Result<()> {
+        instructions::handle_transfer_sol_guarded(ctx, amount, confirm)
+    }
+
+    /// Set the guardian who must co-sign the caller's first transfer to any new recipient
+    /// 
+    /// Once set, every other instruction moving the caller's SOL is rejected,
+    /// so the guardian can't be bypassed through another variant.
+    /// 
+    /// # Arguments
+    /// * `ctx` - The context containing the guardian setting, sender and current guardian
+    /// * `guardian` - New guardian
+    /// 
+    /// # Errors
+    /// * `InvalidConfig` - If `guardian` is the default public key
+    /// * `GuardianRequired` - If a guardian is already set and has not co-signed
+    pub fn set_guardian(ctx: Context<SetGuardian>, guardian: Pubkey) -> Result<()> {
+        instructions::handle_set_guardian(ctx, guardian)
+    }
+
+    /// Transfer SOL, requiring the guardian's co-signature for a never-before-paid recipient
+    /// 
+    /// The first transfer records an approval marker; later transfers to the
+    /// same recipient need no guardian.
+    /// 
+    /// # Errors
+    /// * `InvalidAmount` - If amount is 0
+    /// * `InsufficientFunds` - If sender doesn't have enough SOL
+    /// * `GuardianRequired` - If the recipient is new and the guardian has not co-signed
+    pub fn transfer_with_guardian(ctx: Context<TransferWithGuardian>, amount: u64) -> Result<()> {
+        instructions::handle_transfer_with_guardian(ctx, amount)
+    }
 }
@@ -1,9 +1,29 @@
 //! Account state structures for the Coin Transfer program
 
 use anchor_lang::prelude::*;
+use solana_sha256_hasher::hashv;
+
+use crate::errors::TransferError;
+
+/// Denominator for values expressed in basis points
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Share of the sender's balance guarded transfers may move unconfirmed when
+/// `max_fraction_bps` is unset (90%)
+pub const DEFAULT_MAX_FRACTION_BPS: u16 = 9_000;
+
+/// Lamports in one SOL
+pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+/// Transfer amount is given in lamports
+pub const DENOM_LAMPORTS: u8 = 0;
+
+/// Transfer amount is given in whole SOL
+pub const DENOM_SOL: u8 = 1;
 
 /// Program state account (optional - for tracking program metadata)
 #[account]
+#[derive(Default, InitSpace)]
 pub struct ProgramState {
     /// Authority that initialized the program
     pub authority: Pubkey,
@@ -17,20 +37,84 @@ pub struct ProgramState {
     /// Program version
     pub version: u8,
     
+    /// Recipient that receives transfers bounced from a frozen recipient
+    pub fallback_recipient: Pubkey,
+    
+    /// Whether transfers are currently paused
+    pub paused: bool,
+    
+    /// Total volume (in lamports) at which transfers auto-pause; 0 disables it
+    pub milestone_volume: u64,
+    
+    /// SOL price in micro-USD (USD * 10^6) used to value new transfers
+    pub sol_usd_rate: u64,
+    
+    /// Total volume in micro-USD, valued at the rate in effect for each transfer
+    pub total_volume_usd: u64,
+    
+    /// Longest a timelock escrow may lock funds, in seconds; 0 disables the limit
+    pub max_lock_secs: u32,
+    
+    /// Whether `finalize_config` has validated the settings; transfers are blocked until then
+    pub configured: bool,
+    
+    /// UTC hour (0-23) from which transfers are accepted
+    pub open_hour: u8,
+    
+    /// UTC hour (0-23) at which transfers stop; equal to `open_hour` disables the window
+    pub close_hour: u8,
+    
+    /// Most `TransferRecord`s a sender may keep open at once; 0 disables the cap
+    pub max_records: u32,
+    
+    /// Whether transfers must be signed by the sender alone (no relayer co-signing)
+    pub require_sole_signer: bool,
+    
+    /// Rolling hash over the sender, recipient and amount of every recorded transfer
+    pub checksum: [u8; 32],
+    
+    /// Most transfers a single transaction may make through this program; 0 disables the cap
+    pub max_transfers_per_tx: u16,
+    
+    /// Fingerprint of the transaction that last made a capped transfer
+    pub tx_fingerprint: u64,
+    
+    /// Capped transfers made so far by the transaction in `tx_fingerprint`
+    pub tx_transfer_count: u16,
+    
+    /// Prior `SenderStats` transfers required by `transfer_with_history`
+    pub min_history: u32,
+    
+    /// Whether transfers may send SOL to executable (program) accounts
+    pub allow_executable: bool,
+    
+    /// Most token fees ever collected; 0 disables the cap
+    pub lifetime_fee_cap: u64,
+    
+    /// Token fees collected so far, after rebates
+    pub total_fees_collected: u64,
+    
+    /// Seconds a reversible transfer can be reversed before it may be finalized
+    pub challenge_period_secs: u32,
+    
+    /// Most lamports a sender may send per UTC day through capped transfers; 0 disables it
+    pub sender_daily_cap: u64,
+    
+    /// Seconds that must pass between config changes; 0 disables the cooldown
+    pub config_cooldown_secs: u32,
+    
+    /// Timestamp of the last config change
+    pub last_config_change_ts: i64,
+    
+    /// Whether every SOL-moving instruction must come with a non-empty SPL Memo
+    pub memo_required: bool,
+    
+    /// Largest share of the sender's balance a guarded transfer may move
+    /// unconfirmed (basis points); 0 means `DEFAULT_MAX_FRACTION_BPS`
+    pub max_fraction_bps: u16,
+    
     /// Reserved space for future upgrades
-    pub _reserved: [u8; 64],
-}
-
-impl Default for ProgramState {
-    fn default() -> Self {
-        Self {
-            authority: Pubkey::default(),
-            total_transfers: 0,
-            total_volume: 0,
-            version: 0,
-            _reserved: [0u8; 64],
-        }
-    }
+    pub _reserved: [u8; 31],
 }
 
 impl ProgramState {
@@ -40,23 +124,239 @@ impl ProgramState {
         8 +  // total_transfers
         8 +  // total_volume
         1 +  // version
-        64;  // reserved
+        32 + // fallback_recipient
+        1 +  // paused
+        8 +  // milestone_volume
+        8 +  // sol_usd_rate
+        8 +  // total_volume_usd
+        4 +  // max_lock_secs
+        1 +  // configured
+        1 +  // open_hour
+        1 +  // close_hour
+        4 +  // max_records
+        1 +  // require_sole_signer
+        32 + // checksum
+        2 +  // max_transfers_per_tx
+        8 +  // tx_fingerprint
+        2 +  // tx_transfer_count
+        4 +  // min_history
+        1 +  // allow_executable
+        8 +  // lifetime_fee_cap
+        8 +  // total_fees_collected
+        4 +  // challenge_period_secs
+        8 +  // sender_daily_cap
+        4 +  // config_cooldown_secs
+        8 +  // last_config_change_ts
+        1 +  // memo_required
+        2 +  // max_fraction_bps
+        31;  // reserved
+
+    /// Bytes taken by the fields of the original (v1) layout, excluding reserved space
+    const V1_FIELDS: usize = 32 + 8 + 8 + 1;
+
+    /// Size of `_reserved` in the original (v1) layout
+    pub const ORIGINAL_RESERVED: usize = 64;
+
+    /// Bytes `migrate_state` appends to v1 accounts once their reserved space ran out
+    pub const V2_EXTENSION: usize = 128;
+
+    /// Size of an account created with the v1 layout
+    pub const V1_SIZE: usize = 8 + Self::V1_FIELDS + Self::ORIGINAL_RESERVED;
+
+    /// Layout version written by `initialize` and `migrate_state`
+    pub const VERSION: u8 = 2;
+
+    /// Bytes still left in `_reserved`
+    pub const RESERVED_REMAINING: usize = array_len(|state: ProgramState| state._reserved);
+
+    /// Bytes carved out of the reserved space (and its v2 extension) by fields added since v1
+    pub const CARVED: usize = Self::INIT_SPACE - Self::V1_FIELDS - Self::RESERVED_REMAINING;
 
     /// Initialize new program state
     pub fn init(&mut self, authority: Pubkey) {
         self.authority = authority;
         self.total_transfers = 0;
         self.total_volume = 0;
-        self.version = 1;
+        self.version = Self::VERSION;
+        self.configured = false;
     }
 
-    /// Record a transfer
-    pub fn record_transfer(&mut self, amount: u64) {
-        self.total_transfers = self.total_transfers.saturating_add(1);
-        self.total_volume = self.total_volume.saturating_add(amount);
+    /// Check the settings a finalized program relies on
+    pub fn validate_config(&self) -> Result<()> {
+        require!(self.authority != Pubkey::default(), TransferError::InvalidConfig);
+        // A milestone already behind us would pause on the very next transfer
+        require!(
+            self.milestone_volume == 0 || self.milestone_volume > self.total_volume,
+            TransferError::InvalidConfig
+        );
+        Ok(())
+    }
+
+    /// Require that transfers are accepted: configuration finalized, not paused
+    /// unless the sender is `exempt`, and within business hours
+    pub fn require_open(&self, sender: &Pubkey, exempt: bool) -> Result<()> {
+        require!(self.configured, TransferError::NotInitialized);
+        require!(!self.paused || exempt, TransferError::ProgramPaused);
+        self.require_business_hours(sender)
+    }
+
+    /// Part of `fee` that can still be charged under the lifetime fee cap
+    pub fn capped_fee(&self, fee: u64) -> u64 {
+        if self.lifetime_fee_cap == 0 {
+            return fee;
+        }
+        fee.min(self.lifetime_fee_cap.saturating_sub(self.total_fees_collected))
+    }
+
+    /// Require that `recipient` is not an executable account, unless
+    /// `allow_executable` is set
+    pub fn require_not_program(&self, recipient: &AccountInfo) -> Result<()> {
+        require!(
+            self.allow_executable || !recipient.executable,
+            TransferError::RecipientIsProgram
+        );
+        Ok(())
+    }
+
+    /// Record a config change made at `now`, rejecting it while the
+    /// cooldown since the previous change is still running
+    pub fn record_config_change(&mut self, now: i64) -> Result<()> {
+        require!(
+            now >= self
+                .last_config_change_ts
+                .saturating_add(self.config_cooldown_secs as i64),
+            TransferError::ConfigCooldownActive
+        );
+        self.last_config_change_ts = now;
+        Ok(())
+    }
+
+    /// Require that `amount` is at most the allowed fraction of `balance`,
+    /// unless the sender has explicitly confirmed a larger transfer
+    pub fn require_balance_fraction(
+        &self,
+        amount: u64,
+        balance: u64,
+        confirmed: bool,
+    ) -> Result<()> {
+        let max_bps = match self.max_fraction_bps {
+            0 => DEFAULT_MAX_FRACTION_BPS,
+            bps => bps,
+        };
+        require!(
+            confirmed || amount <= bps_of(balance, max_bps),
+            TransferError::ExceedsBalanceFraction
+        );
+        Ok(())
+    }
+
+    /// Require that the current time is within business hours
+    ///
+    /// The authority may transfer at any time.
+    pub fn require_business_hours(&self, sender: &Pubkey) -> Result<()> {
+        if *sender == self.authority {
+            return Ok(());
+        }
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            self.is_business_hour(utc_hour(now)),
+            TransferError::OutsideBusinessHours
+        );
+        Ok(())
+    }
+
+    /// Whether `hour` falls in `[open_hour, close_hour)`, wrapping past midnight
+    pub fn is_business_hour(&self, hour: u8) -> bool {
+        match self.open_hour.cmp(&self.close_hour) {
+            std::cmp::Ordering::Equal => true,
+            std::cmp::Ordering::Less => hour >= self.open_hour && hour < self.close_hour,
+            std::cmp::Ordering::Greater => hour >= self.open_hour || hour < self.close_hour,
+        }
+    }
+
+    /// Record a transfer and fold it into the rolling checksum
+    pub fn record_transfer(&mut self, sender: &Pubkey, recipient: &Pubkey, amount: u64) {
+        self.checksum = hashv(&[
+            &self.checksum,
+            sender.as_ref(),
+            recipient.as_ref(),
+            &amount.to_le_bytes(),
+        ])
+        .to_bytes();
+        self.record_batch(1, amount);
+    }
+
+    /// Count `transfers` made by the transaction with `fingerprint` against the per-transaction cap
+    pub fn count_tx_transfer(&mut self, fingerprint: u64, transfers: u16) -> Result<()> {
+        if fingerprint != self.tx_fingerprint {
+            self.tx_fingerprint = fingerprint;
+            self.tx_transfer_count = 0;
+        }
+        self.tx_transfer_count = self.tx_transfer_count.saturating_add(transfers);
+        require!(
+            self.tx_transfer_count <= self.max_transfers_per_tx,
+            TransferError::TooManyTransfersInTransaction
+        );
+        Ok(())
+    }
+
+    /// Record a batch of transfers accumulated elsewhere
+    pub fn record_batch(&mut self, transfers: u64, volume: u64) {
+        self.total_transfers = self.total_transfers.saturating_add(transfers);
+        self.total_volume = self.total_volume.saturating_add(volume);
+        self.total_volume_usd = self.total_volume_usd.saturating_add(self.usd_value(volume));
+        self.check_milestone();
+    }
+
+    /// Value `lamports` in micro-USD at the current rate
+    pub fn usd_value(&self, lamports: u64) -> u64 {
+        let value = lamports as u128 * self.sol_usd_rate as u128 / LAMPORTS_PER_SOL as u128;
+        value.min(u64::MAX as u128) as u64
+    }
+
+    /// Pause once total volume reaches the milestone
+    ///
+    /// The milestone is one-shot: it is cleared when it fires so the authority
+    /// can unpause without the next transfer pausing again.
+    fn check_milestone(&mut self) {
+        if self.milestone_volume > 0 && self.total_volume >= self.milestone_volume {
+            msg!("Volume milestone {} reached, pausing transfers", self.milestone_volume);
+            self.paused = true;
+            self.milestone_volume = 0;
+        }
     }
 }
 
+/// UTC hour of day (0-23) of a unix timestamp
+pub fn utc_hour(unix_timestamp: i64) -> u8 {
+    (unix_timestamp.rem_euclid(86_400) / 3_600) as u8
+}
+
+/// UTC day of a unix timestamp, counted in days since the unix epoch
+pub fn utc_day(unix_timestamp: i64) -> i64 {
+    unix_timestamp.div_euclid(86_400)
+}
+
+// Fields carved from `_reserved` must shrink it by exactly their own size, so
+// accounts created with an older layout keep deserializing (v1 accounts once
+// `migrate_state` has appended the extension).
+const _: () = {
+    assert!(
+        ProgramState::SIZE == 8 + ProgramState::INIT_SPACE,
+        "ProgramState::SIZE does not match the struct layout"
+    );
+    assert!(
+        ProgramState::CARVED + ProgramState::RESERVED_REMAINING
+            == ProgramState::ORIGINAL_RESERVED + ProgramState::V2_EXTENSION,
+        "ProgramState fields overshoot the original reserved space"
+    );
+};
+
+/// Length of a byte array field, usable in const context
+const fn array_len<T, const N: usize>(_field: fn(T) -> [u8; N]) -> usize {
+    N
+}
+
 /// Transfer record for tracking individual transfers (optional)
 #[account]
 pub struct TransferRecord {
@@ -72,6 +372,15 @@ pub struct TransferRecord {
     /// Unix timestamp of the transfer
     pub timestamp: i64,
     
+    /// Position of this transfer's leaf in the transfer accumulator
+    pub index: u64,
+    
+    /// Total refunded to the sender so far (in lamports)
+    pub refunded_amount: u64,
+    
+    /// Sender's previous record; `Pubkey::default()` on their first one
+    pub prev: Pubkey,
+    
     /// Bump seed for PDA
     pub bump: u8,
 }
@@ -83,5 +392,1676 @@ impl TransferRecord {
         32 + // recipient
         8 +  // amount
         8 +  // timestamp
+        8 +  // index
+        8 +  // refunded_amount
+        32 + // prev
+        1;   // bump
+
+    /// Amount that can still be refunded
+    pub fn refundable(&self) -> u64 {
+        self.amount.saturating_sub(self.refunded_amount)
+    }
+
+    /// Merkle leaf committing to this record
+    ///
+    /// `sha256(sender || recipient || amount || timestamp || index)` with
+    /// integers encoded little-endian.
+    pub fn leaf(&self) -> [u8; 32] {
+        hashv(&[
+            self.sender.as_ref(),
+            self.recipient.as_ref(),
+            &self.amount.to_le_bytes(),
+            &self.timestamp.to_le_bytes(),
+            &self.index.to_le_bytes(),
+        ])
+        .to_bytes()
+    }
+}
+
+/// Head of a sender's linked list of transfer records
+#[account]
+pub struct SenderChain {
+    /// Sender the chain belongs to
+    pub sender: Pubkey,
+    
+    /// Sender's most recent record; `Pubkey::default()` before the first
+    pub last_record: Pubkey,
+    
+    /// Records created by the sender and not yet closed
+    pub active_records: u32,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl SenderChain {
+    /// Size of the SenderChain account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // sender
+        32 + // last_record
+        4 +  // active_records
+        1;   // bump
+}
+
+/// Marker that a sender has paid a recipient at least once
+#[account]
+pub struct PairMarker {
+    /// Sender of the pair
+    pub sender: Pubkey,
+    
+    /// Recipient of the pair
+    pub recipient: Pubkey,
+    
+    /// Unix timestamp of the pair's first transfer
+    pub first_transfer_ts: i64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl PairMarker {
+    /// Size of the PairMarker account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // sender
+        32 + // recipient
+        8 +  // first_transfer_ts
+        1;   // bump
+}
+
+/// Guardian who must co-sign a sender's guarded transfers to new recipients
+#[account]
+pub struct SenderGuardian {
+    /// Sender the guardian protects
+    pub sender: Pubkey,
+    
+    /// Guardian whose signature approves a new recipient
+    pub guardian: Pubkey,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl SenderGuardian {
+    /// Size of the SenderGuardian account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // sender
+        32 + // guardian
+        1;   // bump
+}
+
+/// Marker that a sender's guardian approved a recipient on their first guarded transfer
+#[account]
+pub struct ApprovedRecipient {
+    /// Sender that paid the recipient
+    pub sender: Pubkey,
+    
+    /// Recipient the guardian approved
+    pub recipient: Pubkey,
+    
+    /// Unix timestamp of the approval
+    pub approved_at: i64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl ApprovedRecipient {
+    /// Size of the ApprovedRecipient account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // sender
+        32 + // recipient
+        8 +  // approved_at
+        1;   // bump
+}
+
+/// Mint that recipients of `transfer_token_gated` must hold, set by the authority
+#[account]
+pub struct TokenGate {
+    /// Required mint
+    pub mint: Pubkey,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl TokenGate {
+    /// Size of the TokenGate account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // mint
+        1;   // bump
+}
+
+/// Marker that a sender already made the transfer for an external event id
+///
+/// Until `expires_at` a second transfer with the same id is rejected; after
+/// it, anyone may close the marker with `cleanup_idempotency`.
+#[account]
+pub struct IdempotencyMarker {
+    /// Sender that made the transfer (receives the rent on cleanup)
+    pub sender: Pubkey,
+    
+    /// External event id supplied by the sender
+    pub event_id: [u8; 32],
+    
+    /// Unix timestamp from which the marker no longer blocks duplicates
+    pub expires_at: i64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl IdempotencyMarker {
+    /// Size of the IdempotencyMarker account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // sender
+        32 + // event_id
+        8 +  // expires_at
+        1;   // bump
+}
+
+/// Linear bonding curve pricing units bought with SOL; the account holds the reserve
+///
+/// The `n`-th unit (counting from 0) costs `base_price + slope * n` lamports,
+/// so buying raises the price and selling walks it back down.
+#[account]
+pub struct Curve {
+    /// Price of the first unit in lamports
+    pub base_price: u64,
+    
+    /// Lamports the price rises by with each unit sold
+    pub slope: u64,
+    
+    /// Units currently outstanding
+    pub supply: u64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl Curve {
+    /// Size of the Curve account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        8 +  // base_price
+        8 +  // slope
+        8 +  // supply
+        1;   // bump
+
+    /// Price of the next unit in lamports
+    pub fn price(&self) -> Result<u64> {
+        self.cost(self.supply, 1)
+    }
+
+    /// Lamports for the `units` units starting at supply `from`
+    pub fn cost(&self, from: u64, units: u64) -> Result<u64> {
+        // units * base_price + slope * (units * from + units * (units - 1) / 2)
+        let units = units as u128;
+        let steps = units
+            .checked_mul(from as u128)
+            .and_then(|steps| steps.checked_add(units * units.saturating_sub(1) / 2));
+        let cost = steps
+            .and_then(|steps| steps.checked_mul(self.slope as u128))
+            .and_then(|rise| rise.checked_add(units * self.base_price as u128));
+        cost.and_then(|cost| u64::try_from(cost).ok())
+            .ok_or_else(|| TransferError::MathOverflow.into())
+    }
+}
+
+/// Units of the bonding curve held by an owner
+#[account]
+pub struct CurvePosition {
+    /// Owner of the units
+    pub owner: Pubkey,
+    
+    /// Units held
+    pub units: u64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl CurvePosition {
+    /// Size of the CurvePosition account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // owner
+        8 +  // units
+        1;   // bump
+}
+
+/// Pooled vault co-owned through shares; the account holds the pooled SOL
+///
+/// Share prices follow the contributed `value` rather than the account's
+/// balance, so lamports sent to the pool directly cannot inflate the price
+/// of a share and round later contributions down to nothing.
+#[account]
+pub struct SharePool {
+    /// Shares outstanding across all owners
+    pub total_shares: u64,
+    
+    /// Lamports contributed and not yet redeemed
+    pub value: u64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl SharePool {
+    /// Size of the SharePool account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        8 +  // total_shares
+        8 +  // value
+        1;   // bump
+
+    /// Shares minted for contributing `amount` to the pool
+    ///
+    /// The first contribution mints one share per lamport.
+    pub fn shares_for(&self, amount: u64) -> Result<u64> {
+        if self.total_shares == 0 || self.value == 0 {
+            return Ok(amount);
+        }
+        mul_div(amount, self.total_shares, self.value)
+    }
+
+    /// Lamports paid out for redeeming `shares` from the pool
+    pub fn lamports_for(&self, shares: u64) -> Result<u64> {
+        mul_div(shares, self.value, self.total_shares)
+    }
+}
+
+/// `a * b / c` rounded down, without intermediate overflow
+fn mul_div(a: u64, b: u64, c: u64) -> Result<u64> {
+    (a as u128 * b as u128)
+        .checked_div(c as u128)
+        .and_then(|result| u64::try_from(result).ok())
+        .ok_or_else(|| TransferError::MathOverflow.into())
+}
+
+/// Shares of the pooled vault held by an owner
+#[account]
+pub struct Shares {
+    /// Owner of the shares
+    pub owner: Pubkey,
+    
+    /// Shares held
+    pub shares: u64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl Shares {
+    /// Size of the Shares account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // owner
+        8 +  // shares
+        1;   // bump
+}
+
+/// Maximum share of a transfer that can be withheld (50%)
+pub const MAX_WITHHOLD_BPS: u16 = 5_000;
+
+/// Tax set aside by a sender; the account holds the withheld SOL
+#[account]
+pub struct TaxWithholding {
+    /// Sender the withholding belongs to (the only one who can withdraw)
+    pub owner: Pubkey,
+    
+    /// Withheld lamports not yet withdrawn
+    pub balance: u64,
+    
+    /// Lifetime lamports withheld
+    pub total_withheld: u64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl TaxWithholding {
+    /// Size of the TaxWithholding account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // owner
+        8 +  // balance
+        8 +  // total_withheld
         1;   // bump
 }
+
+/// Seconds a password escrow refuses claims after an incorrect secret
+pub const PASSWORD_CLAIM_COOLDOWN_SECS: i64 = 5;
+
+/// Escrow claimable by whoever presents the secret hashing to `key_hash`
+#[account]
+pub struct PasswordEscrow {
+    /// Sender that funded the escrow (receives the rent once claimed)
+    pub sender: Pubkey,
+    
+    /// Amount held for the claimer in lamports
+    pub amount: u64,
+    
+    /// SHA-256 of the secret that unlocks the escrow
+    pub key_hash: [u8; 32],
+    
+    /// Claims made with an incorrect secret
+    pub failed_attempts: u32,
+    
+    /// Unix timestamp of the last incorrect claim
+    pub last_failed_ts: i64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl PasswordEscrow {
+    /// Size of the PasswordEscrow account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // sender
+        8 +  // amount
+        32 + // key_hash
+        4 +  // failed_attempts
+        8 +  // last_failed_ts
+        1;   // bump
+
+    /// Whether a claim may be attempted at `now`
+    pub fn accepts_claim_at(&self, now: i64) -> bool {
+        self.failed_attempts == 0
+            || now >= self.last_failed_ts.saturating_add(PASSWORD_CLAIM_COOLDOWN_SECS)
+    }
+}
+
+/// Depth of the transfer accumulator tree (up to 2^16 leaves)
+pub const ACCUMULATOR_DEPTH: usize = 16;
+
+/// Append-only Merkle tree over all recorded transfers
+///
+/// Only the rightmost "frontier" node of each level is stored, which is
+/// enough to append leaves and recompute the root. Empty subtrees hash as
+/// `zero[0] = [0; 32]`, `zero[h + 1] = sha256(zero[h] || zero[h])`.
+#[account]
+pub struct TransferAccumulator {
+    /// Current Merkle root over all leaves
+    pub root: [u8; 32],
+    
+    /// Number of leaves appended so far
+    pub leaf_count: u64,
+    
+    /// Rightmost filled node at each level
+    pub frontier: [[u8; 32]; ACCUMULATOR_DEPTH],
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl TransferAccumulator {
+    /// Size of the TransferAccumulator account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // root
+        8 +  // leaf_count
+        32 * ACCUMULATOR_DEPTH + // frontier
+        1;   // bump
+
+    /// Maximum number of leaves the tree can hold
+    pub const CAPACITY: u64 = 1 << ACCUMULATOR_DEPTH;
+
+    /// Append a leaf and refresh the root
+    pub fn append(&mut self, leaf: [u8; 32]) -> Option<()> {
+        if self.leaf_count >= Self::CAPACITY {
+            return None;
+        }
+
+        // Carry the new leaf up until it lands on an empty left slot
+        let mut node = leaf;
+        let mut size = self.leaf_count + 1;
+        for level in 0..ACCUMULATOR_DEPTH {
+            if size & 1 == 1 {
+                self.frontier[level] = node;
+                break;
+            }
+            node = hashv(&[&self.frontier[level], &node]).to_bytes();
+            size >>= 1;
+        }
+        self.leaf_count += 1;
+        self.root = self.compute_root();
+        Some(())
+    }
+
+    /// Fold the frontier with empty subtrees to get the root
+    fn compute_root(&self) -> [u8; 32] {
+        let mut node = [0u8; 32];
+        let mut zero = [0u8; 32];
+        let mut size = self.leaf_count;
+        for level in 0..ACCUMULATOR_DEPTH {
+            node = if size & 1 == 1 {
+                hashv(&[&self.frontier[level], &node]).to_bytes()
+            } else {
+                hashv(&[&node, &zero]).to_bytes()
+            };
+            zero = hashv(&[&zero, &zero]).to_bytes();
+            size >>= 1;
+        }
+        node
+    }
+}
+
+/// Escrow for a transfer whose release is decided by an arbiter
+#[account]
+pub struct ConditionalEscrow {
+    /// Sender who funded the escrow (refunded on failure)
+    pub sender: Pubkey,
+    
+    /// Recipient paid out on success
+    pub recipient: Pubkey,
+    
+    /// Only key allowed to resolve the escrow
+    pub arbiter: Pubkey,
+    
+    /// Escrowed amount in lamports (excludes rent)
+    pub amount: u64,
+    
+    /// Unix timestamp after which anyone may refund the sender
+    pub deadline: i64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl ConditionalEscrow {
+    /// Size of the ConditionalEscrow account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // sender
+        32 + // recipient
+        32 + // arbiter
+        8 +  // amount
+        8 +  // deadline
+        1;   // bump
+}
+
+/// Escrow that releases to the recipient once its unlock time passes
+#[account]
+pub struct Timelock {
+    /// Sender who funded the timelock (receives the rent back)
+    pub sender: Pubkey,
+    
+    /// Recipient paid out once unlocked
+    pub recipient: Pubkey,
+    
+    /// Locked amount in lamports (excludes rent)
+    pub amount: u64,
+    
+    /// Unix timestamp from which the funds can be released
+    pub unlock_ts: i64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl Timelock {
+    /// Size of the Timelock account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // sender
+        32 + // recipient
+        8 +  // amount
+        8 +  // unlock_ts
+        1;   // bump
+}
+
+/// Escrowed transfer the sender may cancel until its grace period ends
+#[account]
+pub struct GraceTransfer {
+    /// Sender who funded the transfer (receives the rent back)
+    pub sender: Pubkey,
+    
+    /// Recipient who can claim once the grace period ends
+    pub recipient: Pubkey,
+    
+    /// Escrowed amount in lamports (excludes rent)
+    pub amount: u64,
+    
+    /// Unix timestamp at which cancelling stops and claiming starts
+    pub grace_ends_at: i64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl GraceTransfer {
+    /// Size of the GraceTransfer account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // sender
+        32 + // recipient
+        8 +  // amount
+        8 +  // grace_ends_at
+        1;   // bump
+}
+
+/// High-value transfer held until its challenge period ends, reversible until then
+#[account]
+pub struct ReversibleTransfer {
+    /// Sender who funded the transfer (receives the rent back)
+    pub sender: Pubkey,
+    
+    /// Recipient who can finalize once the challenge period ends
+    pub recipient: Pubkey,
+    
+    /// Held amount in lamports (excludes rent)
+    pub amount: u64,
+    
+    /// Unix timestamp at which the challenge period ends
+    pub challenge_ends_at: i64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl ReversibleTransfer {
+    /// Size of the ReversibleTransfer account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // sender
+        32 + // recipient
+        8 +  // amount
+        8 +  // challenge_ends_at
+        1;   // bump
+}
+
+/// Marker showing a recipient has been frozen by the authority
+#[account]
+pub struct FrozenRecipient {
+    /// Recipient that is frozen
+    pub recipient: Pubkey,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl FrozenRecipient {
+    /// Size of the FrozenRecipient account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // recipient
+        1;   // bump
+}
+
+/// Marker exempting transfers to a recipient from per-sender daily caps
+#[account]
+pub struct PriorityRecipient {
+    /// Recipient that has priority
+    pub recipient: Pubkey,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl PriorityRecipient {
+    /// Size of the PriorityRecipient account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // recipient
+        1;   // bump
+}
+
+/// Marker letting a sender keep transferring while the program is paused
+#[account]
+pub struct PauseExempt {
+    /// Sender that is exempt from the global pause
+    pub sender: Pubkey,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl PauseExempt {
+    /// Size of the PauseExempt account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // sender
+        1;   // bump
+}
+
+/// Per-recipient counter used to order incoming payments
+#[account]
+pub struct RecipientNonce {
+    /// Recipient the nonce belongs to
+    pub recipient: Pubkey,
+    
+    /// Nonce the next ordered transfer or acknowledgement must present
+    pub nonce: u64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl RecipientNonce {
+    /// Size of the RecipientNonce account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // recipient
+        8 +  // nonce
+        1;   // bump
+}
+
+/// Per-sender transfer statistics
+#[account]
+pub struct SenderStats {
+    /// Sender the stats belong to
+    pub sender: Pubkey,
+    
+    /// Lifetime number of transfers sent
+    pub transfer_count: u64,
+    
+    /// Lifetime volume sent (in lamports)
+    pub total_sent: u64,
+    
+    /// Transfers not yet flushed to `ProgramState`
+    pub pending_transfers: u64,
+    
+    /// Volume not yet flushed to `ProgramState` (in lamports)
+    pub pending_volume: u64,
+    
+    /// Timestamp of the sender's most recent transfer (0 before the first)
+    pub last_transfer_ts: i64,
+    
+    /// Sum of the gaps between consecutive transfers (in seconds)
+    pub total_interval_secs: u64,
+    
+    /// Number of gaps summed into `total_interval_secs`
+    pub interval_count: u64,
+    
+    /// Fingerprint of the transaction that last made a capped batched transfer
+    pub tx_fingerprint: u64,
+    
+    /// Capped batched transfers made so far by the transaction in `tx_fingerprint`
+    pub tx_transfer_count: u16,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl SenderStats {
+    /// Size of the SenderStats account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // sender
+        8 +  // transfer_count
+        8 +  // total_sent
+        8 +  // pending_transfers
+        8 +  // pending_volume
+        8 +  // last_transfer_ts
+        8 +  // total_interval_secs
+        8 +  // interval_count
+        8 +  // tx_fingerprint
+        2 +  // tx_transfer_count
+        1;   // bump
+
+    /// Record a transfer made at `now` in both lifetime and pending totals
+    pub fn record_transfer(&mut self, amount: u64, now: i64) {
+        self.record_lifetime(amount, now);
+        self.pending_transfers = self.pending_transfers.saturating_add(1);
+        self.pending_volume = self.pending_volume.saturating_add(amount);
+    }
+
+    /// Record a transfer made at `now` in the lifetime totals only
+    ///
+    /// For transfers already recorded in `ProgramState`, which `flush_stats`
+    /// must not fold in again. The first transfer only sets
+    /// `last_transfer_ts`; every later one adds the gap since the previous
+    /// transfer to the interval totals.
+    pub fn record_lifetime(&mut self, amount: u64, now: i64) {
+        self.transfer_count = self.transfer_count.saturating_add(1);
+        self.total_sent = self.total_sent.saturating_add(amount);
+
+        if self.last_transfer_ts != 0 {
+            let interval = now.saturating_sub(self.last_transfer_ts).max(0) as u64;
+            self.total_interval_secs = self.total_interval_secs.saturating_add(interval);
+            self.interval_count = self.interval_count.saturating_add(1);
+        }
+        self.last_transfer_ts = now;
+    }
+
+    /// Count `transfers` made by the transaction with `fingerprint` against `max`
+    ///
+    /// Counts the sender's batched transfers apart from `ProgramState`, so
+    /// they never need a write lock on it.
+    pub fn count_tx_transfer(&mut self, fingerprint: u64, transfers: u16, max: u16) -> Result<()> {
+        if fingerprint != self.tx_fingerprint {
+            self.tx_fingerprint = fingerprint;
+            self.tx_transfer_count = 0;
+        }
+        self.tx_transfer_count = self.tx_transfer_count.saturating_add(transfers);
+        require!(
+            self.tx_transfer_count <= max,
+            TransferError::TooManyTransfersInTransaction
+        );
+        Ok(())
+    }
+
+    /// Average gap between consecutive transfers (in seconds), 0 until there are two
+    pub fn average_interval(&self) -> u64 {
+        if self.interval_count == 0 {
+            return 0;
+        }
+        self.total_interval_secs / self.interval_count
+    }
+}
+
+/// Bits in a sender's recipient bloom filter
+pub const BLOOM_BITS: usize = 1024;
+
+/// Bits set (and probed) per recipient
+pub const BLOOM_HASHES: usize = 3;
+
+/// Bloom filter of the recipients a sender has paid through bloom-tracked transfers
+///
+/// A recipient that was paid always reads as possibly paid; one that was not
+/// can read as paid too once enough bits are set. With 1024 bits and 3
+/// hashes the false-positive rate is about 0.3% after 50 recipients, 2%
+/// after 100 and 9% after 200.
+#[account]
+pub struct RecipientBloom {
+    /// Sender the filter belongs to
+    pub sender: Pubkey,
+    
+    /// Filter bits
+    pub bits: [u8; BLOOM_BITS / 8],
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl RecipientBloom {
+    /// Size of the RecipientBloom account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // sender
+        BLOOM_BITS / 8 + // bits
+        1;   // bump
+
+    /// Bit positions a recipient maps to, taken from its SHA-256 hash
+    fn positions(recipient: &Pubkey) -> [usize; BLOOM_HASHES] {
+        let hash = hashv(&[b"recipient_bloom", recipient.as_ref()]).to_bytes();
+        let mut positions = [0usize; BLOOM_HASHES];
+        for (i, position) in positions.iter_mut().enumerate() {
+            let word = u16::from_le_bytes([hash[2 * i], hash[2 * i + 1]]);
+            *position = word as usize % BLOOM_BITS;
+        }
+        positions
+    }
+
+    /// Add a recipient to the filter
+    pub fn insert(&mut self, recipient: &Pubkey) {
+        for position in Self::positions(recipient) {
+            self.bits[position / 8] |= 1 << (position % 8);
+        }
+    }
+
+    /// Whether the recipient may have been paid; `false` is always exact
+    pub fn possibly_contains(&self, recipient: &Pubkey) -> bool {
+        Self::positions(recipient)
+            .iter()
+            .all(|&position| self.bits[position / 8] & (1 << (position % 8)) != 0)
+    }
+}
+
+/// Fee settings applied by fee-charging transfers
+#[account]
+pub struct FeeConfig {
+    /// Fee charged on each transfer (basis points of the amount)
+    pub fee_bps: u16,
+    
+    /// Share of the fee that is burned (basis points of the fee)
+    pub burn_bps: u16,
+    
+    /// Owner of the accounts that collect the treasury share
+    pub treasury: Pubkey,
+    
+    /// Sender balance (in lamports) required to qualify for the discount; 0 disables it
+    pub discount_min_balance: u64,
+    
+    /// Fee charged to senders holding at least `discount_min_balance` (basis points)
+    pub discount_fee_bps: u16,
+    
+    /// Fee rounded away so far, in 1/10_000ths of a base unit
+    pub pending_fees: u64,
+    
+    /// Transfer amount (in base units) above which part of the fee is rebated; 0 disables it
+    pub rebate_threshold: u64,
+    
+    /// Share of the fee rebated on large transfers (basis points of the fee)
+    pub rebate_bps: u16,
+    
+    /// Most the treasury token account may hold before fees go to the cold wallet; 0 disables it
+    pub hot_treasury_cap: u64,
+    
+    /// Owner of the accounts that receive fees swept past `hot_treasury_cap`
+    pub cold_wallet: Pubkey,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl FeeConfig {
+    /// Size of the FeeConfig account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        2 +  // fee_bps
+        2 +  // burn_bps
+        32 + // treasury
+        8 +  // discount_min_balance
+        2 +  // discount_fee_bps
+        8 +  // pending_fees
+        8 +  // rebate_threshold
+        2 +  // rebate_bps
+        8 +  // hot_treasury_cap
+        32 + // cold_wallet
+        1;   // bump
+
+    /// Fee rate for a sender currently holding `sender_lamports`
+    pub fn fee_bps_for(&self, sender_lamports: u64) -> u16 {
+        if self.discount_min_balance > 0 && sender_lamports >= self.discount_min_balance {
+            self.discount_fee_bps
+        } else {
+            self.fee_bps
+        }
+    }
+
+    /// Fee owed on a transfer of `amount` by a sender holding `sender_lamports`
+    pub fn fee_for(&self, amount: u64, sender_lamports: u64) -> u64 {
+        bps_of(amount, self.fee_bps_for(sender_lamports))
+    }
+
+    /// Fee to charge on a transfer, carrying rounding remainders forward
+    ///
+    /// The fraction `fee_for` rounds away is added to `pending_fees`; once
+    /// that reaches a whole unit it is charged on top of this transfer's fee.
+    /// At most one unit is added per transfer, so the fee never exceeds `amount`.
+    pub fn accrue_fee(&mut self, amount: u64, sender_lamports: u64) -> u64 {
+        let exact = amount as u128 * self.fee_bps_for(sender_lamports) as u128;
+        let denominator = BPS_DENOMINATOR as u128;
+        let mut fee = (exact / denominator) as u64;
+
+        self.pending_fees = self.pending_fees.saturating_add((exact % denominator) as u64);
+        if self.pending_fees >= BPS_DENOMINATOR {
+            self.pending_fees -= BPS_DENOMINATOR;
+            fee += 1;
+        }
+        fee
+    }
+
+    /// Part of `fee` rebated to the sender of a transfer of `amount`
+    pub fn rebate_for(&self, amount: u64, fee: u64) -> u64 {
+        if self.rebate_threshold > 0 && amount > self.rebate_threshold {
+            bps_of(fee, self.rebate_bps)
+        } else {
+            0
+        }
+    }
+
+    /// Split a treasury share into `(kept, swept)` so the treasury stays within its cap
+    pub fn sweep_split(&self, treasury_balance: u64, share: u64) -> (u64, u64) {
+        if self.hot_treasury_cap == 0 {
+            return (share, 0);
+        }
+        let over = treasury_balance
+            .saturating_add(share)
+            .saturating_sub(self.hot_treasury_cap);
+        let swept = over.min(share);
+        (share - swept, swept)
+    }
+
+    /// Split a fee into its `(treasury, burn)` shares
+    pub fn split_fee(&self, fee: u64) -> (u64, u64) {
+        let burn = bps_of(fee, self.burn_bps);
+        (fee - burn, burn)
+    }
+}
+
+/// `value * bps / 10_000`, rounded down
+pub fn bps_of(value: u64, bps: u16) -> u64 {
+    // Cannot overflow: bps is capped at 10_000 so the result fits in u64
+    (value as u128 * bps as u128 / BPS_DENOMINATOR as u128) as u64
+}
+
+/// Maximum number of entries in `AllowedAmounts`
+pub const MAX_ALLOWED_AMOUNTS: usize = 16;
+
+/// Fixed denominations accepted by `transfer_sol_fixed`
+#[account]
+pub struct AllowedAmounts {
+    /// Permitted transfer amounts in lamports
+    pub amounts: Vec<u64>,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl AllowedAmounts {
+    /// Size of the AllowedAmounts account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        4 + 8 * MAX_ALLOWED_AMOUNTS + // amounts
+        1;  // bump
+
+    /// Whether `amount` is one of the permitted denominations
+    pub fn allows(&self, amount: u64) -> bool {
+        self.amounts.contains(&amount)
+    }
+}
+
+/// Maximum number of line items on an `Invoice`
+pub const MAX_LINE_ITEMS: usize = 8;
+
+/// Invoice issued by a payee, settled by a single exact payment
+#[account]
+pub struct Invoice {
+    /// Payee that issued the invoice and receives the payment
+    pub issuer: Pubkey,
+    
+    /// Issuer-chosen invoice number; seeds the PDA
+    pub invoice_id: u64,
+    
+    /// Amount of each line item in lamports
+    pub line_items: Vec<u64>,
+    
+    /// Whether the invoice has been paid
+    pub paid: bool,
+    
+    /// Account that paid the invoice; `Pubkey::default()` while unpaid
+    pub payer: Pubkey,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl Invoice {
+    /// Size of the Invoice account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // issuer
+        8 +  // invoice_id
+        4 + 8 * MAX_LINE_ITEMS + // line_items
+        1 +  // paid
+        32 + // payer
+        1;   // bump
+
+    /// Sum of all line items in lamports
+    pub fn total(&self) -> Result<u64> {
+        self.line_items
+            .iter()
+            .try_fold(0u64, |total, &item| total.checked_add(item))
+            .ok_or_else(|| TransferError::MathOverflow.into())
+    }
+}
+
+/// Maximum number of members of a `GroupBudget`
+pub const MAX_GROUP_MEMBERS: usize = 8;
+
+/// Spending cap shared by a group of member senders
+#[account]
+pub struct GroupBudget {
+    /// Account that created the group
+    pub admin: Pubkey,
+    
+    /// Identifier chosen by the admin, unique per admin
+    pub group_id: u64,
+    
+    /// Senders allowed to spend from the budget
+    pub members: Vec<Pubkey>,
+    
+    /// Combined lamports the members may transfer
+    pub cap: u64,
+    
+    /// Combined lamports the members have transferred so far
+    pub spent: u64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl GroupBudget {
+    /// Size of the GroupBudget account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // admin
+        8 +  // group_id
+        4 + 32 * MAX_GROUP_MEMBERS + // members
+        8 +  // cap
+        8 +  // spent
+        1;   // bump
+
+    /// Charge `amount` spent by `member` against the shared cap
+    pub fn spend(&mut self, member: &Pubkey, amount: u64) -> Result<()> {
+        require!(self.members.contains(member), TransferError::Unauthorized);
+        let spent = self
+            .spent
+            .checked_add(amount)
+            .filter(|&spent| spent <= self.cap)
+            .ok_or(TransferError::GroupBudgetExceeded)?;
+        self.spent = spent;
+        Ok(())
+    }
+}
+
+/// Maximum number of members of the `EmergencyCouncil`
+pub const MAX_EMERGENCY_MEMBERS: usize = 5;
+
+/// Emergency signers able to force-unpause without the program authority
+///
+/// Set once by the authority, so a later compromise of the authority key
+/// cannot replace the council.
+#[account]
+pub struct EmergencyCouncil {
+    /// Keys allowed to sign an emergency unpause
+    pub members: Vec<Pubkey>,
+    
+    /// Distinct members that must sign an emergency unpause
+    pub threshold: u8,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl EmergencyCouncil {
+    /// Size of the EmergencyCouncil account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        4 + 32 * MAX_EMERGENCY_MEMBERS + // members
+        1 +  // threshold
+        1;   // bump
+
+    /// Number of distinct members among the signers of `accounts`
+    pub fn count_signers(&self, accounts: &[AccountInfo]) -> usize {
+        self.members
+            .iter()
+            .filter(|member| {
+                accounts
+                    .iter()
+                    .any(|account| account.is_signer && account.key == *member)
+            })
+            .count()
+    }
+}
+
+/// Program a recipient wants notified of incoming `transfer_with_notification`s
+#[account]
+pub struct NotificationConfig {
+    /// Recipient the setting belongs to
+    pub recipient: Pubkey,
+    
+    /// Program invoked after each notified transfer
+    pub program: Pubkey,
+    
+    /// Whether a missing or wrong notification program skips the notification instead of failing
+    pub best_effort: bool,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl NotificationConfig {
+    /// Size of the NotificationConfig account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // recipient
+        32 + // program
+        1 +  // best_effort
+        1;   // bump
+}
+
+/// Priority fees reported alongside transfers (informational only)
+#[account]
+pub struct PriorityFeeStats {
+    /// Number of transfers that reported a priority fee
+    pub transfers: u64,
+    
+    /// Volume of those transfers (in lamports)
+    pub volume: u64,
+    
+    /// Sum of all reported priority fees
+    pub total_priority_fee: u64,
+    
+    /// Priority fee reported by the most recent transfer
+    pub last_priority_fee: u64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl PriorityFeeStats {
+    /// Size of the PriorityFeeStats account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        8 +  // transfers
+        8 +  // volume
+        8 +  // total_priority_fee
+        8 +  // last_priority_fee
+        1;   // bump
+
+    /// Record a transfer with its reported priority fee
+    pub fn record(&mut self, amount: u64, priority_fee: u64) {
+        self.transfers = self.transfers.saturating_add(1);
+        self.volume = self.volume.saturating_add(amount);
+        self.total_priority_fee = self.total_priority_fee.saturating_add(priority_fee);
+        self.last_priority_fee = priority_fee;
+    }
+}
+
+/// Per-recipient statistics and recipient-registered settings
+#[account]
+pub struct RecipientStats {
+    /// Recipient the stats belong to
+    pub recipient: Pubkey,
+    
+    /// Number of transfers received through stats-tracking instructions
+    pub transfer_count: u64,
+    
+    /// Volume received through stats-tracking instructions (in lamports)
+    pub total_received: u64,
+    
+    /// Routing category registered by the recipient
+    pub category: u8,
+    
+    /// Whether the authority has verified the recipient's identity
+    pub kyc_verified: bool,
+    
+    /// Smallest transfer the recipient accepts (in lamports); 0 accepts any amount
+    pub min_incoming: u64,
+    
+    /// Whether `transfer_with_policy` transfers must carry a non-empty memo
+    pub require_memo: bool,
+    
+    /// Whether `transfer_with_policy` requires the recipient to be KYC-verified
+    pub require_kyc: bool,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl RecipientStats {
+    /// Size of the RecipientStats account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // recipient
+        8 +  // transfer_count
+        8 +  // total_received
+        1 +  // category
+        1 +  // kyc_verified
+        8 +  // min_incoming
+        1 +  // require_memo
+        1 +  // require_kyc
+        1;   // bump
+
+    /// Reject transfers below the recipient's registered minimum
+    pub fn require_min_incoming(&self, amount: u64) -> Result<()> {
+        require!(amount >= self.min_incoming, TransferError::BelowRecipientMinimum);
+        Ok(())
+    }
+
+    /// Reject a transfer breaking the recipient's acceptance policy, reporting
+    /// the first rule broken: minimum amount, then memo, then KYC
+    pub fn require_policy(&self, amount: u64, memo: &str) -> Result<()> {
+        self.require_min_incoming(amount)?;
+        require!(
+            !self.require_memo || !memo.is_empty(),
+            TransferError::MemoRequired
+        );
+        require!(
+            !self.require_kyc || self.kyc_verified,
+            TransferError::RecipientNotVerified
+        );
+        Ok(())
+    }
+
+    /// Record an incoming transfer
+    pub fn record_transfer(&mut self, amount: u64) {
+        self.transfer_count = self.transfer_count.saturating_add(1);
+        self.total_received = self.total_received.saturating_add(amount);
+    }
+}
+
+/// Receipt proving a transfer happened, at an address anyone can derive
+///
+/// The PDA is seeded by `["receipt", Receipt::seed_hash(..)]`, so a recipient
+/// who knows the transfer details can find and check it without logs.
+#[account]
+pub struct Receipt {
+    /// Sender public key
+    pub sender: Pubkey,
+    
+    /// Recipient public key
+    pub recipient: Pubkey,
+    
+    /// Amount transferred in lamports
+    pub amount: u64,
+    
+    /// Sender-chosen nonce distinguishing otherwise identical transfers
+    pub nonce: u64,
+    
+    /// Unix timestamp of the transfer
+    pub timestamp: i64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl Receipt {
+    /// Size of the Receipt account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // sender
+        32 + // recipient
+        8 +  // amount
+        8 +  // nonce
+        8 +  // timestamp
+        1;   // bump
+
+    /// `sha256(sender || recipient || amount || nonce)`, integers little-endian
+    pub fn seed_hash(sender: &Pubkey, recipient: &Pubkey, amount: u64, nonce: u64) -> [u8; 32] {
+        hashv(&[
+            sender.as_ref(),
+            recipient.as_ref(),
+            &amount.to_le_bytes(),
+            &nonce.to_le_bytes(),
+        ])
+        .to_bytes()
+    }
+}
+
+/// Loyalty program settings; the account also holds the SOL paid out on redemption
+#[account]
+pub struct LoyaltyConfig {
+    /// Points credited per lamport sent through `transfer_with_points`
+    pub points_per_lamport: u64,
+    
+    /// Points redeemed for each lamport paid out of the vault
+    pub redeem_points_per_lamport: u64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl LoyaltyConfig {
+    /// Size of the LoyaltyConfig account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        8 +  // points_per_lamport
+        8 +  // redeem_points_per_lamport
+        1;   // bump
+}
+
+/// Loyalty points earned by a sender
+#[account]
+pub struct LoyaltyPoints {
+    /// Sender the points belong to
+    pub owner: Pubkey,
+    
+    /// Unredeemed points
+    pub points: u64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl LoyaltyPoints {
+    /// Size of the LoyaltyPoints account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // owner
+        8 +  // points
+        1;   // bump
+}
+
+/// Number of self-reported region codes; valid codes are `0..REGION_COUNT`
+pub const REGION_COUNT: u8 = 8;
+
+/// Aggregate volume sent from one self-reported region
+#[account]
+pub struct RegionStats {
+    /// Region code the stats belong to
+    pub region: u8,
+    
+    /// Number of transfers reported from the region
+    pub transfer_count: u64,
+    
+    /// Volume reported from the region (in lamports)
+    pub total_volume: u64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl RegionStats {
+    /// Size of the RegionStats account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        1 +  // region
+        8 +  // transfer_count
+        8 +  // total_volume
+        1;   // bump
+
+    /// Record a transfer from the region
+    pub fn record_transfer(&mut self, amount: u64) {
+        self.transfer_count = self.transfer_count.saturating_add(1);
+        self.total_volume = self.total_volume.saturating_add(amount);
+    }
+}
+
+/// Upper bounds (exclusive, in lamports) of all histogram buckets but the last
+pub const HISTOGRAM_BOUNDS: [u64; 3] = [
+    LAMPORTS_PER_SOL / 10,
+    LAMPORTS_PER_SOL,
+    10 * LAMPORTS_PER_SOL,
+];
+
+/// Number of histogram buckets: `< 0.1`, `< 1`, `< 10` and `>= 10` SOL
+pub const HISTOGRAM_BUCKETS: usize = HISTOGRAM_BOUNDS.len() + 1;
+
+/// Count of transfers per size range during one UTC day
+#[account]
+pub struct Histogram {
+    /// UTC day the counts belong to (days since the unix epoch)
+    pub day: i64,
+    
+    /// Transfers per size range, bounded by `HISTOGRAM_BOUNDS`
+    pub buckets: [u64; HISTOGRAM_BUCKETS],
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl Histogram {
+    /// Size of the Histogram account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        8 +  // day
+        8 * HISTOGRAM_BUCKETS + // buckets
+        1;   // bump
+
+    /// Bucket a transfer of `amount` lamports falls into
+    pub fn bucket_for(amount: u64) -> usize {
+        HISTOGRAM_BOUNDS
+            .iter()
+            .position(|&bound| amount < bound)
+            .unwrap_or(HISTOGRAM_BOUNDS.len())
+    }
+
+    /// Count a transfer in its bucket
+    pub fn record_transfer(&mut self, amount: u64) {
+        let bucket = &mut self.buckets[Self::bucket_for(amount)];
+        *bucket = bucket.saturating_add(1);
+    }
+}
+
+/// Volume and count of daily-closed transfers in the current UTC day
+#[account]
+pub struct DailyWindow {
+    /// UTC day being accumulated (days since the unix epoch)
+    pub day: i64,
+    
+    /// Lamports transferred during `day`
+    pub volume: u64,
+    
+    /// Transfers made during `day`
+    pub count: u64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl DailyWindow {
+    /// Size of the DailyWindow account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        8 +  // day
+        8 +  // volume
+        8 +  // count
+        1;   // bump
+
+    /// Count a transfer in the current day
+    pub fn record_transfer(&mut self, amount: u64) {
+        self.volume = self.volume.saturating_add(amount);
+        self.count += 1;
+    }
+}
+
+/// Closing totals of a past UTC day, written when the daily window rolls over
+#[account]
+pub struct DailySnapshot {
+    /// UTC day the totals belong to (days since the unix epoch)
+    pub day: i64,
+    
+    /// Lamports transferred during `day`
+    pub volume: u64,
+    
+    /// Transfers made during `day`
+    pub count: u64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl DailySnapshot {
+    /// Size of the DailySnapshot account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        8 +  // day
+        8 +  // volume
+        8 +  // count
+        1;   // bump
+}
+
+/// Number of recent transfers the moving average covers
+pub const MOVING_AVERAGE_WINDOW: usize = 8;
+
+/// Ring of the most recent transfer amounts, for a sliding-window average
+#[account]
+pub struct MovingAverage {
+    /// Recent transfer amounts in lamports; only the first `count` are valid
+    pub amounts: [u64; MOVING_AVERAGE_WINDOW],
+    
+    /// Slot the next amount is written to
+    pub next: u8,
+    
+    /// Number of valid amounts, up to `MOVING_AVERAGE_WINDOW`
+    pub count: u8,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl MovingAverage {
+    /// Size of the MovingAverage account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        8 * MOVING_AVERAGE_WINDOW + // amounts
+        1 +  // next
+        1 +  // count
+        1;   // bump
+
+    /// Push an amount, overwriting the oldest once the window is full
+    pub fn record_transfer(&mut self, amount: u64) {
+        self.amounts[self.next as usize] = amount;
+        self.next = ((self.next as usize + 1) % MOVING_AVERAGE_WINDOW) as u8;
+        if (self.count as usize) < MOVING_AVERAGE_WINDOW {
+            self.count += 1;
+        }
+    }
+
+    /// Average of the amounts in the window, rounded down; 0 while empty
+    pub fn average(&self) -> u64 {
+        if self.count == 0 {
+            return 0;
+        }
+        // Unused slots are still zero, so summing the whole ring is exact
+        let sum: u128 = self.amounts.iter().map(|&amount| amount as u128).sum();
+        (sum / self.count as u128) as u64
+    }
+}
+
+/// Lamports a sender has sent through capped transfers in the current UTC day
+#[account]
+pub struct SenderDailySpend {
+    /// Sender the spend belongs to
+    pub sender: Pubkey,
+    
+    /// UTC day (days since the unix epoch) that `spent` covers
+    pub day: i64,
+    
+    /// Lamports sent so far on `day`
+    pub spent: u64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl SenderDailySpend {
+    /// Size of the SenderDailySpend account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // sender
+        8 +  // day
+        8 +  // spent
+        1;   // bump
+
+    /// Count `amount` against `cap` for `today`, starting over on a new day
+    pub fn spend(&mut self, amount: u64, today: i64, cap: u64) -> Result<()> {
+        if self.day != today {
+            self.day = today;
+            self.spent = 0;
+        }
+        let spent = self
+            .spent
+            .checked_add(amount)
+            .ok_or(TransferError::MathOverflow)?;
+        require!(cap == 0 || spent <= cap, TransferError::DailyCapExceeded);
+        self.spent = spent;
+        Ok(())
+    }
+}
+
+/// Append-only journal of transfers
+///
+/// Only this header is deserialized; entries are packed after it in
+/// `JournalEntry::SIZE` slots, and the account grows by one slot per append.
+#[account]
+pub struct Journal {
+    /// Entries written so far; the next entry is written at this index
+    pub cursor: u64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl Journal {
+    /// Size of the Journal header in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        8 +  // cursor
+        1;   // bump
+
+    /// Most entries the journal can hold within the 10 MiB account limit
+    pub const MAX_ENTRIES: u64 =
+        ((10 * 1024 * 1024 - Self::SIZE) / JournalEntry::SIZE) as u64;
+
+    /// Account size needed to hold `entries` entries
+    pub fn space_for(entries: u64) -> usize {
+        Self::SIZE + entries as usize * JournalEntry::SIZE
+    }
+
+    /// Byte range of the entry at `index` within the account data
+    pub fn entry_range(index: u64) -> std::ops::Range<usize> {
+        let start = Self::space_for(index);
+        start..start + JournalEntry::SIZE
+    }
+}
+
+/// Fixed-size record of one journaled transfer
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct JournalEntry {
+    /// Sender of the transfer
+    pub sender: Pubkey,
+    
+    /// Recipient of the transfer
+    pub recipient: Pubkey,
+    
+    /// Amount transferred (in lamports)
+    pub amount: u64,
+    
+    /// Unix timestamp of the transfer
+    pub timestamp: i64,
+}
+
+impl JournalEntry {
+    /// Size of a serialized JournalEntry in bytes
+    pub const SIZE: usize = 32 + // sender
+        32 + // recipient
+        8 +  // amount
+        8;   // timestamp
+}
+
+/// Limit, set by a recipient, on how many rate-limited transfers it receives per window
+#[account]
+pub struct RecipientRate {
+    /// Recipient the limit applies to
+    pub recipient: Pubkey,
+    
+    /// Most transfers accepted per window; 0 disables the limit
+    pub max_per_window: u32,
+    
+    /// Length of the window in seconds
+    pub window_secs: u32,
+    
+    /// Unix timestamp at which the current window started
+    pub window_start: i64,
+    
+    /// Transfers received in the current window
+    pub count: u32,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl RecipientRate {
+    /// Size of the RecipientRate account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // recipient
+        4 +  // max_per_window
+        4 +  // window_secs
+        8 +  // window_start
+        4 +  // count
+        1;   // bump
+
+    /// Count a transfer received at `now`, starting a new window once the
+    /// current one has elapsed
+    pub fn record_transfer(&mut self, now: i64) -> Result<()> {
+        if now >= self.window_start + self.window_secs as i64 {
+            self.window_start = now;
+            self.count = 0;
+        }
+        require!(
+            self.max_per_window == 0 || self.count < self.max_per_window,
+            TransferError::RecipientRateLimited
+        );
+        self.count += 1;
+        Ok(())
+    }
+}
+
+/// Payment streamed to a recipient at a fixed rate per second
+#[account]
+pub struct Stream {
+    /// Sender who funded the stream
+    pub sender: Pubkey,
+    
+    /// Recipient the stream pays
+    pub recipient: Pubkey,
+    
+    /// Total deposited in lamports (excludes rent)
+    pub deposit: u64,
+    
+    /// Lamports accruing to the recipient per second
+    pub rate_per_sec: u64,
+    
+    /// Unix timestamp the stream started accruing
+    pub start_ts: i64,
+    
+    /// Lamports already withdrawn by the recipient
+    pub withdrawn: u64,
+    
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl Stream {
+    /// Size of the Stream account in bytes
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // sender
+        32 + // recipient
+        8 +  // deposit
+        8 +  // rate_per_sec
+        8 +  // start_ts
+        8 +  // withdrawn
+        1;   // bump
+
+    /// Lamports accrued to the recipient by `now`, capped at the deposit
+    pub fn accrued(&self, now: i64) -> u64 {
+        let elapsed = now.saturating_sub(self.start_ts).max(0) as u64;
+        elapsed.saturating_mul(self.rate_per_sec).min(self.deposit)
+    }
+
+    /// Lamports accrued but not yet withdrawn
+    pub fn withdrawable(&self, now: i64) -> u64 {
+        self.accrued(now).saturating_sub(self.withdrawn)
+    }
+}
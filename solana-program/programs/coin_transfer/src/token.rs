@@ -0,0 +1,116 @@
+//! Minimal SPL Token helpers for the Coin Transfer program
+//!
+//! Only the handful of token instructions the program needs are encoded here,
+//! using the SPL Token wire format directly.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program::invoke_signed;
+
+use crate::errors::TransferError;
+
+/// SPL Token program ID
+pub const TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+/// Mint of wrapped SOL (wSOL)
+pub const NATIVE_MINT: Pubkey = pubkey!("So11111111111111111111111111111111111111112");
+
+/// Size of an SPL token account in bytes
+const TOKEN_ACCOUNT_LEN: usize = 165;
+
+/// SPL Token instruction tags
+const IX_TRANSFER: u8 = 3;
+const IX_BURN: u8 = 8;
+const IX_SYNC_NATIVE: u8 = 17;
+
+/// Fields of an SPL token account this program inspects
+pub struct TokenAccountData {
+    /// Mint of the tokens held
+    pub mint: Pubkey,
+
+    /// Owner allowed to move the tokens
+    pub owner: Pubkey,
+
+    /// Token balance in base units
+    pub amount: u64,
+}
+
+/// Read the mint, owner and amount out of an SPL token account
+pub fn read_token_account(account: &AccountInfo) -> Result<TokenAccountData> {
+    require_keys_eq!(*account.owner, TOKEN_PROGRAM_ID, TransferError::InvalidTokenAccount);
+
+    let data = account.try_borrow_data()?;
+    require!(data.len() == TOKEN_ACCOUNT_LEN, TransferError::InvalidTokenAccount);
+
+    let mint = Pubkey::try_from(&data[0..32]).map_err(|_| TransferError::InvalidTokenAccount)?;
+    let owner = Pubkey::try_from(&data[32..64]).map_err(|_| TransferError::InvalidTokenAccount)?;
+    let mut amount = [0u8; 8];
+    amount.copy_from_slice(&data[64..72]);
+
+    Ok(TokenAccountData {
+        mint,
+        owner,
+        amount: u64::from_le_bytes(amount),
+    })
+}
+
+/// Transfer tokens between two token accounts of the same mint
+pub fn transfer<'info>(
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let ix = Instruction {
+        program_id: TOKEN_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(from.key(), false),
+            AccountMeta::new(to.key(), false),
+            AccountMeta::new_readonly(authority.key(), true),
+        ],
+        data: encode(IX_TRANSFER, amount),
+    };
+    invoke_signed(&ix, &[from.clone(), to.clone(), authority.clone()], signer_seeds)?;
+    Ok(())
+}
+
+/// Burn tokens from a token account, reducing the mint's supply
+pub fn burn<'info>(
+    account: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let ix = Instruction {
+        program_id: TOKEN_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(account.key(), false),
+            AccountMeta::new(mint.key(), false),
+            AccountMeta::new_readonly(authority.key(), true),
+        ],
+        data: encode(IX_BURN, amount),
+    };
+    invoke_signed(&ix, &[account.clone(), mint.clone(), authority.clone()], signer_seeds)?;
+    Ok(())
+}
+
+/// Sync a native (wSOL) token account's balance with the lamports it holds
+pub fn sync_native<'info>(account: &AccountInfo<'info>) -> Result<()> {
+    let ix = Instruction {
+        program_id: TOKEN_PROGRAM_ID,
+        accounts: vec![AccountMeta::new(account.key(), false)],
+        data: vec![IX_SYNC_NATIVE],
+    };
+    invoke_signed(&ix, std::slice::from_ref(account), &[])?;
+    Ok(())
+}
+
+/// Encode an instruction that takes a single `u64` amount
+fn encode(tag: u8, amount: u64) -> Vec<u8> {
+    let mut data = Vec::with_capacity(9);
+    data.push(tag);
+    data.extend_from_slice(&amount.to_le_bytes());
+    data
+}
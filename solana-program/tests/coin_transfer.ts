@@ -2,7 +2,18 @@ import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { CoinTransfer } from "../target/types/coin_transfer";
 import { expect } from "chai";
+import { createHash } from "crypto";
+import {
+  createMint,
+  getAccount,
+  getMint,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+  NATIVE_MINT,
+  TOKEN_PROGRAM_ID,
+} from "@solana/spl-token";
 import { 
+  Ed25519Program,
   Keypair, 
   LAMPORTS_PER_SOL, 
   SystemProgram,
@@ -22,6 +33,15 @@ describe("coin_transfer", () => {
   let statePda: PublicKey;
   let stateBump: number;
 
+  // Fund a fresh test account from the faucet
+  const airdrop = async (pubkey: PublicKey, sol: number) => {
+    const signature = await provider.connection.requestAirdrop(
+      pubkey,
+      sol * LAMPORTS_PER_SOL
+    );
+    await provider.connection.confirmTransaction(signature);
+  };
+
   before(async () => {
     // Create test accounts
     sender = Keypair.generate();
@@ -63,7 +83,77 @@ describe("coin_transfer", () => {
       const state = await program.account.programState.fetch(statePda);
       expect(state.authority.toBase58()).to.equal(provider.wallet.publicKey.toBase58());
       expect(state.totalTransfers.toNumber()).to.equal(0);
-      expect(state.version).to.equal(1);
+      expect(state.version).to.equal(2);
+      expect(state.configured).to.equal(false);
+    });
+
+    describe("finalize_config", () => {
+      const transfer = () =>
+        program.methods
+          .transferSol(new anchor.BN(0.01 * LAMPORTS_PER_SOL))
+          .accounts({
+            state: statePda,
+            sender: sender.publicKey,
+            recipient: recipient.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([sender])
+          .rpc();
+
+      it("blocks transfers before the configuration is finalized", async () => {
+        try {
+          await transfer();
+          expect.fail("Should have thrown NotInitialized error");
+        } catch (error: any) {
+          expect(error.message).to.include("NotInitialized");
+        }
+      });
+
+      it("blocks the other transfer variants as well", async () => {
+        try {
+          await program.methods
+            .transferSolGuarded(new anchor.BN(0.01 * LAMPORTS_PER_SOL), false)
+            .accounts({
+              sender: sender.publicKey,
+              recipient: recipient.publicKey,
+              systemProgram: SystemProgram.programId,
+            })
+            .signers([sender])
+            .rpc();
+          expect.fail("Should have thrown NotInitialized error");
+        } catch (error: any) {
+          expect(error.message).to.include("NotInitialized");
+        }
+      });
+
+      it("rejects finalizing from a non-authority", async () => {
+        try {
+          await program.methods
+            .finalizeConfig()
+            .accounts({ state: statePda, authority: sender.publicKey })
+            .signers([sender])
+            .rpc();
+          expect.fail("Should have thrown Unauthorized error");
+        } catch (error: any) {
+          expect(error.message).to.include("Unauthorized");
+        }
+      });
+
+      it("accepts transfers once finalized", async () => {
+        await program.methods
+          .finalizeConfig()
+          .accounts({ state: statePda, authority: provider.wallet.publicKey })
+          .rpc();
+
+        const state = await program.account.programState.fetch(statePda);
+        expect(state.configured).to.equal(true);
+
+        const before = await provider.connection.getBalance(recipient.publicKey);
+        await transfer();
+        expect(await provider.connection.getBalance(recipient.publicKey)).to.equal(
+          before + 0.01 * LAMPORTS_PER_SOL
+        );
+      });
     });
   });
 
@@ -84,6 +174,7 @@ describe("coin_transfer", () => {
       const tx = await program.methods
         .transferSol(new anchor.BN(transferAmount))
         .accounts({
+          state: statePda,
           sender: sender.publicKey,
           recipient: recipient.publicKey,
           systemProgram: SystemProgram.programId,
@@ -112,6 +203,7 @@ describe("coin_transfer", () => {
         await program.methods
           .transferSol(new anchor.BN(0))
           .accounts({
+            state: statePda,
             sender: sender.publicKey,
             recipient: recipient.publicKey,
             systemProgram: SystemProgram.programId,
@@ -140,6 +232,7 @@ describe("coin_transfer", () => {
         await program.methods
           .transferSol(new anchor.BN(1 * LAMPORTS_PER_SOL))
           .accounts({
+            state: statePda,
             sender: poorSender.publicKey,
             recipient: recipient.publicKey,
             systemProgram: SystemProgram.programId,
@@ -170,4 +263,5293 @@ describe("coin_transfer", () => {
       console.log("Balance check passed:", balance.toNumber() / LAMPORTS_PER_SOL, "SOL");
     });
   });
+
+  describe("transfer_conditional", () => {
+    const amount = 0.2 * LAMPORTS_PER_SOL;
+    let arbiter: Keypair;
+
+    const escrowFor = (from: PublicKey, to: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("conditional_escrow"), from.toBuffer(), to.toBuffer()],
+        program.programId
+      )[0];
+
+    const openEscrow = async (to: PublicKey, timeoutSecs = 3600) => {
+      await program.methods
+        .transferConditional(new anchor.BN(amount), new anchor.BN(timeoutSecs))
+        .accounts({
+          escrow: escrowFor(sender.publicKey, to),
+          sender: sender.publicKey,
+          recipient: to,
+          arbiter: arbiter.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([sender])
+        .rpc();
+    };
+
+    before(async () => {
+      arbiter = Keypair.generate();
+    });
+
+    it("releases escrowed funds to the recipient on success", async () => {
+      const payee = Keypair.generate().publicKey;
+      await openEscrow(payee);
+
+      const escrow = await program.account.conditionalEscrow.fetch(
+        escrowFor(sender.publicKey, payee)
+      );
+      expect(escrow.arbiter.toBase58()).to.equal(arbiter.publicKey.toBase58());
+      expect(escrow.amount.toNumber()).to.equal(amount);
+
+      await program.methods
+        .resolveConditional(true)
+        .accounts({
+          escrow: escrowFor(sender.publicKey, payee),
+          arbiter: arbiter.publicKey,
+          sender: sender.publicKey,
+          recipient: payee,
+        })
+        .signers([arbiter])
+        .rpc();
+
+      expect(await provider.connection.getBalance(payee)).to.equal(amount);
+      const closed = await provider.connection.getAccountInfo(
+        escrowFor(sender.publicKey, payee)
+      );
+      expect(closed).to.be.null;
+    });
+
+    it("refunds the sender when the condition fails", async () => {
+      const payee = Keypair.generate().publicKey;
+      await openEscrow(payee);
+
+      const senderBefore = await provider.connection.getBalance(sender.publicKey);
+
+      await program.methods
+        .resolveConditional(false)
+        .accounts({
+          escrow: escrowFor(sender.publicKey, payee),
+          arbiter: arbiter.publicKey,
+          sender: sender.publicKey,
+          recipient: payee,
+        })
+        .signers([arbiter])
+        .rpc();
+
+      // Sender gets the escrowed amount plus the escrow's rent back
+      const senderAfter = await provider.connection.getBalance(sender.publicKey);
+      expect(senderAfter).to.be.greaterThan(senderBefore + amount);
+      expect(await provider.connection.getBalance(payee)).to.equal(0);
+    });
+
+    it("rejects resolution by anyone other than the arbiter", async () => {
+      const payee = Keypair.generate().publicKey;
+      const impostor = Keypair.generate();
+      await openEscrow(payee);
+
+      try {
+        await program.methods
+          .resolveConditional(true)
+          .accounts({
+            escrow: escrowFor(sender.publicKey, payee),
+            arbiter: impostor.publicKey,
+            sender: sender.publicKey,
+            recipient: payee,
+          })
+          .signers([impostor])
+          .rpc();
+
+        expect.fail("Should have thrown Unauthorized error");
+      } catch (error: any) {
+        expect(error.message).to.include("Unauthorized");
+        console.log("✓ Correctly rejected unauthorized arbiter");
+      }
+    });
+
+    it("blocks default resolution before the deadline", async () => {
+      const payee = Keypair.generate().publicKey;
+      await openEscrow(payee);
+
+      try {
+        await program.methods
+          .resolveConditionalDefault()
+          .accounts({
+            escrow: escrowFor(sender.publicKey, payee),
+            sender: sender.publicKey,
+          })
+          .rpc();
+
+        expect.fail("Should have thrown EscrowNotExpired error");
+      } catch (error: any) {
+        expect(error.message).to.include("EscrowNotExpired");
+      }
+    });
+
+    it("lets anyone refund the sender after the deadline", async () => {
+      const payee = Keypair.generate().publicKey;
+      await openEscrow(payee, 1);
+
+      // Wait out the arbitration window
+      await new Promise((resolve) => setTimeout(resolve, 3000));
+
+      const senderBefore = await provider.connection.getBalance(sender.publicKey);
+
+      // Provider wallet is neither party nor arbiter
+      await program.methods
+        .resolveConditionalDefault()
+        .accounts({
+          escrow: escrowFor(sender.publicKey, payee),
+          sender: sender.publicKey,
+        })
+        .rpc();
+
+      const senderAfter = await provider.connection.getBalance(sender.publicKey);
+      expect(senderAfter).to.be.greaterThan(senderBefore + amount);
+      expect(await provider.connection.getBalance(payee)).to.equal(0);
+    });
+  });
+
+  describe("transfer_sol_with_fallback", () => {
+    const amount = 0.05 * LAMPORTS_PER_SOL;
+    let payer: Keypair;
+    let frozenRecipient: Keypair;
+    let fallback: Keypair;
+
+    const frozenPda = (recipientKey: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("frozen_recipient"), recipientKey.toBuffer()],
+        program.programId
+      )[0];
+
+    const transferWithFallback = (to: PublicKey, allowFallback: boolean) =>
+      program.methods
+        .transferSolWithFallback(new anchor.BN(amount), allowFallback)
+        .accounts({
+          state: statePda,
+          sender: payer.publicKey,
+          recipient: to,
+          frozen: frozenPda(to),
+          fallback: fallback.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([payer])
+        .rpc();
+
+    before(async () => {
+      payer = Keypair.generate();
+      frozenRecipient = Keypair.generate();
+      fallback = Keypair.generate();
+      await airdrop(payer.publicKey, 1);
+
+      await program.methods
+        .setFallbackRecipient(fallback.publicKey)
+        .accounts({ state: statePda, authority: provider.wallet.publicKey })
+        .rpc();
+
+      await program.methods
+        .freezeRecipient()
+        .accounts({
+          state: statePda,
+          frozen: frozenPda(frozenRecipient.publicKey),
+          recipient: frozenRecipient.publicKey,
+          authority: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    it("routes funds to the fallback when the recipient is frozen", async () => {
+      const fallbackBefore = await provider.connection.getBalance(fallback.publicKey);
+
+      await transferWithFallback(frozenRecipient.publicKey, true);
+
+      const fallbackAfter = await provider.connection.getBalance(fallback.publicKey);
+      expect(fallbackAfter).to.equal(fallbackBefore + amount);
+      expect(
+        await provider.connection.getBalance(frozenRecipient.publicKey)
+      ).to.equal(0);
+    });
+
+    it("fails for a frozen recipient when fallback is not allowed", async () => {
+      try {
+        await transferWithFallback(frozenRecipient.publicKey, false);
+        expect.fail("Should have thrown RecipientFrozen error");
+      } catch (error: any) {
+        expect(error.message).to.include("RecipientFrozen");
+        console.log("✓ Correctly rejected transfer to frozen recipient");
+      }
+    });
+
+    it("pays an unfrozen recipient directly", async () => {
+      const payee = Keypair.generate().publicKey;
+      const fallbackBefore = await provider.connection.getBalance(fallback.publicKey);
+
+      await transferWithFallback(payee, true);
+
+      expect(await provider.connection.getBalance(payee)).to.equal(amount);
+      expect(
+        await provider.connection.getBalance(fallback.publicKey)
+      ).to.equal(fallbackBefore);
+    });
+  });
+
+  describe("transfer_sol_ordered", () => {
+    const amount = 0.01 * LAMPORTS_PER_SOL;
+    let payer: Keypair;
+    let payee: Keypair;
+    let noncePda: PublicKey;
+
+    const transferOrdered = (expectedNonce: number) =>
+      program.methods
+        .transferSolOrdered(new anchor.BN(amount), new anchor.BN(expectedNonce))
+        .accounts({
+          recipientNonce: noncePda,
+          sender: payer.publicKey,
+          recipient: payee.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([payer])
+        .rpc();
+
+    before(async () => {
+      payer = Keypair.generate();
+      payee = Keypair.generate();
+      await airdrop(payer.publicKey, 1);
+
+      [noncePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("recipient_nonce"), payee.publicKey.toBuffer()],
+        program.programId
+      );
+    });
+
+    it("accepts transfers presenting the current nonce in order", async () => {
+      await transferOrdered(0);
+      await transferOrdered(1);
+
+      const nonce = await program.account.recipientNonce.fetch(noncePda);
+      expect(nonce.nonce.toNumber()).to.equal(2);
+      expect(await provider.connection.getBalance(payee.publicKey)).to.equal(2 * amount);
+    });
+
+    it("rejects a stale nonce", async () => {
+      try {
+        await transferOrdered(1);
+        expect.fail("Should have thrown InvalidNonce error");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidNonce");
+        console.log("✓ Correctly rejected stale nonce");
+      }
+    });
+
+    it("rejects a nonce from the future", async () => {
+      try {
+        await transferOrdered(5);
+        expect.fail("Should have thrown InvalidNonce error");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidNonce");
+      }
+
+      const nonce = await program.account.recipientNonce.fetch(noncePda);
+      expect(nonce.nonce.toNumber()).to.equal(2);
+    });
+  });
+
+  describe("transfer_sol_batched", () => {
+    const senderStatsPda = (senderKey: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("sender_stats"), senderKey.toBuffer()],
+        program.programId
+      )[0];
+
+    const transferBatched = (from: Keypair, lamports: number) =>
+      program.methods
+        .transferSolBatched(new anchor.BN(lamports))
+        .accounts({
+          senderStats: senderStatsPda(from.publicKey),
+          sender: from.publicKey,
+          recipient: recipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([from])
+        .rpc();
+
+    const flush = (from: Keypair) =>
+      program.methods
+        .flushStats()
+        .accounts({
+          state: statePda,
+          senderStats: senderStatsPda(from.publicKey),
+        })
+        .rpc();
+
+    it("flushes totals equal to the sum of per-sender accumulations", async () => {
+      const alice = Keypair.generate();
+      const bob = Keypair.generate();
+      await airdrop(alice.publicKey, 1);
+      await airdrop(bob.publicKey, 1);
+
+      const stateBefore = await program.account.programState.fetch(statePda);
+
+      await transferBatched(alice, 1_000_000);
+      await transferBatched(alice, 2_000_000);
+      await transferBatched(bob, 3_000_000);
+
+      // Nothing reaches the global account until a flush
+      const stateMid = await program.account.programState.fetch(statePda);
+      expect(stateMid.totalTransfers.toNumber()).to.equal(
+        stateBefore.totalTransfers.toNumber()
+      );
+
+      const aliceStats = await program.account.senderStats.fetch(
+        senderStatsPda(alice.publicKey)
+      );
+      const bobStats = await program.account.senderStats.fetch(
+        senderStatsPda(bob.publicKey)
+      );
+      const pendingTransfers =
+        aliceStats.pendingTransfers.toNumber() + bobStats.pendingTransfers.toNumber();
+      const pendingVolume =
+        aliceStats.pendingVolume.toNumber() + bobStats.pendingVolume.toNumber();
+      expect(pendingTransfers).to.equal(3);
+      expect(pendingVolume).to.equal(6_000_000);
+
+      await flush(alice);
+      await flush(bob);
+
+      const stateAfter = await program.account.programState.fetch(statePda);
+      expect(stateAfter.totalTransfers.toNumber()).to.equal(
+        stateBefore.totalTransfers.toNumber() + pendingTransfers
+      );
+      expect(stateAfter.totalVolume.toNumber()).to.equal(
+        stateBefore.totalVolume.toNumber() + pendingVolume
+      );
+
+      const flushed = await program.account.senderStats.fetch(
+        senderStatsPda(alice.publicKey)
+      );
+      expect(flushed.pendingTransfers.toNumber()).to.equal(0);
+      expect(flushed.pendingVolume.toNumber()).to.equal(0);
+      expect(flushed.transferCount.toNumber()).to.equal(2);
+    });
+  });
+
+  describe("transfer_token_with_fee", () => {
+    const feeBps = 100; // 1% fee
+    const burnBps = 5_000; // half of the fee is burned
+    let feeConfigPda: PublicKey;
+    let treasury: Keypair;
+    let mint: PublicKey;
+    let senderToken: PublicKey;
+    let recipientToken: PublicKey;
+    let treasuryToken: PublicKey;
+    let tokenSender: Keypair;
+
+    before(async () => {
+      treasury = Keypair.generate();
+      tokenSender = Keypair.generate();
+      await airdrop(tokenSender.publicKey, 1);
+
+      [feeConfigPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("fee_config")],
+        program.programId
+      );
+
+      const payer = (provider.wallet as anchor.Wallet).payer;
+      mint = await createMint(provider.connection, payer, payer.publicKey, null, 6);
+      senderToken = (
+        await getOrCreateAssociatedTokenAccount(
+          provider.connection, payer, mint, tokenSender.publicKey
+        )
+      ).address;
+      recipientToken = (
+        await getOrCreateAssociatedTokenAccount(
+          provider.connection, payer, mint, recipient.publicKey
+        )
+      ).address;
+      treasuryToken = (
+        await getOrCreateAssociatedTokenAccount(
+          provider.connection, payer, mint, treasury.publicKey
+        )
+      ).address;
+      await mintTo(provider.connection, payer, mint, senderToken, payer, 1_000_000);
+
+      await program.methods
+        .setFee(feeBps, burnBps, treasury.publicKey)
+        .accounts({
+          state: statePda,
+          feeConfig: feeConfigPda,
+          authority: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    it("splits the fee between the treasury and a burn", async () => {
+      const amount = 100_000;
+      const supplyBefore = (await getMint(provider.connection, mint)).supply;
+
+      await program.methods
+        .transferTokenWithFee(new anchor.BN(amount))
+        .accounts({
+          state: statePda,
+          feeConfig: feeConfigPda,
+          sender: tokenSender.publicKey,
+          mint,
+          senderToken,
+          recipientToken,
+          treasuryToken,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([tokenSender])
+        .rpc();
+
+      const fee = (amount * feeBps) / 10_000;
+      const burned = (fee * burnBps) / 10_000;
+
+      const received = await getAccount(provider.connection, recipientToken);
+      const collected = await getAccount(provider.connection, treasuryToken);
+      const supplyAfter = (await getMint(provider.connection, mint)).supply;
+
+      expect(Number(received.amount)).to.equal(amount - fee);
+      expect(Number(collected.amount)).to.equal(fee - burned);
+      expect(Number(supplyBefore - supplyAfter)).to.equal(burned);
+    });
+
+    it("rejects a treasury account not owned by the configured treasury", async () => {
+      try {
+        await program.methods
+          .transferTokenWithFee(new anchor.BN(10_000))
+          .accounts({
+            state: statePda,
+            feeConfig: feeConfigPda,
+            sender: tokenSender.publicKey,
+            mint,
+            senderToken,
+            recipientToken,
+            treasuryToken: recipientToken,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([tokenSender])
+          .rpc();
+
+        expect.fail("Should have thrown InvalidTokenAccount error");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidTokenAccount");
+      }
+    });
+
+    it("accrues rounded-away fees until they add up to one unit", async () => {
+      // 1% of 10 units is 0.1 units, which rounds down to no fee at all
+      const amount = 10;
+      const fraction = amount * feeBps;
+      const transferTiny = async () => {
+        const before = Number((await getAccount(provider.connection, recipientToken)).amount);
+        await program.methods
+          .transferTokenWithFee(new anchor.BN(amount))
+          .accounts({
+            state: statePda,
+            feeConfig: feeConfigPda,
+            sender: tokenSender.publicKey,
+            mint,
+            senderToken,
+            recipientToken,
+            treasuryToken,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([tokenSender])
+          .rpc();
+        const after = Number((await getAccount(provider.connection, recipientToken)).amount);
+        return amount - (after - before);
+      };
+
+      const pendingFees = async () =>
+        (await program.account.feeConfig.fetch(feeConfigPda)).pendingFees.toNumber();
+      const pending = await pendingFees();
+      const freeTransfers = Math.ceil((10_000 - pending) / fraction) - 1;
+
+      for (let i = 0; i < freeTransfers; i++) {
+        expect(await transferTiny()).to.equal(0);
+      }
+      expect(await transferTiny()).to.equal(1);
+
+      expect(await pendingFees()).to.equal(pending + (freeTransfers + 1) * fraction - 10_000);
+    });
+  });
+
+  describe("program_state layout", () => {
+    it("allocates exactly the size of the ProgramState struct", async () => {
+      const info = await provider.connection.getAccountInfo(statePda);
+      expect(info).to.not.be.null;
+
+      // `size` is derived from the IDL layout, so it tracks the struct itself
+      expect(info!.data.length).to.equal(program.account.programState.size);
+      // 121-byte v1 layout plus the 128-byte v2 extension
+      expect(info!.data.length).to.equal(249);
+    });
+
+    it("treats migrate_state on a current-layout account as a no-op", async () => {
+      const before = await program.account.programState.fetch(statePda);
+
+      await program.methods
+        .migrateState()
+        .accounts({
+          state: statePda,
+          authority: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const info = await provider.connection.getAccountInfo(statePda);
+      expect(info!.data.length).to.equal(249);
+      const after = await program.account.programState.fetch(statePda);
+      expect(after.version).to.equal(2);
+      expect(after.totalTransfers.toNumber()).to.equal(before.totalTransfers.toNumber());
+    });
+  });
+
+  describe("transfer_sol_compact", () => {
+    it("logs a compact record that decodes back into the transfer fields", async () => {
+      const payer = Keypair.generate();
+      const payee = Keypair.generate();
+      const amount = 12_345_678;
+      await airdrop(payer.publicKey, 1);
+
+      const signature = await program.methods
+        .transferSolCompact(new anchor.BN(amount))
+        .accounts({
+          state: statePda,
+          sender: payer.publicKey,
+          recipient: payee.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([payer])
+        .rpc({ commitment: "confirmed" });
+
+      const tx = await provider.connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      const line = tx!.meta!.logMessages!.find((log) =>
+        log.startsWith("Program data: ")
+      );
+      expect(line).to.not.be.undefined;
+
+      const data = Buffer.from(line!.slice("Program data: ".length), "base64");
+      expect(data.length).to.equal(81);
+      expect(data[0]).to.equal(0x01);
+      expect(new PublicKey(data.subarray(1, 33)).toBase58()).to.equal(
+        payer.publicKey.toBase58()
+      );
+      expect(new PublicKey(data.subarray(33, 65)).toBase58()).to.equal(
+        payee.publicKey.toBase58()
+      );
+      expect(Number(data.readBigUInt64LE(65))).to.equal(amount);
+      expect(Number(data.readBigInt64LE(73))).to.be.closeTo(tx!.blockTime!, 5);
+    });
+  });
+
+  describe("set_fee_discount", () => {
+    const feeBps = 100;
+    const discountFeeBps = 10;
+    const discountMinBalance = 2 * LAMPORTS_PER_SOL;
+    const amount = 100_000;
+    let feeConfigPda: PublicKey;
+    let treasury: Keypair;
+    let mint: PublicKey;
+    let treasuryToken: PublicKey;
+    let recipientToken: PublicKey;
+
+    // Create a token holder funded with `sol` SOL and 1,000,000 tokens
+    const fundedHolder = async (sol: number) => {
+      const payer = (provider.wallet as anchor.Wallet).payer;
+      const holder = Keypair.generate();
+      await airdrop(holder.publicKey, sol);
+      const token = (
+        await getOrCreateAssociatedTokenAccount(
+          provider.connection, payer, mint, holder.publicKey
+        )
+      ).address;
+      await mintTo(provider.connection, payer, mint, token, payer, 1_000_000);
+      return { holder, token };
+    };
+
+    const feeCharged = async (holder: Keypair, token: PublicKey) => {
+      const before = Number((await getAccount(provider.connection, treasuryToken)).amount);
+      await program.methods
+        .transferTokenWithFee(new anchor.BN(amount))
+        .accounts({
+          state: statePda,
+          feeConfig: feeConfigPda,
+          sender: holder.publicKey,
+          mint,
+          senderToken: token,
+          recipientToken,
+          treasuryToken,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([holder])
+        .rpc();
+      const after = Number((await getAccount(provider.connection, treasuryToken)).amount);
+      return after - before;
+    };
+
+    before(async () => {
+      const payer = (provider.wallet as anchor.Wallet).payer;
+      treasury = Keypair.generate();
+      [feeConfigPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("fee_config")],
+        program.programId
+      );
+
+      mint = await createMint(provider.connection, payer, payer.publicKey, null, 6);
+      treasuryToken = (
+        await getOrCreateAssociatedTokenAccount(
+          provider.connection, payer, mint, treasury.publicKey
+        )
+      ).address;
+      recipientToken = (
+        await getOrCreateAssociatedTokenAccount(
+          provider.connection, payer, mint, recipient.publicKey
+        )
+      ).address;
+
+      await program.methods
+        .setFee(feeBps, 0, treasury.publicKey)
+        .accounts({
+          state: statePda,
+          feeConfig: feeConfigPda,
+          authority: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      await program.methods
+        .setFeeDiscount(new anchor.BN(discountMinBalance), discountFeeBps)
+        .accounts({
+          state: statePda,
+          feeConfig: feeConfigPda,
+          authority: provider.wallet.publicKey,
+        })
+        .rpc();
+    });
+
+    it("charges the discounted fee to a well-funded sender", async () => {
+      const { holder, token } = await fundedHolder(5);
+      expect(await feeCharged(holder, token)).to.equal((amount * discountFeeBps) / 10_000);
+    });
+
+    it("charges the full fee to an under-funded sender", async () => {
+      const { holder, token } = await fundedHolder(1);
+      expect(await feeCharged(holder, token)).to.equal((amount * feeBps) / 10_000);
+    });
+  });
+
+  describe("transfer_sol_recorded", () => {
+    const depth = 16;
+    let accumulatorPda: PublicKey;
+    let chainPda: PublicKey;
+    let payer: Keypair;
+
+    const sha256 = (...parts: Buffer[]) =>
+      createHash("sha256").update(Buffer.concat(parts)).digest();
+
+    const u64 = (value: number) => {
+      const buf = Buffer.alloc(8);
+      buf.writeBigUInt64LE(BigInt(value));
+      return buf;
+    };
+
+    const recordPda = (index: number) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("transfer_record"), u64(index)],
+        program.programId
+      )[0];
+
+    const leafOf = (record: any) =>
+      sha256(
+        record.sender.toBuffer(),
+        record.recipient.toBuffer(),
+        u64(record.amount.toNumber()),
+        u64(record.timestamp.toNumber()),
+        u64(record.index.toNumber())
+      );
+
+    // Root of the depth-16 tree with empty slots hashed as zero subtrees
+    const merkleRoot = (leaves: Buffer[]) => {
+      let nodes = leaves;
+      let zero = Buffer.alloc(32);
+      for (let level = 0; level < depth; level++) {
+        if (nodes.length % 2 === 1) nodes = [...nodes, zero];
+        const next: Buffer[] = [];
+        for (let i = 0; i < nodes.length; i += 2) next.push(sha256(nodes[i], nodes[i + 1]));
+        nodes = next.length ? next : [sha256(zero, zero)];
+        zero = sha256(zero, zero);
+      }
+      return nodes[0];
+    };
+
+    const leafCount = async () => {
+      const info = await provider.connection.getAccountInfo(accumulatorPda);
+      if (!info) return 0;
+      const accumulator = await program.account.transferAccumulator.fetch(accumulatorPda);
+      return accumulator.leafCount.toNumber();
+    };
+
+    const transferRecorded = async (lamports: number, to = recipient.publicKey) => {
+      const index = await leafCount();
+      await program.methods
+        .transferSolRecorded(new anchor.BN(lamports))
+        .accounts({
+          state: statePda,
+          accumulator: accumulatorPda,
+          record: recordPda(index),
+          chain: chainPda,
+          sender: payer.publicKey,
+          recipient: to,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([payer])
+        .rpc();
+      return index;
+    };
+
+    before(async () => {
+      payer = Keypair.generate();
+      await airdrop(payer.publicKey, 1);
+      [accumulatorPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("transfer_accumulator")],
+        program.programId
+      );
+      [chainPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("sender_chain"), payer.publicKey.toBuffer()],
+        program.programId
+      );
+    });
+
+    it("creates a record and folds its leaf into the root", async () => {
+      const first = await transferRecorded(1_000_000);
+      const second = await transferRecorded(2_000_000);
+
+      const record = await program.account.transferRecord.fetch(recordPda(second));
+      expect(record.sender.toBase58()).to.equal(payer.publicKey.toBase58());
+      expect(record.recipient.toBase58()).to.equal(recipient.publicKey.toBase58());
+      expect(record.amount.toNumber()).to.equal(2_000_000);
+      expect(record.index.toNumber()).to.equal(first + 1);
+
+      // Rebuild the tree from every record and compare with the on-chain root
+      const accumulator = await program.account.transferAccumulator.fetch(accumulatorPda);
+      const leaves: Buffer[] = [];
+      for (let i = 0; i < accumulator.leafCount.toNumber(); i++) {
+        leaves.push(leafOf(await program.account.transferRecord.fetch(recordPda(i))));
+      }
+      expect(Buffer.from(accumulator.root).toString("hex")).to.equal(
+        merkleRoot(leaves).toString("hex")
+      );
+    });
+
+    it("links each record to the sender's previous one", async () => {
+      const first = await transferRecorded(1_000_000);
+      const second = await transferRecorded(1_000_000);
+
+      const secondRecord = await program.account.transferRecord.fetch(recordPda(second));
+      expect(secondRecord.prev.toBase58()).to.equal(recordPda(first).toBase58());
+
+      const chain = await program.account.senderChain.fetch(chainPda);
+      expect(chain.lastRecord.toBase58()).to.equal(recordPda(second).toBase58());
+
+      // Walking back from the head only visits this sender's records
+      let cursor = chain.lastRecord;
+      while (!cursor.equals(PublicKey.default)) {
+        const record = await program.account.transferRecord.fetch(cursor);
+        expect(record.sender.toBase58()).to.equal(payer.publicKey.toBase58());
+        cursor = record.prev;
+      }
+    });
+
+    describe("max_records", () => {
+      const closeRecord = (index: number) =>
+        program.methods
+          .closeRecord()
+          .accounts({ record: recordPda(index), chain: chainPda, sender: payer.publicKey })
+          .signers([payer])
+          .rpc();
+
+      const setMaxRecords = (max: number) =>
+        program.methods
+          .setMaxRecords(max)
+          .accounts({ state: statePda, authority: provider.wallet.publicKey })
+          .rpc();
+
+      after(async () => {
+        await setMaxRecords(0);
+      });
+
+      it("rejects a new record once the cap is reached", async () => {
+        const open = (await program.account.senderChain.fetch(chainPda)).activeRecords;
+        await setMaxRecords(open + 1);
+
+        const last = await transferRecorded(1_000_000);
+        expect((await program.account.senderChain.fetch(chainPda)).activeRecords).to.equal(
+          open + 1
+        );
+
+        try {
+          await transferRecorded(1_000_000);
+          expect.fail("Should have thrown TooManyRecords error");
+        } catch (error: any) {
+          expect(error.message).to.include("TooManyRecords");
+        }
+
+        // Closing a record frees a slot
+        await closeRecord(last);
+        expect(await provider.connection.getAccountInfo(recordPda(last))).to.be.null;
+        await transferRecorded(1_000_000);
+      });
+
+      it("only lets the sender close a record", async () => {
+        const index = (await leafCount()) - 1;
+        const other = Keypair.generate();
+        await airdrop(other.publicKey, 1);
+
+        try {
+          await program.methods
+            .closeRecord()
+            .accounts({ record: recordPda(index), chain: chainPda, sender: other.publicKey })
+            .signers([other])
+            .rpc();
+          expect.fail("Should have rejected a close by someone else");
+        } catch (error: any) {
+          expect(error.message).to.include("Unauthorized");
+        }
+      });
+    });
+
+    describe("refund_transfer", () => {
+      const original = 0.3 * LAMPORTS_PER_SOL;
+      let payee: Keypair;
+      let index: number;
+
+      const refund = (lamports: number) =>
+        program.methods
+          .refundTransfer(new anchor.BN(lamports))
+          .accounts({
+            record: recordPda(index),
+            recipient: payee.publicKey,
+            sender: payer.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([payee])
+          .rpc();
+
+      before(async () => {
+        payee = Keypair.generate();
+        index = await transferRecorded(original, payee.publicKey);
+      });
+
+      it("allows partial refunds summing to the original amount", async () => {
+        const senderBefore = await provider.connection.getBalance(payer.publicKey);
+
+        await refund(0.1 * LAMPORTS_PER_SOL);
+        await refund(0.2 * LAMPORTS_PER_SOL);
+
+        const record = await program.account.transferRecord.fetch(recordPda(index));
+        expect(record.refundedAmount.toNumber()).to.equal(original);
+        expect(await provider.connection.getBalance(payer.publicKey)).to.equal(
+          senderBefore + original
+        );
+      });
+
+      it("rejects a refund beyond the original amount", async () => {
+        try {
+          await refund(1);
+          expect.fail("Should have thrown AlreadyRefunded error");
+        } catch (error: any) {
+          expect(error.message).to.include("AlreadyRefunded");
+        }
+      });
+
+      it("refunds while transfers are paused", async () => {
+        index = await transferRecorded(original, payee.publicKey);
+        const setPaused = (paused: boolean) =>
+          program.methods
+            .setPaused(paused)
+            .accounts({ state: statePda, authority: provider.wallet.publicKey })
+            .rpc();
+
+        await setPaused(true);
+        try {
+          await refund(original);
+        } finally {
+          await setPaused(false);
+        }
+
+        const record = await program.account.transferRecord.fetch(recordPda(index));
+        expect(record.refundedAmount.toNumber()).to.equal(original);
+      });
+    });
+  });
+
+  describe("transfer_sol_denominated", () => {
+    let payer: Keypair;
+
+    const transferDenominated = (to: PublicKey, amount: anchor.BN, denom: number) =>
+      program.methods
+        .transferSolDenominated(amount, denom)
+        .accounts({
+          state: statePda,
+          sender: payer.publicKey,
+          recipient: to,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([payer])
+        .rpc();
+
+    before(async () => {
+      payer = Keypair.generate();
+      await airdrop(payer.publicKey, 3);
+    });
+
+    it("moves the same lamports for lamport and SOL denominations", async () => {
+      const inLamports = Keypair.generate().publicKey;
+      const inSol = Keypair.generate().publicKey;
+
+      await transferDenominated(inLamports, new anchor.BN(LAMPORTS_PER_SOL), 0);
+      await transferDenominated(inSol, new anchor.BN(1), 1);
+
+      const lamportsReceived = await provider.connection.getBalance(inLamports);
+      const solReceived = await provider.connection.getBalance(inSol);
+      expect(lamportsReceived).to.equal(LAMPORTS_PER_SOL);
+      expect(solReceived).to.equal(lamportsReceived);
+    });
+
+    it("rejects an unknown denomination", async () => {
+      try {
+        await transferDenominated(recipient.publicKey, new anchor.BN(1), 2);
+        expect.fail("Should have thrown InvalidDenomination error");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidDenomination");
+      }
+    });
+
+    it("rejects a SOL amount that overflows when converted", async () => {
+      try {
+        await transferDenominated(
+          recipient.publicKey,
+          new anchor.BN("18446744073709551615"),
+          1
+        );
+        expect.fail("Should have thrown InvalidAmount error");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidAmount");
+      }
+    });
+  });
+
+  describe("transfer_sol balance reporting", () => {
+    it("reports the sender's on-chain balance after the transfer", async () => {
+      const payer = Keypair.generate();
+      await airdrop(payer.publicKey, 1);
+
+      const signature = await program.methods
+        .transferSol(new anchor.BN(0.1 * LAMPORTS_PER_SOL))
+        .accounts({
+          state: statePda,
+          sender: payer.publicKey,
+          recipient: recipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([payer])
+        .rpc({ commitment: "confirmed" });
+
+      const onChain = await provider.connection.getBalance(payer.publicKey, "confirmed");
+      const tx = await provider.connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      const logs = tx!.meta!.logMessages!;
+
+      // Event
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const events = [...parser.parseLogs(logs)];
+      const executed = events.find((event) => event.name === "transferExecuted");
+      expect(executed).to.not.be.undefined;
+      expect(executed!.data.senderBalanceAfter.toNumber()).to.equal(onChain);
+
+      // Return data
+      const prefix = `Program return: ${program.programId.toBase58()} `;
+      const returned = logs.find((log) => log.startsWith(prefix));
+      expect(returned).to.not.be.undefined;
+      const value = Buffer.from(returned!.slice(prefix.length), "base64").readBigUInt64LE(0);
+      expect(Number(value)).to.equal(onChain);
+    });
+  });
+
+  describe("transfer_sol_fixed", () => {
+    const ticketPrice = 0.25 * LAMPORTS_PER_SOL;
+    let allowedPda: PublicKey;
+    let payer: Keypair;
+
+    const transferFixed = (lamports: number) =>
+      program.methods
+        .transferSolFixed(new anchor.BN(lamports))
+        .accounts({
+          allowedAmounts: allowedPda,
+          sender: payer.publicKey,
+          recipient: recipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([payer])
+        .rpc();
+
+    before(async () => {
+      payer = Keypair.generate();
+      await airdrop(payer.publicKey, 1);
+      [allowedPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("allowed_amounts")],
+        program.programId
+      );
+
+      await program.methods
+        .setAllowedAmounts([new anchor.BN(ticketPrice), new anchor.BN(2 * ticketPrice)])
+        .accounts({
+          state: statePda,
+          allowedAmounts: allowedPda,
+          authority: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    it("accepts an allowed amount", async () => {
+      const before = await provider.connection.getBalance(recipient.publicKey);
+      await transferFixed(ticketPrice);
+      const after = await provider.connection.getBalance(recipient.publicKey);
+      expect(after).to.equal(before + ticketPrice);
+    });
+
+    it("rejects an amount outside the list", async () => {
+      try {
+        await transferFixed(ticketPrice + 1);
+        expect.fail("Should have thrown AmountNotAllowed error");
+      } catch (error: any) {
+        expect(error.message).to.include("AmountNotAllowed");
+      }
+    });
+
+    it("bounds the size of the list", async () => {
+      const tooMany = Array.from({ length: 17 }, (_, i) => new anchor.BN(i + 1));
+      try {
+        await program.methods
+          .setAllowedAmounts(tooMany)
+          .accounts({
+            state: statePda,
+            allowedAmounts: allowedPda,
+            authority: provider.wallet.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        expect.fail("Should have thrown TooManyAllowedAmounts error");
+      } catch (error: any) {
+        expect(error.message).to.include("TooManyAllowedAmounts");
+      }
+    });
+  });
+
+  describe("transfer_sol_with_priority_fee", () => {
+    it("records the reported priority fee without charging it", async () => {
+      const payer = Keypair.generate();
+      const amount = 0.01 * LAMPORTS_PER_SOL;
+      const priorityFee = 50_000;
+      await airdrop(payer.publicKey, 1);
+
+      const [statsPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("priority_fee_stats")],
+        program.programId
+      );
+      const existing = await provider.connection.getAccountInfo(statsPda);
+      const before = existing
+        ? await program.account.priorityFeeStats.fetch(statsPda)
+        : null;
+      const recipientBefore = await provider.connection.getBalance(recipient.publicKey);
+
+      await program.methods
+        .transferSolWithPriorityFee(new anchor.BN(amount), new anchor.BN(priorityFee))
+        .accounts({
+          priorityFeeStats: statsPda,
+          sender: payer.publicKey,
+          recipient: recipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([payer])
+        .rpc();
+
+      const stats = await program.account.priorityFeeStats.fetch(statsPda);
+      expect(stats.lastPriorityFee.toNumber()).to.equal(priorityFee);
+      expect(stats.totalPriorityFee.toNumber()).to.equal(
+        (before?.totalPriorityFee.toNumber() ?? 0) + priorityFee
+      );
+      expect(stats.volume.toNumber()).to.equal((before?.volume.toNumber() ?? 0) + amount);
+      expect(await provider.connection.getBalance(recipient.publicKey)).to.equal(
+        recipientBefore + amount
+      );
+    });
+  });
+
+  describe("transfer_to_category", () => {
+    const merchantCategory = 7;
+    let payer: Keypair;
+    let merchant: Keypair;
+    let merchantStats: PublicKey;
+
+    const transferToCategory = (expectedCategory: number) =>
+      program.methods
+        .transferToCategory(new anchor.BN(0.01 * LAMPORTS_PER_SOL), expectedCategory)
+        .accounts({
+          recipientStats: merchantStats,
+          sender: payer.publicKey,
+          recipient: merchant.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([payer])
+        .rpc();
+
+    before(async () => {
+      payer = Keypair.generate();
+      merchant = Keypair.generate();
+      await airdrop(payer.publicKey, 1);
+      await airdrop(merchant.publicKey, 1);
+
+      [merchantStats] = PublicKey.findProgramAddressSync(
+        [Buffer.from("recipient_stats"), merchant.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .registerRecipient(merchantCategory)
+        .accounts({
+          recipientStats: merchantStats,
+          recipient: merchant.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([merchant])
+        .rpc();
+    });
+
+    it("transfers when the recipient's category matches", async () => {
+      await transferToCategory(merchantCategory);
+
+      const stats = await program.account.recipientStats.fetch(merchantStats);
+      expect(stats.category).to.equal(merchantCategory);
+      expect(stats.transferCount.toNumber()).to.equal(1);
+      expect(stats.totalReceived.toNumber()).to.equal(0.01 * LAMPORTS_PER_SOL);
+    });
+
+    it("rejects a mismatched category", async () => {
+      try {
+        await transferToCategory(merchantCategory + 1);
+        expect.fail("Should have thrown CategoryMismatch error");
+      } catch (error: any) {
+        expect(error.message).to.include("CategoryMismatch");
+      }
+    });
+  });
+
+  describe("volume milestone auto-pause", () => {
+    const amount = 0.1 * LAMPORTS_PER_SOL;
+    let payer: Keypair;
+
+    const transfer = () =>
+      program.methods
+        .transferSol(new anchor.BN(amount))
+        .accounts({
+          state: statePda,
+          sender: payer.publicKey,
+          recipient: recipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([payer])
+        .rpc();
+
+    before(async () => {
+      payer = Keypair.generate();
+      await airdrop(payer.publicKey, 1);
+
+      const state = await program.account.programState.fetch(statePda);
+      const milestone = state.totalVolume.toNumber() + 1.5 * amount;
+      await program.methods
+        .setMilestoneVolume(new anchor.BN(milestone))
+        .accounts({ state: statePda, authority: provider.wallet.publicKey })
+        .rpc();
+    });
+
+    after(async () => {
+      await program.methods
+        .setPaused(false)
+        .accounts({ state: statePda, authority: provider.wallet.publicKey })
+        .rpc();
+    });
+
+    it("pauses once total volume crosses the milestone", async () => {
+      await transfer();
+      expect((await program.account.programState.fetch(statePda)).paused).to.be.false;
+
+      // The crossing transfer itself succeeds
+      await transfer();
+      const state = await program.account.programState.fetch(statePda);
+      expect(state.paused).to.be.true;
+      expect(state.milestoneVolume.toNumber()).to.equal(0);
+    });
+
+    it("rejects transfers until the authority unpauses", async () => {
+      try {
+        await transfer();
+        expect.fail("Should have thrown ProgramPaused error");
+      } catch (error: any) {
+        expect(error.message).to.include("ProgramPaused");
+      }
+
+      await program.methods
+        .setPaused(false)
+        .accounts({ state: statePda, authority: provider.wallet.publicKey })
+        .rpc();
+      await transfer();
+    });
+
+    it("counts volume moved by the other transfer variants", async () => {
+      const before = await program.account.programState.fetch(statePda);
+
+      await program.methods
+        .transferSolGuarded(new anchor.BN(amount), false)
+        .accounts({
+          sender: payer.publicKey,
+          recipient: recipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([payer])
+        .rpc();
+
+      const after = await program.account.programState.fetch(statePda);
+      expect(after.totalTransfers.toNumber()).to.equal(before.totalTransfers.toNumber() + 1);
+      expect(after.totalVolume.toNumber()).to.equal(before.totalVolume.toNumber() + amount);
+    });
+  });
+
+  describe("usd volume tracking", () => {
+    let payer: Keypair;
+
+    const setRate = (microUsd: number) =>
+      program.methods
+        .setSolUsdRate(new anchor.BN(microUsd))
+        .accounts({ state: statePda, authority: provider.wallet.publicKey })
+        .rpc();
+
+    const transfer = (lamports: number) =>
+      program.methods
+        .transferSol(new anchor.BN(lamports))
+        .accounts({
+          state: statePda,
+          sender: payer.publicKey,
+          recipient: recipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([payer])
+        .rpc();
+
+    const usdVolume = async () =>
+      (await program.methods.getUsdVolume().accounts({ state: statePda }).view()).toNumber();
+
+    before(async () => {
+      payer = Keypair.generate();
+      await airdrop(payer.publicKey, 1);
+    });
+
+    after(async () => {
+      await setRate(0);
+    });
+
+    it("values each transfer at the rate in effect", async () => {
+      const start = await usdVolume();
+
+      // $150.00 per SOL
+      await setRate(150_000_000);
+      await transfer(0.1 * LAMPORTS_PER_SOL);
+      expect(await usdVolume()).to.equal(start + 15_000_000);
+
+      // $200.00 per SOL only affects later transfers
+      await setRate(200_000_000);
+      await transfer(0.1 * LAMPORTS_PER_SOL);
+      expect(await usdVolume()).to.equal(start + 35_000_000);
+
+      const state = await program.account.programState.fetch(statePda);
+      expect(state.totalVolumeUsd.toNumber()).to.equal(start + 35_000_000);
+    });
+  });
+
+  describe("transfer_with_receipt", () => {
+    const amount = 0.05 * LAMPORTS_PER_SOL;
+    let payer: Keypair;
+
+    const u64 = (value: number) => {
+      const buf = Buffer.alloc(8);
+      buf.writeBigUInt64LE(BigInt(value));
+      return buf;
+    };
+
+    // Anyone who knows the transfer details can derive the receipt address
+    const receiptPda = (sender: PublicKey, to: PublicKey, lamports: number, nonce: number) => {
+      const hash = createHash("sha256")
+        .update(Buffer.concat([sender.toBuffer(), to.toBuffer(), u64(lamports), u64(nonce)]))
+        .digest();
+      return PublicKey.findProgramAddressSync(
+        [Buffer.from("receipt"), hash],
+        program.programId
+      )[0];
+    };
+
+    const verify = (lamports: number, nonce: number) =>
+      program.methods
+        .verifyReceipt(
+          payer.publicKey,
+          recipient.publicKey,
+          new anchor.BN(lamports),
+          new anchor.BN(nonce)
+        )
+        .accounts({ receipt: receiptPda(payer.publicKey, recipient.publicKey, lamports, nonce) })
+        .view();
+
+    before(async () => {
+      payer = Keypair.generate();
+      await airdrop(payer.publicKey, 1);
+    });
+
+    it("creates a receipt at the derived address", async () => {
+      const nonce = 1;
+      const receipt = receiptPda(payer.publicKey, recipient.publicKey, amount, nonce);
+      const before = await provider.connection.getBalance(recipient.publicKey);
+
+      await program.methods
+        .transferWithReceipt(new anchor.BN(amount), new anchor.BN(nonce))
+        .accounts({
+          receipt,
+          sender: payer.publicKey,
+          recipient: recipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([payer])
+        .rpc();
+
+      expect(await provider.connection.getBalance(recipient.publicKey)).to.equal(before + amount);
+
+      const account = await program.account.receipt.fetch(receipt);
+      expect(account.sender.toBase58()).to.equal(payer.publicKey.toBase58());
+      expect(account.recipient.toBase58()).to.equal(recipient.publicKey.toBase58());
+      expect(account.amount.toNumber()).to.equal(amount);
+      expect(account.nonce.toNumber()).to.equal(nonce);
+
+      expect(await verify(amount, nonce)).to.equal(true);
+    });
+
+    it("reports no receipt for details that were never paid", async () => {
+      expect(await verify(amount, 2)).to.equal(false);
+      expect(await verify(amount + 1, 1)).to.equal(false);
+    });
+
+    it("rejects reusing a nonce for identical details", async () => {
+      try {
+        await program.methods
+          .transferWithReceipt(new anchor.BN(amount), new anchor.BN(1))
+          .accounts({
+            receipt: receiptPda(payer.publicKey, recipient.publicKey, amount, 1),
+            sender: payer.publicKey,
+            recipient: recipient.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([payer])
+          .rpc();
+        expect.fail("Should have rejected a duplicate receipt");
+      } catch (error: any) {
+        expect(error.message).to.include("already in use");
+      }
+    });
+  });
+
+  describe("pause exemption", () => {
+    let exemptSender: Keypair;
+    let normalSender: Keypair;
+    let exemptPda: PublicKey;
+
+    const setPaused = (paused: boolean) =>
+      program.methods
+        .setPaused(paused)
+        .accounts({ state: statePda, authority: provider.wallet.publicKey })
+        .rpc();
+
+    const transfer = (sender: Keypair) =>
+      program.methods
+        .transferSol(new anchor.BN(0.01 * LAMPORTS_PER_SOL))
+        .accounts({
+          state: statePda,
+          sender: sender.publicKey,
+          recipient: recipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([sender])
+        .rpc();
+
+    before(async () => {
+      exemptSender = Keypair.generate();
+      normalSender = Keypair.generate();
+      await airdrop(exemptSender.publicKey, 1);
+      await airdrop(normalSender.publicKey, 1);
+
+      [exemptPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("pause_exempt"), exemptSender.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addPauseExemption()
+        .accounts({
+          state: statePda,
+          exempt: exemptPda,
+          sender: exemptSender.publicKey,
+          authority: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      await setPaused(true);
+    });
+
+    after(async () => {
+      await setPaused(false);
+    });
+
+    it("lets an exempt sender transfer while paused", async () => {
+      const before = await provider.connection.getBalance(recipient.publicKey);
+      await transfer(exemptSender);
+      expect(await provider.connection.getBalance(recipient.publicKey)).to.equal(
+        before + 0.01 * LAMPORTS_PER_SOL
+      );
+    });
+
+    it("blocks a normal sender while paused", async () => {
+      try {
+        await transfer(normalSender);
+        expect.fail("Should have rejected a transfer while paused");
+      } catch (error: any) {
+        expect(error.message).to.include("ProgramPaused");
+      }
+    });
+
+    it("applies the pause and the exemption to the other transfer variants", async () => {
+      const guarded = (sender: Keypair) =>
+        program.methods
+          .transferSolGuarded(new anchor.BN(0.01 * LAMPORTS_PER_SOL), false)
+          .accounts({
+            sender: sender.publicKey,
+            recipient: recipient.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([sender])
+          .rpc();
+
+      await guarded(exemptSender);
+      try {
+        await guarded(normalSender);
+        expect.fail("Should have rejected a transfer while paused");
+      } catch (error: any) {
+        expect(error.message).to.include("ProgramPaused");
+      }
+    });
+
+    it("blocks a sender once the exemption is removed", async () => {
+      await program.methods
+        .removePauseExemption()
+        .accounts({
+          state: statePda,
+          exempt: exemptPda,
+          sender: exemptSender.publicKey,
+          authority: provider.wallet.publicKey,
+        })
+        .rpc();
+
+      try {
+        await transfer(exemptSender);
+        expect.fail("Should have rejected a transfer while paused");
+      } catch (error: any) {
+        expect(error.message).to.include("ProgramPaused");
+      }
+    });
+  });
+
+  describe("net_settle", () => {
+    let partyA: Keypair;
+    let partyB: Keypair;
+
+    const settle = (aToB: number, bToA: number) =>
+      program.methods
+        .netSettle(new anchor.BN(aToB), new anchor.BN(bToA))
+        .accounts({
+          state: statePda,
+          partyA: partyA.publicKey,
+          partyB: partyB.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([partyA, partyB])
+        .rpc();
+
+    const balances = async () => [
+      await provider.connection.getBalance(partyA.publicKey),
+      await provider.connection.getBalance(partyB.publicKey),
+    ];
+
+    before(async () => {
+      partyA = Keypair.generate();
+      partyB = Keypair.generate();
+      await airdrop(partyA.publicKey, 1);
+      await airdrop(partyB.publicKey, 1);
+    });
+
+    it("moves the difference from A to B when A owes more", async () => {
+      const [a, b] = await balances();
+      await settle(30_000_000, 10_000_000);
+      expect(await balances()).to.deep.equal([a - 20_000_000, b + 20_000_000]);
+    });
+
+    it("moves the difference from B to A when B owes more", async () => {
+      const [a, b] = await balances();
+      await settle(5_000_000, 12_000_000);
+      expect(await balances()).to.deep.equal([a + 7_000_000, b - 7_000_000]);
+    });
+
+    it("moves nothing when the debts are equal", async () => {
+      const [a, b] = await balances();
+      await settle(8_000_000, 8_000_000);
+      expect(await balances()).to.deep.equal([a, b]);
+    });
+
+    it("requires both parties to sign", async () => {
+      try {
+        await program.methods
+          .netSettle(new anchor.BN(1_000_000), new anchor.BN(0))
+          .accounts({
+            state: statePda,
+            partyA: partyA.publicKey,
+            partyB: partyB.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([partyA])
+          .rpc();
+        expect.fail("Should have required party B's signature");
+      } catch (error: any) {
+        expect(error.message).to.include("Signature verification failed");
+      }
+    });
+  });
+
+  describe("escrow_timelock", () => {
+    const maxLockSecs = 60;
+    const amount = 0.05 * LAMPORTS_PER_SOL;
+    let payer: Keypair;
+
+    const setMaxLock = (secs: number) =>
+      program.methods
+        .setMaxLockSecs(secs)
+        .accounts({ state: statePda, authority: provider.wallet.publicKey })
+        .rpc();
+
+    const chainTime = async () =>
+      (await provider.connection.getBlockTime(await provider.connection.getSlot()))!;
+
+    const lock = (to: PublicKey, unlockTs: number) =>
+      program.methods
+        .escrowTimelock(new anchor.BN(amount), new anchor.BN(unlockTs))
+        .accounts({
+          state: statePda,
+          timelock: PublicKey.findProgramAddressSync(
+            [Buffer.from("timelock"), payer.publicKey.toBuffer(), to.toBuffer()],
+            program.programId
+          )[0],
+          sender: payer.publicKey,
+          recipient: to,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([payer])
+        .rpc();
+
+    before(async () => {
+      payer = Keypair.generate();
+      await airdrop(payer.publicKey, 1);
+      await setMaxLock(maxLockSecs);
+    });
+
+    after(async () => {
+      await setMaxLock(0);
+    });
+
+    it("accepts a lock at the maximum duration", async () => {
+      const to = Keypair.generate().publicKey;
+      // Chain time only moves forward before execution, so this stays within the limit
+      await lock(to, (await chainTime()) + maxLockSecs);
+
+      const [timelockPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("timelock"), payer.publicKey.toBuffer(), to.toBuffer()],
+        program.programId
+      );
+      const timelock = await program.account.timelock.fetch(timelockPda);
+      expect(timelock.amount.toNumber()).to.equal(amount);
+    });
+
+    it("rejects a lock beyond the maximum duration", async () => {
+      try {
+        await lock(Keypair.generate().publicKey, (await chainTime()) + maxLockSecs + 30);
+        expect.fail("Should have rejected an overlong lock");
+      } catch (error: any) {
+        expect(error.message).to.include("LockTooLong");
+      }
+    });
+
+    it("releases to the recipient once unlocked", async () => {
+      const to = Keypair.generate().publicKey;
+      const [timelockPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("timelock"), payer.publicKey.toBuffer(), to.toBuffer()],
+        program.programId
+      );
+      await lock(to, (await chainTime()) + 2);
+
+      await new Promise((resolve) => setTimeout(resolve, 4000));
+      await program.methods
+        .releaseTimelock()
+        .accounts({ timelock: timelockPda, sender: payer.publicKey, recipient: to })
+        .rpc();
+
+      expect(await provider.connection.getBalance(to)).to.equal(amount);
+      expect(await provider.connection.getAccountInfo(timelockPda)).to.be.null;
+    });
+  });
+
+  describe("transfer_kyc", () => {
+    let payer: Keypair;
+
+    const statsPda = (recipient: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("recipient_stats"), recipient.toBuffer()],
+        program.programId
+      )[0];
+
+    const setKycVerified = (recipient: PublicKey, verified: boolean) =>
+      program.methods
+        .setKycVerified(verified)
+        .accounts({
+          state: statePda,
+          recipientStats: statsPda(recipient),
+          recipient,
+          authority: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+    const transferKyc = (recipient: PublicKey) =>
+      program.methods
+        .transferKyc(new anchor.BN(0.01 * LAMPORTS_PER_SOL))
+        .accounts({
+          recipientStats: statsPda(recipient),
+          sender: payer.publicKey,
+          recipient,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([payer])
+        .rpc();
+
+    before(async () => {
+      payer = Keypair.generate();
+      await airdrop(payer.publicKey, 1);
+    });
+
+    it("transfers to a verified recipient", async () => {
+      const verified = Keypair.generate().publicKey;
+      await setKycVerified(verified, true);
+
+      await transferKyc(verified);
+
+      expect(await provider.connection.getBalance(verified)).to.equal(0.01 * LAMPORTS_PER_SOL);
+      const stats = await program.account.recipientStats.fetch(statsPda(verified));
+      expect(stats.kycVerified).to.equal(true);
+      expect(stats.transferCount.toNumber()).to.equal(1);
+    });
+
+    it("rejects an unverified recipient", async () => {
+      const unverified = Keypair.generate().publicKey;
+      await setKycVerified(unverified, false);
+
+      try {
+        await transferKyc(unverified);
+        expect.fail("Should have thrown RecipientNotVerified error");
+      } catch (error: any) {
+        expect(error.message).to.include("RecipientNotVerified");
+      }
+    });
+
+    it("rejects a recipient whose verification was revoked", async () => {
+      const revoked = Keypair.generate().publicKey;
+      await setKycVerified(revoked, true);
+      await setKycVerified(revoked, false);
+
+      try {
+        await transferKyc(revoked);
+        expect.fail("Should have thrown RecipientNotVerified error");
+      } catch (error: any) {
+        expect(error.message).to.include("RecipientNotVerified");
+      }
+    });
+  });
+
+  describe("loyalty points", () => {
+    const pointsPerLamport = 2;
+    const redeemPointsPerLamport = 100;
+    const amount = 0.01 * LAMPORTS_PER_SOL;
+    let payer: Keypair;
+    let configPda: PublicKey;
+    let pointsPda: PublicKey;
+
+    const pointsBalance = async () =>
+      (await program.account.loyaltyPoints.fetch(pointsPda)).points.toNumber();
+
+    const redeem = (points: number) =>
+      program.methods
+        .redeemPoints(new anchor.BN(points))
+        .accounts({ loyaltyConfig: configPda, points: pointsPda, owner: payer.publicKey })
+        .signers([payer])
+        .rpc();
+
+    before(async () => {
+      payer = Keypair.generate();
+      await airdrop(payer.publicKey, 1);
+
+      [configPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("loyalty_config")],
+        program.programId
+      );
+      [pointsPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("loyalty_points"), payer.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .setLoyaltyConfig(new anchor.BN(pointsPerLamport), new anchor.BN(redeemPointsPerLamport))
+        .accounts({
+          state: statePda,
+          loyaltyConfig: configPda,
+          authority: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      // Fund the redemption vault
+      await provider.sendAndConfirm(
+        new anchor.web3.Transaction().add(
+          SystemProgram.transfer({
+            fromPubkey: provider.wallet.publicKey,
+            toPubkey: configPda,
+            lamports: 0.1 * LAMPORTS_PER_SOL,
+          })
+        )
+      );
+    });
+
+    it("credits points for each transfer", async () => {
+      for (let i = 0; i < 2; i++) {
+        await program.methods
+          .transferWithPoints(new anchor.BN(amount))
+          .accounts({
+            state: statePda,
+            loyaltyConfig: configPda,
+            points: pointsPda,
+            sender: payer.publicKey,
+            recipient: recipient.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([payer])
+          .rpc();
+      }
+
+      expect(await pointsBalance()).to.equal(2 * amount * pointsPerLamport);
+    });
+
+    it("redeems points for SOL from the vault", async () => {
+      const pointsBefore = await pointsBalance();
+      const balanceBefore = await provider.connection.getBalance(payer.publicKey);
+
+      // Points are spent in whole lamports; the 50 left over are kept
+      await redeem(1_000_050);
+
+      expect(await provider.connection.getBalance(payer.publicKey)).to.equal(
+        balanceBefore + 1_000_000 / redeemPointsPerLamport
+      );
+      expect(await pointsBalance()).to.equal(pointsBefore - 1_000_000);
+    });
+
+    it("rejects redeeming more points than held", async () => {
+      try {
+        await redeem((await pointsBalance()) + 1);
+        expect.fail("Should have thrown InsufficientPoints error");
+      } catch (error: any) {
+        expect(error.message).to.include("InsufficientPoints");
+      }
+    });
+  });
+
+  describe("business hours", () => {
+    let payer: Keypair;
+
+    const setHours = (open: number, close: number) =>
+      program.methods
+        .setBusinessHours(open, close)
+        .accounts({ state: statePda, authority: provider.wallet.publicKey })
+        .rpc();
+
+    const transfer = (from: PublicKey, signers: Keypair[]) =>
+      program.methods
+        .transferSol(new anchor.BN(0.01 * LAMPORTS_PER_SOL))
+        .accounts({
+          state: statePda,
+          sender: from,
+          recipient: recipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers(signers)
+        .rpc();
+
+    // Current UTC hour according to the cluster clock
+    const currentHour = async () => {
+      const now = await provider.connection.getBlockTime(await provider.connection.getSlot());
+      return Math.floor((now! % 86_400) / 3_600);
+    };
+
+    before(async () => {
+      payer = Keypair.generate();
+      await airdrop(payer.publicKey, 1);
+    });
+
+    after(async () => {
+      await setHours(0, 0);
+    });
+
+    it("accepts transfers inside the window", async () => {
+      const hour = await currentHour();
+      await setHours(hour, (hour + 1) % 24);
+
+      const before = await provider.connection.getBalance(recipient.publicKey);
+      await transfer(payer.publicKey, [payer]);
+      expect(await provider.connection.getBalance(recipient.publicKey)).to.equal(
+        before + 0.01 * LAMPORTS_PER_SOL
+      );
+    });
+
+    it("rejects transfers outside the window", async () => {
+      const hour = await currentHour();
+      await setHours((hour + 1) % 24, (hour + 2) % 24);
+
+      try {
+        await transfer(payer.publicKey, [payer]);
+        expect.fail("Should have thrown OutsideBusinessHours error");
+      } catch (error: any) {
+        expect(error.message).to.include("OutsideBusinessHours");
+      }
+    });
+
+    it("lets the authority transfer outside the window", async () => {
+      const hour = await currentHour();
+      await setHours((hour + 1) % 24, (hour + 2) % 24);
+
+      const before = await provider.connection.getBalance(recipient.publicKey);
+      await transfer(provider.wallet.publicKey, []);
+      expect(await provider.connection.getBalance(recipient.publicKey)).to.equal(
+        before + 0.01 * LAMPORTS_PER_SOL
+      );
+    });
+
+    it("rejects hours outside 0-23", async () => {
+      try {
+        await setHours(9, 24);
+        expect.fail("Should have thrown InvalidConfig error");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidConfig");
+      }
+    });
+  });
+
+  describe("transfer_with_region", () => {
+    let payer: Keypair;
+
+    const regionPda = (region: number) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("region_stats"), Buffer.from([region])],
+        program.programId
+      )[0];
+
+    const regionVolume = async (region: number) => {
+      const info = await provider.connection.getAccountInfo(regionPda(region));
+      if (!info) return 0;
+      return (await program.account.regionStats.fetch(regionPda(region))).totalVolume.toNumber();
+    };
+
+    const transfer = (lamports: number, region: number) =>
+      program.methods
+        .transferWithRegion(new anchor.BN(lamports), region)
+        .accounts({
+          state: statePda,
+          regionStats: regionPda(region),
+          sender: payer.publicKey,
+          recipient: recipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([payer])
+        .rpc();
+
+    before(async () => {
+      payer = Keypair.generate();
+      await airdrop(payer.publicKey, 1);
+    });
+
+    it("accumulates volume separately per region", async () => {
+      const [before1, before2] = [await regionVolume(1), await regionVolume(2)];
+
+      await transfer(1_000_000, 1);
+      await transfer(2_000_000, 2);
+      await transfer(3_000_000, 1);
+
+      expect(await regionVolume(1)).to.equal(before1 + 4_000_000);
+      expect(await regionVolume(2)).to.equal(before2 + 2_000_000);
+    });
+
+    it("rejects an unknown region code", async () => {
+      try {
+        await transfer(1_000_000, 200);
+        expect.fail("Should have thrown InvalidRegion error");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidRegion");
+      }
+    });
+  });
+
+  describe("swap_sol_for_token", () => {
+    const solAmount = 0.1 * LAMPORTS_PER_SOL;
+    const tokenAmount = 5_000;
+    let buyer: Keypair;
+    let seller: Keypair;
+    let mint: PublicKey;
+    let sellerToken: PublicKey;
+    let buyerToken: PublicKey;
+
+    const swapAccounts = () => ({
+      buyer: buyer.publicKey,
+      seller: seller.publicKey,
+      mint,
+      sellerToken,
+      buyerToken,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    });
+
+    before(async () => {
+      buyer = Keypair.generate();
+      seller = Keypair.generate();
+      await airdrop(buyer.publicKey, 1);
+      await airdrop(seller.publicKey, 1);
+
+      const payer = (provider.wallet as anchor.Wallet).payer;
+      mint = await createMint(provider.connection, payer, payer.publicKey, null, 6);
+      sellerToken = (
+        await getOrCreateAssociatedTokenAccount(provider.connection, payer, mint, seller.publicKey)
+      ).address;
+      buyerToken = (
+        await getOrCreateAssociatedTokenAccount(provider.connection, payer, mint, buyer.publicKey)
+      ).address;
+      await mintTo(provider.connection, payer, mint, sellerToken, payer, 100_000);
+    });
+
+    it("moves SOL and tokens in opposite directions", async () => {
+      const buyerBefore = await provider.connection.getBalance(buyer.publicKey);
+      const sellerBefore = await provider.connection.getBalance(seller.publicKey);
+
+      await program.methods
+        .swapSolForToken(new anchor.BN(solAmount), new anchor.BN(tokenAmount))
+        .accounts(swapAccounts())
+        .signers([buyer, seller])
+        .rpc();
+
+      expect(await provider.connection.getBalance(buyer.publicKey)).to.equal(
+        buyerBefore - solAmount
+      );
+      expect(await provider.connection.getBalance(seller.publicKey)).to.equal(
+        sellerBefore + solAmount
+      );
+      expect(Number((await getAccount(provider.connection, buyerToken)).amount)).to.equal(
+        tokenAmount
+      );
+      expect(Number((await getAccount(provider.connection, sellerToken)).amount)).to.equal(
+        100_000 - tokenAmount
+      );
+    });
+
+    it("fails without the seller's signature", async () => {
+      const buyerBefore = await provider.connection.getBalance(buyer.publicKey);
+
+      try {
+        await program.methods
+          .swapSolForToken(new anchor.BN(solAmount), new anchor.BN(tokenAmount))
+          .accounts(swapAccounts())
+          .signers([buyer])
+          .rpc();
+        expect.fail("Should have required the seller's signature");
+      } catch (error: any) {
+        expect(error.message).to.include("Signature verification failed");
+      }
+
+      expect(await provider.connection.getBalance(buyer.publicKey)).to.equal(buyerBefore);
+    });
+
+    it("leaves both balances untouched when the token leg can't be covered", async () => {
+      const buyerBefore = await provider.connection.getBalance(buyer.publicKey);
+
+      try {
+        await program.methods
+          .swapSolForToken(new anchor.BN(solAmount), new anchor.BN(1_000_000))
+          .accounts(swapAccounts())
+          .signers([buyer, seller])
+          .rpc();
+        expect.fail("Should have thrown InsufficientFunds error");
+      } catch (error: any) {
+        expect(error.message).to.include("InsufficientFunds");
+      }
+
+      expect(await provider.connection.getBalance(buyer.publicKey)).to.equal(buyerBefore);
+    });
+  });
+
+  describe("payment streams", () => {
+    const deposit = 0.04 * LAMPORTS_PER_SOL;
+    const ratePerSec = 0.01 * LAMPORTS_PER_SOL; // fully accrued after 4 seconds
+    let payer: Keypair;
+    let payee: Keypair;
+    let streamPda: PublicKey;
+
+    const sleep = (ms: number) => new Promise((resolve) => setTimeout(resolve, ms));
+
+    const withdraw = () =>
+      program.methods
+        .withdrawStream()
+        .accounts({ stream: streamPda, recipient: payee.publicKey })
+        .signers([payee])
+        .rpc();
+
+    before(async () => {
+      payer = Keypair.generate();
+      payee = Keypair.generate();
+      await airdrop(payer.publicKey, 1);
+      await airdrop(payee.publicKey, 1);
+
+      [streamPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("stream"), payer.publicKey.toBuffer(), payee.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .createStream(new anchor.BN(deposit), new anchor.BN(ratePerSec))
+        .accounts({
+          stream: streamPda,
+          sender: payer.publicKey,
+          recipient: payee.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([payer])
+        .rpc();
+    });
+
+    it("pays out only what has accrued partway through", async () => {
+      const before = await provider.connection.getBalance(payee.publicKey);
+      await sleep(1500);
+      await withdraw();
+
+      const stream = await program.account.stream.fetch(streamPda);
+      const withdrawn = stream.withdrawn.toNumber();
+      expect(withdrawn).to.be.greaterThan(0);
+      expect(withdrawn).to.be.lessThan(deposit);
+      expect(withdrawn % ratePerSec).to.equal(0);
+      expect(await provider.connection.getBalance(payee.publicKey)).to.equal(before + withdrawn);
+    });
+
+    it("caps the total at the deposit once the stream ends", async () => {
+      const before = await provider.connection.getBalance(payee.publicKey);
+      const withdrawnBefore = (await program.account.stream.fetch(streamPda)).withdrawn.toNumber();
+      await sleep(5000);
+      await withdraw();
+
+      const stream = await program.account.stream.fetch(streamPda);
+      expect(stream.withdrawn.toNumber()).to.equal(deposit);
+      expect(await provider.connection.getBalance(payee.publicKey)).to.equal(
+        before + deposit - withdrawnBefore
+      );
+
+      try {
+        await withdraw();
+        expect.fail("Should have thrown NothingToWithdraw error");
+      } catch (error: any) {
+        expect(error.message).to.include("NothingToWithdraw");
+      }
+    });
+  });
+
+  describe("cancel_stream", () => {
+    const deposit = 0.1 * LAMPORTS_PER_SOL;
+    const ratePerSec = 0.01 * LAMPORTS_PER_SOL; // fully accrued after 10 seconds
+    let payer: Keypair;
+    let payee: Keypair;
+    let streamPda: PublicKey;
+
+    const cancel = (signer: Keypair) =>
+      program.methods
+        .cancelStream()
+        .accounts({ stream: streamPda, sender: signer.publicKey, recipient: payee.publicKey })
+        .signers([signer])
+        .rpc();
+
+    before(async () => {
+      payer = Keypair.generate();
+      payee = Keypair.generate();
+      await airdrop(payer.publicKey, 1);
+      await airdrop(payee.publicKey, 1);
+
+      [streamPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("stream"), payer.publicKey.toBuffer(), payee.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .createStream(new anchor.BN(deposit), new anchor.BN(ratePerSec))
+        .accounts({
+          stream: streamPda,
+          sender: payer.publicKey,
+          recipient: payee.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([payer])
+        .rpc();
+    });
+
+    it("only lets the sender cancel", async () => {
+      try {
+        await cancel(payee);
+        expect.fail("Should have rejected a cancel by the recipient");
+      } catch (error: any) {
+        expect(error.message).to.include("Unauthorized");
+      }
+    });
+
+    it("splits the deposit between accrued and refunded at a midpoint", async () => {
+      await new Promise((resolve) => setTimeout(resolve, 3000));
+
+      const rent = (await provider.connection.getBalance(streamPda)) - deposit;
+      const senderBefore = await provider.connection.getBalance(payer.publicKey);
+      const recipientBefore = await provider.connection.getBalance(payee.publicKey);
+
+      await cancel(payer);
+
+      const paid = (await provider.connection.getBalance(payee.publicKey)) - recipientBefore;
+      const refunded = (await provider.connection.getBalance(payer.publicKey)) - senderBefore - rent;
+
+      expect(paid).to.be.greaterThan(0);
+      expect(refunded).to.be.greaterThan(0);
+      expect(paid % ratePerSec).to.equal(0);
+      expect(paid + refunded).to.equal(deposit);
+      expect(await provider.connection.getAccountInfo(streamPda)).to.be.null;
+    });
+  });
+
+  describe("set_require_sole_signer", () => {
+    const amount = 0.01 * LAMPORTS_PER_SOL;
+    let payer: Keypair;
+
+    const setRequired = (required: boolean) =>
+      program.methods
+        .setRequireSoleSigner(required)
+        .accounts({ state: statePda, authority: provider.wallet.publicKey })
+        .rpc();
+
+    const transferIx = () =>
+      program.methods
+        .transferSol(new anchor.BN(amount))
+        .accounts({
+          state: statePda,
+          sender: payer.publicKey,
+          recipient: recipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .instruction();
+
+    before(async () => {
+      payer = Keypair.generate();
+      await airdrop(payer.publicKey, 1);
+      await setRequired(true);
+    });
+
+    after(async () => {
+      await setRequired(false);
+    });
+
+    it("accepts a transfer whose fee the sender pays", async () => {
+      const before = await provider.connection.getBalance(recipient.publicKey);
+
+      const tx = new anchor.web3.Transaction().add(await transferIx());
+      tx.feePayer = payer.publicKey;
+      await anchor.web3.sendAndConfirmTransaction(provider.connection, tx, [payer]);
+
+      expect(await provider.connection.getBalance(recipient.publicKey)).to.equal(before + amount);
+    });
+
+    it("rejects a transfer a relayer signs and pays for", async () => {
+      // The relayer tops up the sender in the same transaction it pays for
+      const tx = new anchor.web3.Transaction()
+        .add(
+          SystemProgram.transfer({
+            fromPubkey: provider.wallet.publicKey,
+            toPubkey: payer.publicKey,
+            lamports: 5_000,
+          })
+        )
+        .add(await transferIx());
+
+      try {
+        await provider.sendAndConfirm(tx, [payer]);
+        expect.fail("Should have thrown RelayerNotAllowed error");
+      } catch (error: any) {
+        expect(error.message + (error.logs ?? []).join("\n")).to.include("RelayerNotAllowed");
+      }
+    });
+
+    it("cannot see a relayer that only pays the fee", async () => {
+      // No instruction references the fee payer, so the program has no way to see it
+      const before = await provider.connection.getBalance(recipient.publicKey);
+
+      const tx = new anchor.web3.Transaction().add(await transferIx());
+      await provider.sendAndConfirm(tx, [payer]);
+
+      expect(await provider.connection.getBalance(recipient.publicKey)).to.equal(before + amount);
+    });
+
+    it("rejects a relayer on the other transfer variants too", async () => {
+      const tx = new anchor.web3.Transaction()
+        .add(
+          SystemProgram.transfer({
+            fromPubkey: provider.wallet.publicKey,
+            toPubkey: payer.publicKey,
+            lamports: 5_000,
+          })
+        )
+        .add(
+          await program.methods
+            .transferSolGuarded(new anchor.BN(amount), false)
+            .accounts({
+              sender: payer.publicKey,
+              recipient: recipient.publicKey,
+              systemProgram: SystemProgram.programId,
+            })
+            .instruction()
+        );
+
+      try {
+        await provider.sendAndConfirm(tx, [payer]);
+        expect.fail("Should have thrown RelayerNotAllowed error");
+      } catch (error: any) {
+        expect(error.message + (error.logs ?? []).join("\n")).to.include("RelayerNotAllowed");
+      }
+    });
+  });
+
+  describe("get_checksum", () => {
+    const amount = 10_000;
+
+    // Mirrors ProgramState::record_transfer: sha256(prev || sender || recipient || amount LE)
+    const fold = (prev: Buffer, sender: PublicKey, to: PublicKey, lamports: number) =>
+      createHash("sha256")
+        .update(
+          Buffer.concat([
+            prev,
+            sender.toBuffer(),
+            to.toBuffer(),
+            new anchor.BN(lamports).toArrayLike(Buffer, "le", 8),
+          ])
+        )
+        .digest();
+
+    const readChecksum = async () =>
+      Buffer.from(await program.methods.getChecksum().accounts({ state: statePda }).view());
+
+    const transfer = () =>
+      program.methods
+        .transferSol(new anchor.BN(amount))
+        .accounts({
+          state: statePda,
+          sender: provider.wallet.publicKey,
+          recipient: recipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+    it("folds each transfer into the checksum deterministically", async () => {
+      let expected = await readChecksum();
+
+      for (let i = 0; i < 2; i++) {
+        await transfer();
+        expected = fold(expected, provider.wallet.publicKey, recipient.publicKey, amount);
+
+        const checksum = await readChecksum();
+        expect(checksum.equals(expected)).to.be.true;
+      }
+    });
+
+    it("matches the checksum stored in the state account", async () => {
+      const state = await program.account.programState.fetch(statePda);
+      expect(Buffer.from(state.checksum).equals(await readChecksum())).to.be.true;
+    });
+  });
+
+  describe("set_min_incoming", () => {
+    const category = 3;
+    const minimum = 0.005 * LAMPORTS_PER_SOL;
+    let payer: Keypair;
+    let merchant: Keypair;
+    let merchantStats: PublicKey;
+
+    const transferToCategory = (amount: number) =>
+      program.methods
+        .transferToCategory(new anchor.BN(amount), category)
+        .accounts({
+          recipientStats: merchantStats,
+          sender: payer.publicKey,
+          recipient: merchant.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([payer])
+        .rpc();
+
+    before(async () => {
+      payer = Keypair.generate();
+      merchant = Keypair.generate();
+      await airdrop(payer.publicKey, 1);
+      await airdrop(merchant.publicKey, 1);
+
+      [merchantStats] = PublicKey.findProgramAddressSync(
+        [Buffer.from("recipient_stats"), merchant.publicKey.toBuffer()],
+        program.programId
+      );
+
+      const accounts = {
+        recipientStats: merchantStats,
+        recipient: merchant.publicKey,
+        systemProgram: SystemProgram.programId,
+      };
+      await program.methods.registerRecipient(category).accounts(accounts).signers([merchant]).rpc();
+      await program.methods
+        .setMinIncoming(new anchor.BN(minimum))
+        .accounts(accounts)
+        .signers([merchant])
+        .rpc();
+    });
+
+    it("rejects a transfer below the recipient's minimum", async () => {
+      try {
+        await transferToCategory(minimum - 1);
+        expect.fail("Should have thrown BelowRecipientMinimum error");
+      } catch (error: any) {
+        expect(error.message).to.include("BelowRecipientMinimum");
+      }
+    });
+
+    it("accepts a transfer at or above the recipient's minimum", async () => {
+      const before = await provider.connection.getBalance(merchant.publicKey);
+
+      await transferToCategory(minimum);
+
+      expect(await provider.connection.getBalance(merchant.publicKey)).to.equal(before + minimum);
+      const stats = await program.account.recipientStats.fetch(merchantStats);
+      expect(stats.minIncoming.toNumber()).to.equal(minimum);
+      expect(stats.transferCount.toNumber()).to.equal(1);
+    });
+  });
+
+  describe("transfer_sol_paired", () => {
+    // Fresh recipients need at least the rent-exempt minimum
+    const amount = 0.01 * LAMPORTS_PER_SOL;
+    let payer: Keypair;
+
+    const firstTransferEvents = async (to: PublicKey) => {
+      const signature = await program.methods
+        .transferSolPaired(new anchor.BN(amount))
+        .accounts({
+          sender: payer.publicKey,
+          recipient: to,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([payer])
+        .rpc({ commitment: "confirmed" });
+
+      const tx = await provider.connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      return [...parser.parseLogs(tx!.meta!.logMessages!)].filter(
+        (event) => event.name === "firstTransferBetween"
+      );
+    };
+
+    before(async () => {
+      payer = Keypair.generate();
+      await airdrop(payer.publicKey, 1);
+    });
+
+    it("emits FirstTransferBetween only on a pair's first transfer", async () => {
+      const to = Keypair.generate().publicKey;
+
+      const first = await firstTransferEvents(to);
+      expect(first).to.have.length(1);
+      expect(first[0].data.sender.toBase58()).to.equal(payer.publicKey.toBase58());
+      expect(first[0].data.recipient.toBase58()).to.equal(to.toBase58());
+      expect(first[0].data.amount.toNumber()).to.equal(amount);
+
+      expect(await firstTransferEvents(to)).to.have.length(0);
+    });
+
+    it("emits it again for a new recipient", async () => {
+      expect(await firstTransferEvents(Keypair.generate().publicKey)).to.have.length(1);
+    });
+  });
+
+  describe("set_fee_rebate", () => {
+    const feeBps = 100;
+    const rebateBps = 5_000; // half of the fee comes back
+    const threshold = 500_000;
+    let feeConfigPda: PublicKey;
+    let treasury: Keypair;
+    let mint: PublicKey;
+    let holder: Keypair;
+    let holderToken: PublicKey;
+    let treasuryToken: PublicKey;
+    let recipientToken: PublicKey;
+    let rebateToken: PublicKey;
+
+    const setRebate = (rebateThreshold: number) =>
+      program.methods
+        .setFeeRebate(new anchor.BN(rebateThreshold), rebateBps)
+        .accounts({
+          state: statePda,
+          feeConfig: feeConfigPda,
+          authority: provider.wallet.publicKey,
+        })
+        .rpc();
+
+    // Returns what the sender paid, the recipient received, the treasury collected
+    // and the rebate vault paid out
+    const transfer = async (amount: number) => {
+      const balance = async (token: PublicKey) =>
+        Number((await getAccount(provider.connection, token)).amount);
+      const accounts = [holderToken, recipientToken, treasuryToken, rebateToken];
+      const before = await Promise.all(accounts.map(balance));
+      await program.methods
+        .transferTokenWithFee(new anchor.BN(amount))
+        .accounts({
+          state: statePda,
+          feeConfig: feeConfigPda,
+          sender: holder.publicKey,
+          mint,
+          senderToken: holderToken,
+          recipientToken,
+          treasuryToken,
+          rebateToken,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([holder])
+        .rpc();
+      const after = await Promise.all(accounts.map(balance));
+      return {
+        paid: before[0] - after[0],
+        received: after[1] - before[1],
+        collected: after[2] - before[2],
+        rebated: before[3] - after[3],
+      };
+    };
+
+    before(async () => {
+      const payer = (provider.wallet as anchor.Wallet).payer;
+      treasury = Keypair.generate();
+      holder = Keypair.generate();
+      // Below the discount balance, so the full fee rate applies
+      await airdrop(holder.publicKey, 1);
+      [feeConfigPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("fee_config")],
+        program.programId
+      );
+
+      mint = await createMint(provider.connection, payer, payer.publicKey, null, 6);
+      const tokenAccount = async (owner: PublicKey) =>
+        (await getOrCreateAssociatedTokenAccount(provider.connection, payer, mint, owner)).address;
+      holderToken = await tokenAccount(holder.publicKey);
+      treasuryToken = await tokenAccount(treasury.publicKey);
+      recipientToken = await tokenAccount(recipient.publicKey);
+      // The rebate vault is owned by the fee config PDA and funded by the authority
+      rebateToken = (
+        await getOrCreateAssociatedTokenAccount(provider.connection, payer, mint, feeConfigPda, true)
+      ).address;
+      await mintTo(provider.connection, payer, mint, holderToken, payer, 10_000_000);
+      await mintTo(provider.connection, payer, mint, rebateToken, payer, 1_000_000);
+
+      await program.methods
+        .setFee(feeBps, 0, treasury.publicKey)
+        .accounts({
+          state: statePda,
+          feeConfig: feeConfigPda,
+          authority: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      await setRebate(threshold);
+    });
+
+    after(async () => {
+      await setRebate(0);
+    });
+
+    it("charges the full fee at or below the threshold", async () => {
+      const fee = (threshold * feeBps) / 10_000;
+      expect(await transfer(threshold)).to.deep.equal({
+        paid: threshold,
+        received: threshold - fee,
+        collected: fee,
+        rebated: 0,
+      });
+    });
+
+    it("pays part of the fee back from the rebate vault above the threshold", async () => {
+      const amount = 2 * threshold;
+      const fee = (amount * feeBps) / 10_000;
+      const rebate = (fee * rebateBps) / 10_000;
+      expect(await transfer(amount)).to.deep.equal({
+        paid: amount - rebate,
+        received: amount - fee,
+        collected: fee,
+        rebated: rebate,
+      });
+    });
+  });
+
+  describe("transfer_token_gated", () => {
+    const amount = 0.01 * LAMPORTS_PER_SOL;
+    let mint: PublicKey;
+    let holder: Keypair;
+    let holderToken: PublicKey;
+    let nonHolder: Keypair;
+    let nonHolderToken: PublicKey;
+
+    const transferGated = (to: PublicKey, recipientToken: PublicKey) =>
+      program.methods
+        .transferTokenGated(new anchor.BN(amount))
+        .accounts({
+          sender: provider.wallet.publicKey,
+          recipient: to,
+          recipientToken,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+    before(async () => {
+      const payer = (provider.wallet as anchor.Wallet).payer;
+      holder = Keypair.generate();
+      nonHolder = Keypair.generate();
+      await airdrop(holder.publicKey, 1);
+      await airdrop(nonHolder.publicKey, 1);
+
+      mint = await createMint(provider.connection, payer, payer.publicKey, null, 0);
+      const tokenAccount = async (owner: PublicKey) =>
+        (await getOrCreateAssociatedTokenAccount(provider.connection, payer, mint, owner)).address;
+      holderToken = await tokenAccount(holder.publicKey);
+      // The non-holder has an account for the mint, but an empty one
+      nonHolderToken = await tokenAccount(nonHolder.publicKey);
+      await mintTo(provider.connection, payer, mint, holderToken, payer, 1);
+
+      await program.methods
+        .setTokenGate(mint)
+        .accounts({ state: statePda, authority: provider.wallet.publicKey })
+        .rpc();
+    });
+
+    it("transfers to a recipient holding the required token", async () => {
+      const before = await provider.connection.getBalance(holder.publicKey);
+      await transferGated(holder.publicKey, holderToken);
+      expect(await provider.connection.getBalance(holder.publicKey)).to.equal(before + amount);
+    });
+
+    it("rejects a recipient with no balance of the required token", async () => {
+      try {
+        await transferGated(nonHolder.publicKey, nonHolderToken);
+        expect.fail("Should have thrown MissingRequiredToken error");
+      } catch (error: any) {
+        expect(error.message).to.include("MissingRequiredToken");
+      }
+    });
+
+    it("rejects a recipient holding only a mint the authority did not set", async () => {
+      const payer = (provider.wallet as anchor.Wallet).payer;
+      const otherMint = await createMint(provider.connection, payer, payer.publicKey, null, 0);
+      const otherToken = (
+        await getOrCreateAssociatedTokenAccount(
+          provider.connection,
+          payer,
+          otherMint,
+          nonHolder.publicKey
+        )
+      ).address;
+      await mintTo(provider.connection, payer, otherMint, otherToken, payer, 1);
+
+      try {
+        await transferGated(nonHolder.publicKey, otherToken);
+        expect.fail("Should have thrown MissingRequiredToken error");
+      } catch (error: any) {
+        expect(error.message).to.include("MissingRequiredToken");
+      }
+    });
+
+    it("rejects a token account belonging to someone else", async () => {
+      try {
+        await transferGated(nonHolder.publicKey, holderToken);
+        expect.fail("Should have thrown MissingRequiredToken error");
+      } catch (error: any) {
+        expect(error.message).to.include("MissingRequiredToken");
+      }
+    });
+  });
+
+  describe("set_cold_sweep", () => {
+    const feeBps = 100;
+    const amount = 100_000; // 1,000 fee per transfer
+    const cap = 1_500;
+    let feeConfigPda: PublicKey;
+    let mint: PublicKey;
+    let holder: Keypair;
+    let holderToken: PublicKey;
+    let treasuryToken: PublicKey;
+    let coldToken: PublicKey;
+    let recipientToken: PublicKey;
+
+    const setColdSweep = (hotTreasuryCap: number, coldWallet: PublicKey) =>
+      program.methods
+        .setColdSweep(new anchor.BN(hotTreasuryCap), coldWallet)
+        .accounts({
+          state: statePda,
+          feeConfig: feeConfigPda,
+          authority: provider.wallet.publicKey,
+        })
+        .rpc();
+
+    const balance = async (token: PublicKey) =>
+      Number((await getAccount(provider.connection, token)).amount);
+
+    const transfer = () =>
+      program.methods
+        .transferTokenWithFee(new anchor.BN(amount))
+        .accounts({
+          state: statePda,
+          feeConfig: feeConfigPda,
+          sender: holder.publicKey,
+          mint,
+          senderToken: holderToken,
+          recipientToken,
+          treasuryToken,
+          coldToken,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([holder])
+        .rpc();
+
+    before(async () => {
+      const payer = (provider.wallet as anchor.Wallet).payer;
+      const treasury = Keypair.generate();
+      const cold = Keypair.generate();
+      holder = Keypair.generate();
+      await airdrop(holder.publicKey, 1);
+      [feeConfigPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("fee_config")],
+        program.programId
+      );
+
+      mint = await createMint(provider.connection, payer, payer.publicKey, null, 6);
+      const tokenAccount = async (owner: PublicKey) =>
+        (await getOrCreateAssociatedTokenAccount(provider.connection, payer, mint, owner)).address;
+      holderToken = await tokenAccount(holder.publicKey);
+      treasuryToken = await tokenAccount(treasury.publicKey);
+      coldToken = await tokenAccount(cold.publicKey);
+      recipientToken = await tokenAccount(recipient.publicKey);
+      await mintTo(provider.connection, payer, mint, holderToken, payer, 1_000_000);
+
+      await program.methods
+        .setFee(feeBps, 0, treasury.publicKey)
+        .accounts({
+          state: statePda,
+          feeConfig: feeConfigPda,
+          authority: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      await setColdSweep(cap, cold.publicKey);
+    });
+
+    after(async () => {
+      await setColdSweep(0, PublicKey.default);
+    });
+
+    it("keeps fees in the treasury while it stays under the cap", async () => {
+      await transfer();
+      expect(await balance(treasuryToken)).to.equal(1_000);
+      expect(await balance(coldToken)).to.equal(0);
+    });
+
+    it("sweeps the part of a fee above the cap to the cold wallet", async () => {
+      await transfer();
+      expect(await balance(treasuryToken)).to.equal(cap);
+      expect(await balance(coldToken)).to.equal(2_000 - cap);
+    });
+  });
+
+  describe("transfer_idempotent", () => {
+    const amount = 10_000;
+    const ttlSecs = 2;
+    let payer: Keypair;
+
+    const markerFor = (eventId: Buffer) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("idempotency"), payer.publicKey.toBuffer(), eventId],
+        program.programId
+      )[0];
+
+    const transferOnce = (eventId: Buffer) =>
+      program.methods
+        .transferIdempotent(new anchor.BN(amount), [...eventId], ttlSecs)
+        .accounts({
+          marker: markerFor(eventId),
+          sender: payer.publicKey,
+          recipient: recipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([payer])
+        .rpc();
+
+    const cleanup = (eventId: Buffer) =>
+      program.methods
+        .cleanupIdempotency()
+        .accounts({ marker: markerFor(eventId), sender: payer.publicKey })
+        .rpc();
+
+    before(async () => {
+      payer = Keypair.generate();
+      await airdrop(payer.publicKey, 1);
+    });
+
+    it("rejects a duplicate event id within its TTL", async () => {
+      const eventId = createHash("sha256").update("order-1").digest();
+      await transferOnce(eventId);
+
+      try {
+        await transferOnce(eventId);
+        expect.fail("Should have thrown DuplicateEventId error");
+      } catch (error: any) {
+        expect(error.message).to.include("DuplicateEventId");
+      }
+    });
+
+    it("cleans up a marker only after it expires", async () => {
+      const eventId = createHash("sha256").update("order-2").digest();
+      await transferOnce(eventId);
+
+      try {
+        await cleanup(eventId);
+        expect.fail("Should have thrown MarkerNotExpired error");
+      } catch (error: any) {
+        expect(error.message).to.include("MarkerNotExpired");
+      }
+
+      await new Promise((resolve) => setTimeout(resolve, (ttlSecs + 2) * 1000));
+      const before = await provider.connection.getBalance(payer.publicKey);
+      await cleanup(eventId);
+
+      expect(await provider.connection.getAccountInfo(markerFor(eventId))).to.be.null;
+      expect(await provider.connection.getBalance(payer.publicKey)).to.be.greaterThan(before);
+    });
+  });
+
+  describe("set_max_transfers_per_tx", () => {
+    const amount = 10_000;
+
+    const setMax = (max: number) =>
+      program.methods
+        .setMaxTransfersPerTx(max)
+        .accounts({ state: statePda, authority: provider.wallet.publicKey })
+        .rpc();
+
+    // Several transfers composed into one transaction, as a batching program would.
+    // Transactions are told apart by slot and content, so each call uses new amounts.
+    // The workspace has no composing program, so transfers made through CPI are not
+    // exercised here; they read the same instructions sysvar as these top-level calls.
+    let sent = 0;
+    const transferTx = async (transfers: number) => {
+      const tx = new anchor.web3.Transaction();
+      for (let i = 0; i < transfers; i++) {
+        tx.add(
+          await program.methods
+            .transferSol(new anchor.BN(amount + sent++))
+            .accounts({
+              state: statePda,
+              sender: provider.wallet.publicKey,
+              recipient: recipient.publicKey,
+              systemProgram: SystemProgram.programId,
+            })
+            .instruction()
+        );
+      }
+      return provider.sendAndConfirm(tx);
+    };
+
+    before(async () => {
+      await setMax(2);
+    });
+
+    after(async () => {
+      await setMax(0);
+    });
+
+    it("allows transactions up to the cap, each counted separately", async () => {
+      await transferTx(2);
+      await transferTx(2);
+    });
+
+    it("rejects a transaction exceeding the per-transaction cap", async () => {
+      try {
+        await transferTx(3);
+        expect.fail("Should have thrown TooManyTransfersInTransaction error");
+      } catch (error: any) {
+        expect(error.message + (error.logs ?? []).join("\n")).to.include(
+          "TooManyTransfersInTransaction"
+        );
+      }
+    });
+
+    it("counts every payment of a batch against the cap", async () => {
+      const recipients = [recipient.publicKey, recipient.publicKey, recipient.publicKey];
+      try {
+        await program.methods
+          .transferSolBatch(
+            recipients.map(() => new anchor.BN(amount + sent++)),
+            false
+          )
+          .accounts({
+            sender: provider.wallet.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .remainingAccounts(
+            recipients.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true }))
+          )
+          .rpc();
+        expect.fail("Should have thrown TooManyTransfersInTransaction error");
+      } catch (error: any) {
+        expect(error.message).to.include("TooManyTransfersInTransaction");
+      }
+    });
+
+    const batchedTx = async (transfers: number) => {
+      const tx = new anchor.web3.Transaction();
+      for (let i = 0; i < transfers; i++) {
+        tx.add(
+          await program.methods
+            .transferSolBatched(new anchor.BN(amount + sent++))
+            .accounts({
+              sender: provider.wallet.publicKey,
+              recipient: recipient.publicKey,
+              systemProgram: SystemProgram.programId,
+            })
+            .instruction()
+        );
+      }
+      return provider.sendAndConfirm(tx);
+    };
+
+    it("counts batched-stats transfers against the sender's stats", async () => {
+      await batchedTx(2);
+
+      try {
+        await batchedTx(3);
+        expect.fail("Should have thrown TooManyTransfersInTransaction error");
+      } catch (error: any) {
+        expect(error.message + (error.logs ?? []).join("\n")).to.include(
+          "TooManyTransfersInTransaction"
+        );
+      }
+    });
+  });
+
+  describe("bonding curve", () => {
+    const basePrice = 1_000_000;
+    const slope = 100_000;
+    let buyer: Keypair;
+    let curvePda: PublicKey;
+    let positionPda: PublicKey;
+
+    const getPrice = async () =>
+      (await program.methods.getPrice().accounts({ curve: curvePda }).view()).toNumber();
+
+    const buy = (units: anchor.BN) =>
+      program.methods
+        .buyCurve(units)
+        .accounts({
+          curve: curvePda,
+          position: positionPda,
+          buyer: buyer.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([buyer])
+        .rpc();
+
+    before(async () => {
+      buyer = Keypair.generate();
+      await airdrop(buyer.publicKey, 1);
+      [curvePda] = PublicKey.findProgramAddressSync([Buffer.from("curve")], program.programId);
+      [positionPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("curve_position"), buyer.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .setCurve(new anchor.BN(basePrice), new anchor.BN(slope))
+        .accounts({
+          state: statePda,
+          curve: curvePda,
+          authority: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    it("raises the price after a buy and charges along the curve", async () => {
+      const supply = (await program.account.curve.fetch(curvePda)).supply.toNumber();
+      expect(await getPrice()).to.equal(basePrice + slope * supply);
+
+      const reserveBefore = await provider.connection.getBalance(curvePda);
+      await buy(new anchor.BN(3));
+
+      // Units supply, supply + 1 and supply + 2
+      const cost = 3 * basePrice + slope * (3 * supply + 3);
+      expect(await provider.connection.getBalance(curvePda)).to.equal(reserveBefore + cost);
+      expect(await getPrice()).to.equal(basePrice + slope * (supply + 3));
+    });
+
+    it("pays sellers back along the curve and lowers the price", async () => {
+      const priceBefore = await getPrice();
+      const balanceBefore = await provider.connection.getBalance(buyer.publicKey);
+
+      await program.methods
+        .sellCurve(new anchor.BN(1))
+        .accounts({ curve: curvePda, position: positionPda, owner: buyer.publicKey })
+        .signers([buyer])
+        .rpc();
+
+      expect(await getPrice()).to.equal(priceBefore - slope);
+      expect(await provider.connection.getBalance(buyer.publicKey)).to.equal(
+        balanceBefore + priceBefore - slope
+      );
+    });
+
+    it("rejects a buy whose cost overflows instead of wrapping", async () => {
+      try {
+        await buy(new anchor.BN("18446744073709551615"));
+        expect.fail("Should have thrown MathOverflow error");
+      } catch (error: any) {
+        expect(error.message).to.include("MathOverflow");
+      }
+    });
+  });
+
+  describe("password escrow", () => {
+    const amount = 0.05 * LAMPORTS_PER_SOL;
+    let sender: Keypair;
+    let claimer: Keypair;
+
+    const escrowFor = (secret: string) => {
+      const keyHash = createHash("sha256").update(secret).digest();
+      const [escrow] = PublicKey.findProgramAddressSync(
+        [Buffer.from("password_escrow"), keyHash],
+        program.programId
+      );
+      return { keyHash, escrow };
+    };
+
+    const lock = (secret: string) => {
+      const { keyHash, escrow } = escrowFor(secret);
+      return program.methods
+        .escrowPassword(new anchor.BN(amount), [...keyHash])
+        .accounts({ escrow, sender: sender.publicKey, systemProgram: SystemProgram.programId })
+        .signers([sender])
+        .rpc();
+    };
+
+    const [feeConfig] = PublicKey.findProgramAddressSync(
+      [Buffer.from("fee_config")],
+      program.programId
+    );
+    let treasury: PublicKey;
+
+    const claim = (secret: string, presented: string) =>
+      program.methods
+        .claimPassword(Buffer.from(presented))
+        .accounts({
+          escrow: escrowFor(secret).escrow,
+          claimer: claimer.publicKey,
+          sender: sender.publicKey,
+          feeConfig,
+          treasury,
+        })
+        .signers([claimer])
+        .rpc();
+
+    before(async () => {
+      sender = Keypair.generate();
+      claimer = Keypair.generate();
+      await airdrop(sender.publicKey, 1);
+      await airdrop(claimer.publicKey, 1);
+      // The treasury only takes dust while it stays rent-exempt
+      treasury = (await program.account.feeConfig.fetch(feeConfig)).treasury;
+      await airdrop(treasury, 1);
+    });
+
+    it("pays out to whoever presents the correct secret", async () => {
+      const secret = "correct horse battery staple";
+      await lock(secret);
+      const before = await provider.connection.getBalance(claimer.publicKey);
+
+      await claim(secret, secret);
+
+      expect(await provider.connection.getBalance(claimer.publicKey)).to.equal(before + amount);
+      expect(await provider.connection.getAccountInfo(escrowFor(secret).escrow)).to.be.null;
+    });
+
+    it("records an incorrect secret and rate-limits the next attempt", async () => {
+      const secret = "rosebud";
+      await lock(secret);
+      const before = await provider.connection.getBalance(claimer.publicKey);
+
+      await claim(secret, "wrong guess");
+
+      const escrow = await program.account.passwordEscrow.fetch(escrowFor(secret).escrow);
+      expect(escrow.failedAttempts).to.equal(1);
+      expect(await provider.connection.getBalance(claimer.publicKey)).to.equal(before);
+
+      try {
+        await claim(secret, secret);
+        expect.fail("Should have thrown ClaimRateLimited error");
+      } catch (error: any) {
+        expect(error.message).to.include("ClaimRateLimited");
+      }
+    });
+
+    // Claim an escrow holding `extra` lamports above its amount and rent
+    const claimWithExtra = async (secret: string, extra: number) => {
+      await lock(secret);
+      const { escrow } = escrowFor(secret);
+      await provider.sendAndConfirm(
+        new anchor.web3.Transaction().add(
+          SystemProgram.transfer({
+            fromPubkey: provider.wallet.publicKey,
+            toPubkey: escrow,
+            lamports: extra,
+          })
+        )
+      );
+      const rent = (await provider.connection.getAccountInfo(escrow))!.lamports - amount - extra;
+      const treasuryBefore = await provider.connection.getBalance(treasury);
+      const senderBefore = await provider.connection.getBalance(sender.publicKey);
+
+      await claim(secret, secret);
+
+      return {
+        rent,
+        toTreasury: (await provider.connection.getBalance(treasury)) - treasuryBefore,
+        toSender: (await provider.connection.getBalance(sender.publicKey)) - senderBefore,
+      };
+    };
+
+    it("sends dust left in the escrow to the treasury on close", async () => {
+      const dust = 1_000;
+      const { rent, toTreasury, toSender } = await claimWithExtra("dusty", dust);
+
+      expect(toTreasury).to.equal(dust);
+      expect(toSender).to.equal(rent);
+    });
+
+    it("returns a residual too large to be dust to the sender", async () => {
+      const residual = 0.01 * LAMPORTS_PER_SOL;
+      const { rent, toTreasury, toSender } = await claimWithExtra("leftover", residual);
+
+      expect(toTreasury).to.equal(0);
+      expect(toSender).to.equal(rent + residual);
+    });
+
+    it("keeps dust with the sender while the treasury would not be rent-exempt", async () => {
+      const config = await program.account.feeConfig.fetch(feeConfig);
+      const setTreasury = (to: PublicKey) =>
+        program.methods
+          .setFee(config.feeBps, config.burnBps, to)
+          .accounts({
+            state: statePda,
+            feeConfig,
+            authority: provider.wallet.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+      const funded = treasury;
+      treasury = Keypair.generate().publicKey;
+      await setTreasury(treasury);
+
+      try {
+        const dust = 1_000;
+        const { rent, toTreasury, toSender } = await claimWithExtra("unfunded", dust);
+
+        expect(toTreasury).to.equal(0);
+        expect(toSender).to.equal(rent + dust);
+      } finally {
+        treasury = funded;
+        await setTreasury(funded);
+      }
+    });
+  });
+
+  describe("transfer size histogram", () => {
+    let payer: Keypair;
+
+    const currentDay = async () => {
+      const now = await provider.connection.getBlockTime(await provider.connection.getSlot());
+      return Math.floor(now! / 86_400);
+    };
+
+    const histogramFor = (day: number) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("histogram"), new anchor.BN(day).toArrayLike(Buffer, "le", 8)],
+        program.programId
+      )[0];
+
+    const getHistogram = async (day: number) => {
+      const histogram = histogramFor(day);
+      if (!(await provider.connection.getAccountInfo(histogram))) return [0, 0, 0, 0];
+      const buckets = await program.methods
+        .getHistogram(new anchor.BN(day))
+        .accounts({ histogram })
+        .view();
+      return buckets.map((count: anchor.BN) => count.toNumber());
+    };
+
+    const transfer = (sol: number, day: number) =>
+      program.methods
+        .transferWithHistogram(new anchor.BN(sol * LAMPORTS_PER_SOL), new anchor.BN(day))
+        .accounts({
+          state: statePda,
+          histogram: histogramFor(day),
+          sender: payer.publicKey,
+          recipient: recipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([payer])
+        .rpc();
+
+    before(async () => {
+      payer = Keypair.generate();
+      await airdrop(payer.publicKey, 20);
+    });
+
+    it("counts each transfer in its size bucket", async () => {
+      const day = await currentDay();
+      const before = await getHistogram(day);
+
+      // One transfer per bucket, plus the exact bucket bounds
+      for (const sol of [0.05, 0.1, 0.5, 1, 10]) {
+        await transfer(sol, day);
+      }
+
+      const after = await getHistogram(day);
+      expect(after.map((count: number, i: number) => count - before[i])).to.deep.equal([
+        1, 2, 1, 1,
+      ]);
+    });
+
+    it("rejects a day other than the current one", async () => {
+      try {
+        await transfer(0.05, (await currentDay()) - 1);
+        expect.fail("Should have thrown InvalidDay error");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidDay");
+      }
+    });
+  });
+
+  describe("transfer_with_history", () => {
+    const amount = 10_000;
+    const minHistory = 2;
+
+    const setMinHistory = (min: number) =>
+      program.methods
+        .setMinHistory(min)
+        .accounts({ state: statePda, authority: provider.wallet.publicKey })
+        .rpc();
+
+    const senderStatsPda = (sender: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("sender_stats"), sender.toBuffer()],
+        program.programId
+      )[0];
+
+    const accounts = (from: Keypair) => ({
+      senderStats: senderStatsPda(from.publicKey),
+      sender: from.publicKey,
+      recipient: recipient.publicKey,
+      systemProgram: SystemProgram.programId,
+    });
+
+    const transferWithHistory = (from: Keypair) =>
+      program.methods
+        .transferWithHistory(new anchor.BN(amount))
+        .accounts({ state: statePda, ...accounts(from) })
+        .signers([from])
+        .rpc();
+
+    // Builds up history through the stats-tracking batched transfer
+    const buildHistory = async (from: Keypair, transfers: number) => {
+      for (let i = 0; i < transfers; i++) {
+        await program.methods
+          .transferSolBatched(new anchor.BN(amount))
+          .accounts(accounts(from))
+          .signers([from])
+          .rpc();
+      }
+    };
+
+    before(async () => {
+      await setMinHistory(minHistory);
+    });
+
+    after(async () => {
+      await setMinHistory(0);
+    });
+
+    it("rejects a sender below the required history", async () => {
+      const newcomer = Keypair.generate();
+      await airdrop(newcomer.publicKey, 1);
+      await buildHistory(newcomer, minHistory - 1);
+
+      try {
+        await transferWithHistory(newcomer);
+        expect.fail("Should have thrown InsufficientHistory error");
+      } catch (error: any) {
+        expect(error.message).to.include("InsufficientHistory");
+      }
+    });
+
+    it("accepts a sender meeting the required history", async () => {
+      const regular = Keypair.generate();
+      await airdrop(regular.publicKey, 1);
+      await buildHistory(regular, minHistory);
+
+      await transferWithHistory(regular);
+
+      const stats = await program.account.senderStats.fetch(senderStatsPda(regular.publicKey));
+      expect(stats.transferCount.toNumber()).to.equal(minHistory + 1);
+    });
+
+    it("leaves its transfer out of the stats still pending a flush", async () => {
+      const regular = Keypair.generate();
+      await airdrop(regular.publicKey, 1);
+      await buildHistory(regular, minHistory);
+      const before = await program.account.programState.fetch(statePda);
+
+      await transferWithHistory(regular);
+
+      // Already in the program totals, so `flush_stats` must not add it again
+      const after = await program.account.programState.fetch(statePda);
+      expect(after.totalTransfers.toNumber()).to.equal(before.totalTransfers.toNumber() + 1);
+      const stats = await program.account.senderStats.fetch(senderStatsPda(regular.publicKey));
+      expect(stats.pendingTransfers.toNumber()).to.equal(minHistory);
+      expect(stats.pendingVolume.toNumber()).to.equal(minHistory * amount);
+    });
+  });
+
+  describe("invoices", () => {
+    const lineItems = [0.01, 0.02, 0.005].map((sol) => sol * LAMPORTS_PER_SOL);
+    const total = lineItems.reduce((sum, item) => sum + item, 0);
+    const invoiceId = new anchor.BN(1);
+    let issuer: Keypair;
+    let payer: Keypair;
+    let invoicePda: PublicKey;
+
+    const pay = (amount: number) =>
+      program.methods
+        .payInvoice(new anchor.BN(amount))
+        .accounts({
+          invoice: invoicePda,
+          payer: payer.publicKey,
+          issuer: issuer.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([payer])
+        .rpc();
+
+    before(async () => {
+      issuer = Keypair.generate();
+      payer = Keypair.generate();
+      await airdrop(issuer.publicKey, 1);
+      await airdrop(payer.publicKey, 1);
+      [invoicePda] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("invoice"),
+          issuer.publicKey.toBuffer(),
+          invoiceId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      );
+
+      await program.methods
+        .createInvoice(invoiceId, lineItems.map((item) => new anchor.BN(item)))
+        .accounts({
+          invoice: invoicePda,
+          issuer: issuer.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([issuer])
+        .rpc();
+    });
+
+    it("rejects an underpayment", async () => {
+      try {
+        await pay(total - 1);
+        expect.fail("Should have thrown InvoiceUnderpaid error");
+      } catch (error: any) {
+        expect(error.message).to.include("InvoiceUnderpaid");
+      }
+    });
+
+    it("rejects an overpayment", async () => {
+      try {
+        await pay(total + 1);
+        expect.fail("Should have thrown InvoiceOverpaid error");
+      } catch (error: any) {
+        expect(error.message).to.include("InvoiceOverpaid");
+      }
+    });
+
+    it("settles an exact payment of the line item total", async () => {
+      const before = await provider.connection.getBalance(issuer.publicKey);
+
+      await pay(total);
+
+      expect(await provider.connection.getBalance(issuer.publicKey)).to.equal(before + total);
+      const invoice = await program.account.invoice.fetch(invoicePda);
+      expect(invoice.paid).to.be.true;
+      expect(invoice.payer.toBase58()).to.equal(payer.publicKey.toBase58());
+    });
+
+    it("rejects paying the invoice twice", async () => {
+      try {
+        await pay(total);
+        expect.fail("Should have thrown InvoiceAlreadyPaid error");
+      } catch (error: any) {
+        expect(error.message).to.include("InvoiceAlreadyPaid");
+      }
+    });
+  });
+
+  describe("transfer_as_wsol", () => {
+    const amount = 0.25 * LAMPORTS_PER_SOL;
+    let wsolOwner: Keypair;
+    let wsolAccount: PublicKey;
+
+    const transferAsWsol = (recipientToken: PublicKey) =>
+      program.methods
+        .transferAsWsol(new anchor.BN(amount))
+        .accounts({
+          sender: provider.wallet.publicKey,
+          recipient: wsolOwner.publicKey,
+          recipientToken,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+    before(async () => {
+      const payer = (provider.wallet as anchor.Wallet).payer;
+      wsolOwner = Keypair.generate();
+      wsolAccount = (
+        await getOrCreateAssociatedTokenAccount(
+          provider.connection, payer, NATIVE_MINT, wsolOwner.publicKey
+        )
+      ).address;
+    });
+
+    it("delivers wrapped SOL with a synced token balance", async () => {
+      const before = Number((await getAccount(provider.connection, wsolAccount)).amount);
+
+      await transferAsWsol(wsolAccount);
+
+      const after = Number((await getAccount(provider.connection, wsolAccount)).amount);
+      expect(after - before).to.equal(amount);
+    });
+
+    it("rejects a token account of another mint", async () => {
+      const payer = (provider.wallet as anchor.Wallet).payer;
+      const mint = await createMint(provider.connection, payer, payer.publicKey, null, 9);
+      const other = (
+        await getOrCreateAssociatedTokenAccount(provider.connection, payer, mint, wsolOwner.publicKey)
+      ).address;
+
+      try {
+        await transferAsWsol(other);
+        expect.fail("Should have thrown InvalidTokenAccount error");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidTokenAccount");
+      }
+    });
+  });
+
+  describe("emergency_unpause", () => {
+    let members: Keypair[];
+    let councilPda: PublicKey;
+
+    const setPaused = (paused: boolean) =>
+      program.methods
+        .setPaused(paused)
+        .accounts({ state: statePda, authority: provider.wallet.publicKey })
+        .rpc();
+
+    const emergencyUnpause = (signers: Keypair[]) =>
+      program.methods
+        .emergencyUnpause()
+        .accounts({ state: statePda, council: councilPda })
+        .remainingAccounts(
+          signers.map((signer) => ({ pubkey: signer.publicKey, isSigner: true, isWritable: false }))
+        )
+        .signers(signers)
+        .rpc();
+
+    before(async () => {
+      members = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+      [councilPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("emergency_council")],
+        program.programId
+      );
+
+      await program.methods
+        .setEmergencyCouncil(
+          members.map((member) => member.publicKey),
+          2
+        )
+        .accounts({
+          state: statePda,
+          council: councilPda,
+          authority: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    beforeEach(async () => {
+      await setPaused(true);
+    });
+
+    after(async () => {
+      await setPaused(false);
+    });
+
+    it("rejects an unpause signed by too few council members", async () => {
+      try {
+        await emergencyUnpause([members[0]]);
+        expect.fail("Should have thrown EmergencyThresholdNotMet error");
+      } catch (error: any) {
+        expect(error.message).to.include("EmergencyThresholdNotMet");
+      }
+      expect((await program.account.programState.fetch(statePda)).paused).to.be.true;
+    });
+
+    it("does not count non-members towards the threshold", async () => {
+      try {
+        await emergencyUnpause([members[0], Keypair.generate()]);
+        expect.fail("Should have thrown EmergencyThresholdNotMet error");
+      } catch (error: any) {
+        expect(error.message).to.include("EmergencyThresholdNotMet");
+      }
+    });
+
+    it("unpauses once the threshold of council members signs", async () => {
+      await emergencyUnpause([members[0], members[2]]);
+      expect((await program.account.programState.fetch(statePda)).paused).to.be.false;
+    });
+  });
+
+  describe("transfer_with_notification", () => {
+    // The SPL Memo program stands in for a notification program: it accepts
+    // any UTF-8 payload and logs it
+    const MEMO_PROGRAM_ID = new PublicKey("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+    const amount = 10_000;
+
+    const configFor = (to: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("notification_config"), to.toBuffer()],
+        program.programId
+      )[0];
+
+    const registeredRecipient = async (notifier: PublicKey, bestEffort: boolean) => {
+      const to = Keypair.generate();
+      await airdrop(to.publicKey, 1);
+      await program.methods
+        .setNotificationProgram(notifier, bestEffort)
+        .accounts({
+          notificationConfig: configFor(to.publicKey),
+          recipient: to.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([to])
+        .rpc();
+      return to.publicKey;
+    };
+
+    const transferLogs = async (to: PublicKey, notificationProgram: PublicKey) => {
+      const signature = await program.methods
+        .transferWithNotification(new anchor.BN(amount))
+        .accounts({
+          sender: provider.wallet.publicKey,
+          recipient: to,
+          notificationConfig: configFor(to),
+          notificationProgram,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc({ commitment: "confirmed" });
+      const tx = await provider.connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      return tx!.meta!.logMessages!.join("\n");
+    };
+
+    it("invokes the registered notification program with the recipient and amount", async () => {
+      const to = await registeredRecipient(MEMO_PROGRAM_ID, false);
+
+      const logs = await transferLogs(to, MEMO_PROGRAM_ID);
+
+      expect(logs).to.include(`Program ${MEMO_PROGRAM_ID.toBase58()} invoke [2]`);
+      expect(logs).to.include(`transfer ${to.toBase58()} ${amount}`);
+    });
+
+    it("skips the notification in best-effort mode when the program is wrong", async () => {
+      const to = await registeredRecipient(MEMO_PROGRAM_ID, true);
+      const before = await provider.connection.getBalance(to);
+
+      const logs = await transferLogs(to, SystemProgram.programId);
+
+      expect(logs).to.include("Notification skipped");
+      expect(await provider.connection.getBalance(to)).to.equal(before + amount);
+    });
+
+    it("rejects a wrong notification program without best effort", async () => {
+      const to = await registeredRecipient(MEMO_PROGRAM_ID, false);
+
+      try {
+        await transferLogs(to, SystemProgram.programId);
+        expect.fail("Should have thrown InvalidNotificationProgram error");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidNotificationProgram");
+      }
+    });
+  });
+
+  describe("transfer_with_average", () => {
+    const WINDOW = 8;
+    const [movingAverage] = PublicKey.findProgramAddressSync(
+      [Buffer.from("moving_average")],
+      program.programId
+    );
+    const recipient = Keypair.generate();
+    const pushed: number[] = [];
+
+    const transfer = async (amount: number) => {
+      await program.methods
+        .transferWithAverage(new anchor.BN(amount))
+        .accounts({
+          movingAverage,
+          sender: provider.wallet.publicKey,
+          recipient: recipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      pushed.push(amount);
+    };
+
+    const expected = () => {
+      const window = pushed.slice(-WINDOW);
+      return Math.floor(window.reduce((sum, amount) => sum + amount, 0) / window.length);
+    };
+
+    const average = async () =>
+      (await program.methods.getMovingAverage().accounts({ movingAverage }).view()).toNumber();
+
+    it("averages over a partially filled window", async () => {
+      await transfer(0.01 * LAMPORTS_PER_SOL);
+      await transfer(0.02 * LAMPORTS_PER_SOL);
+      await transfer(0.03 * LAMPORTS_PER_SOL);
+
+      expect(await average()).to.equal(0.02 * LAMPORTS_PER_SOL);
+      const account = await program.account.movingAverage.fetch(movingAverage);
+      expect(account.count).to.equal(3);
+    });
+
+    it("drops the oldest amounts once the window is full", async () => {
+      for (let i = 0; i < WINDOW; i++) {
+        await transfer((i + 1) * 1_000_000);
+      }
+      expect(await average()).to.equal(expected());
+      expect(await average()).to.equal(4_500_000);
+
+      await transfer(100_000_000);
+      expect(await average()).to.equal(expected());
+      const account = await program.account.movingAverage.fetch(movingAverage);
+      expect(account.count).to.equal(WINDOW);
+    });
+  });
+
+  describe("set_allow_executable", () => {
+    const MEMO_PROGRAM_ID = new PublicKey("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+    const amount = 10_000;
+
+    const setAllowExecutable = (allowed: boolean) =>
+      program.methods
+        .setAllowExecutable(allowed)
+        .accounts({ state: statePda, authority: provider.wallet.publicKey })
+        .rpc();
+
+    const transferToProgram = () =>
+      program.methods
+        .transferSol(new anchor.BN(amount))
+        .accounts({
+          state: statePda,
+          sender: provider.wallet.publicKey,
+          recipient: MEMO_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+    after(async () => {
+      await setAllowExecutable(false);
+    });
+
+    it("rejects a transfer to a program account by default", async () => {
+      try {
+        await transferToProgram();
+        expect.fail("Should have thrown RecipientIsProgram error");
+      } catch (error: any) {
+        expect(error.message).to.include("RecipientIsProgram");
+      }
+    });
+
+    it("rejects a guarded transfer to a program account by default", async () => {
+      try {
+        await program.methods
+          .transferSolGuarded(new anchor.BN(amount), false)
+          .accounts({
+            sender: provider.wallet.publicKey,
+            recipient: MEMO_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        expect.fail("Should have thrown RecipientIsProgram error");
+      } catch (error: any) {
+        expect(error.message).to.include("RecipientIsProgram");
+      }
+    });
+
+    it("sends to a program account once allowed", async () => {
+      await setAllowExecutable(true);
+      const before = await provider.connection.getBalance(MEMO_PROGRAM_ID);
+
+      await transferToProgram();
+
+      expect(await provider.connection.getBalance(MEMO_PROGRAM_ID)).to.equal(before + amount);
+    });
+  });
+
+  describe("transfer_with_grace", () => {
+    const amount = 0.01 * LAMPORTS_PER_SOL;
+    let payer: Keypair;
+
+    const gracePda = (to: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("grace"), payer.publicKey.toBuffer(), to.toBuffer()],
+        program.programId
+      )[0];
+
+    const transferWithGrace = (to: PublicKey, graceSecs: number) =>
+      program.methods
+        .transferWithGrace(new anchor.BN(amount), graceSecs)
+        .accounts({
+          graceTransfer: gracePda(to),
+          sender: payer.publicKey,
+          recipient: to,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([payer])
+        .rpc();
+
+    const cancel = (to: PublicKey) =>
+      program.methods
+        .cancelGrace()
+        .accounts({ graceTransfer: gracePda(to), sender: payer.publicKey, recipient: to })
+        .signers([payer])
+        .rpc();
+
+    const claim = (to: Keypair) =>
+      program.methods
+        .claimGrace()
+        .accounts({
+          graceTransfer: gracePda(to.publicKey),
+          sender: payer.publicKey,
+          recipient: to.publicKey,
+        })
+        .signers([to])
+        .rpc();
+
+    before(async () => {
+      payer = Keypair.generate();
+      await airdrop(payer.publicKey, 1);
+    });
+
+    it("refunds the sender when cancelled within the grace period", async () => {
+      const to = Keypair.generate();
+      await transferWithGrace(to.publicKey, 60);
+
+      try {
+        await claim(to);
+        expect.fail("Should have thrown EscrowNotExpired error");
+      } catch (error: any) {
+        expect(error.message).to.include("EscrowNotExpired");
+      }
+
+      const before = await provider.connection.getBalance(payer.publicKey);
+      await cancel(to.publicKey);
+
+      expect(await provider.connection.getBalance(payer.publicKey)).to.be.greaterThan(
+        before + amount - 10_000
+      );
+      expect(await provider.connection.getBalance(to.publicKey)).to.equal(0);
+      expect(await provider.connection.getAccountInfo(gracePda(to.publicKey))).to.be.null;
+    });
+
+    it("lets the recipient claim after the grace period and blocks cancelling", async () => {
+      const to = Keypair.generate();
+      await transferWithGrace(to.publicKey, 1);
+
+      await new Promise((resolve) => setTimeout(resolve, 3000));
+      try {
+        await cancel(to.publicKey);
+        expect.fail("Should have thrown GracePeriodOver error");
+      } catch (error: any) {
+        expect(error.message).to.include("GracePeriodOver");
+      }
+
+      await claim(to);
+
+      expect(await provider.connection.getBalance(to.publicKey)).to.equal(amount);
+      expect(await provider.connection.getAccountInfo(gracePda(to.publicKey))).to.be.null;
+    });
+  });
+
+  describe("transfer_sol_batch", () => {
+    const amounts = [0.01, 0.02, 0.03].map((sol) => sol * LAMPORTS_PER_SOL);
+
+    const batchEvents = async (recipients: PublicKey[], summarize: boolean) => {
+      const signature = await program.methods
+        .transferSolBatch(
+          amounts.map((amount) => new anchor.BN(amount)),
+          summarize
+        )
+        .accounts({
+          state: statePda,
+          sender: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(
+          recipients.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true }))
+        )
+        .rpc({ commitment: "confirmed" });
+
+      const tx = await provider.connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      return [...parser.parseLogs(tx!.meta!.logMessages!)];
+    };
+
+    it("emits one BatchSummary matching the recipient set", async () => {
+      const recipients = amounts.map(() => Keypair.generate().publicKey);
+
+      const events = await batchEvents(recipients, true);
+
+      expect(events).to.have.length(1);
+      expect(events[0].name).to.equal("batchSummary");
+      const summary = events[0].data;
+      expect(summary.recipientCount).to.equal(recipients.length);
+      expect(summary.totalAmount.toNumber()).to.equal(amounts.reduce((a, b) => a + b, 0));
+      const expectedHash = createHash("sha256")
+        .update(Buffer.concat(recipients.map((pubkey) => pubkey.toBuffer())))
+        .digest();
+      expect(Buffer.from(summary.recipientsHash)).to.deep.equal(expectedHash);
+
+      for (let i = 0; i < recipients.length; i++) {
+        expect(await provider.connection.getBalance(recipients[i])).to.equal(amounts[i]);
+      }
+    });
+
+    it("emits a TransferExecuted per recipient when not summarized", async () => {
+      const recipients = amounts.map(() => Keypair.generate().publicKey);
+
+      const events = await batchEvents(recipients, false);
+
+      expect(events.map((event) => event.name)).to.deep.equal(
+        recipients.map(() => "transferExecuted")
+      );
+    });
+  });
+
+  describe("Recipient rate limits", () => {
+    const amount = 0.01 * LAMPORTS_PER_SOL;
+    const maxPerWindow = 2;
+    const windowSecs = 3;
+    const recipient = Keypair.generate();
+    const [recipientRate] = PublicKey.findProgramAddressSync(
+      [Buffer.from("recipient_rate"), recipient.publicKey.toBuffer()],
+      program.programId
+    );
+
+    const transfer = () =>
+      program.methods
+        .transferRateLimited(new anchor.BN(amount))
+        .accounts({
+          recipientRate,
+          sender: provider.wallet.publicKey,
+          recipient: recipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+    before(async () => {
+      await airdrop(recipient.publicKey, 1);
+      await program.methods
+        .setRecipientRate(maxPerWindow, windowSecs)
+        .accounts({
+          recipientRate,
+          recipient: recipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([recipient])
+        .rpc();
+    });
+
+    it("rejects transfers beyond the recipient's rate", async () => {
+      for (let i = 0; i < maxPerWindow; i++) {
+        await transfer();
+      }
+
+      try {
+        await transfer();
+        expect.fail("Should have thrown RecipientRateLimited error");
+      } catch (error: any) {
+        expect(error.message).to.include("RecipientRateLimited");
+      }
+    });
+
+    it("accepts transfers again once the window resets", async () => {
+      await new Promise((resolve) => setTimeout(resolve, (windowSecs + 2) * 1000));
+
+      await transfer();
+
+      const rate = await program.account.recipientRate.fetch(recipientRate);
+      expect(rate.count).to.equal(1);
+    });
+  });
+
+  describe("contribute", () => {
+    const [pool] = PublicKey.findProgramAddressSync(
+      [Buffer.from("share_pool")],
+      program.programId
+    );
+    const sharesPda = (owner: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("shares"), owner.toBuffer()],
+        program.programId
+      )[0];
+    const alice = Keypair.generate();
+    const bob = Keypair.generate();
+
+    const contribute = (contributor: Keypair, amount: number) =>
+      program.methods
+        .contribute(new anchor.BN(amount))
+        .accounts({
+          pool,
+          shares: sharesPda(contributor.publicKey),
+          contributor: contributor.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([contributor])
+        .rpc();
+
+    const sharesOf = async (owner: Keypair) =>
+      (await program.account.shares.fetch(sharesPda(owner.publicKey))).shares.toNumber();
+
+    before(async () => {
+      await airdrop(alice.publicKey, 1);
+      await airdrop(bob.publicKey, 1);
+    });
+
+    it("mints shares in proportion to each contribution", async () => {
+      await contribute(alice, 0.1 * LAMPORTS_PER_SOL);
+      await contribute(bob, 0.3 * LAMPORTS_PER_SOL);
+
+      expect(await sharesOf(bob)).to.equal(3 * (await sharesOf(alice)));
+    });
+
+    it("redeems shares for the proportional SOL", async () => {
+      for (const [owner, contributed] of [
+        [alice, 0.1 * LAMPORTS_PER_SOL],
+        [bob, 0.3 * LAMPORTS_PER_SOL],
+      ] as [Keypair, number][]) {
+        const before = await provider.connection.getBalance(owner.publicKey);
+
+        await program.methods
+          .redeemShares(new anchor.BN(await sharesOf(owner)))
+          .accounts({ pool, shares: sharesPda(owner.publicKey), owner: owner.publicKey })
+          .signers([owner])
+          .rpc();
+
+        expect(await provider.connection.getBalance(owner.publicKey)).to.equal(
+          before + contributed
+        );
+        expect(await sharesOf(owner)).to.equal(0);
+      }
+    });
+
+    it("ignores SOL donated to the pool when pricing shares", async () => {
+      await contribute(alice, 1);
+      await provider.sendAndConfirm(
+        new anchor.web3.Transaction().add(
+          SystemProgram.transfer({
+            fromPubkey: provider.wallet.publicKey,
+            toPubkey: pool,
+            lamports: 0.2 * LAMPORTS_PER_SOL,
+          })
+        )
+      );
+
+      await contribute(bob, 0.1 * LAMPORTS_PER_SOL);
+
+      expect(await sharesOf(alice)).to.equal(1);
+      expect(await sharesOf(bob)).to.equal(0.1 * LAMPORTS_PER_SOL);
+    });
+  });
+
+  describe("Group budgets", () => {
+    const groupId = new anchor.BN(1);
+    const cap = 0.05 * LAMPORTS_PER_SOL;
+    const alice = Keypair.generate();
+    const bob = Keypair.generate();
+    const recipient = Keypair.generate().publicKey;
+    const [groupBudget] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("group_budget"),
+        provider.wallet.publicKey.toBuffer(),
+        groupId.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+
+    const spend = (member: Keypair, amount: number) =>
+      program.methods
+        .transferFromGroup(new anchor.BN(amount))
+        .accounts({
+          groupBudget,
+          sender: member.publicKey,
+          recipient,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([member])
+        .rpc();
+
+    before(async () => {
+      await airdrop(alice.publicKey, 1);
+      await airdrop(bob.publicKey, 1);
+      await program.methods
+        .createGroupBudget(groupId, [alice.publicKey, bob.publicKey], new anchor.BN(cap))
+        .accounts({
+          groupBudget,
+          admin: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    it("charges every member's spending to the shared cap", async () => {
+      await spend(alice, 0.02 * LAMPORTS_PER_SOL);
+      await spend(bob, 0.02 * LAMPORTS_PER_SOL);
+
+      const budget = await program.account.groupBudget.fetch(groupBudget);
+      expect(budget.spent.toNumber()).to.equal(0.04 * LAMPORTS_PER_SOL);
+    });
+
+    it("rejects a member transfer once the combined spending would exceed the cap", async () => {
+      try {
+        await spend(alice, 0.02 * LAMPORTS_PER_SOL);
+        expect.fail("Should have thrown GroupBudgetExceeded error");
+      } catch (error: any) {
+        expect(error.message).to.include("GroupBudgetExceeded");
+      }
+
+      await spend(bob, 0.01 * LAMPORTS_PER_SOL);
+      const budget = await program.account.groupBudget.fetch(groupBudget);
+      expect(budget.spent.toNumber()).to.equal(cap);
+    });
+
+    it("rejects senders outside the group", async () => {
+      const outsider = Keypair.generate();
+      await airdrop(outsider.publicKey, 1);
+
+      try {
+        await spend(outsider, 1);
+        expect.fail("Should have thrown Unauthorized error");
+      } catch (error: any) {
+        expect(error.message).to.include("Unauthorized");
+      }
+    });
+  });
+
+  describe("transfer_with_daily_close", () => {
+    const amount = 0.01 * LAMPORTS_PER_SOL;
+    // The validator starts with a daily window preloaded from
+    // tests/fixtures/daily_window.json, recording 3 transfers of 0.5 SOL on day 1
+    const fixtureDay = 1;
+    const [dailyWindow] = PublicKey.findProgramAddressSync(
+      [Buffer.from("daily_window")],
+      program.programId
+    );
+    const snapshotFor = (day: number) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("daily_snapshot"), new anchor.BN(day).toArrayLike(Buffer, "le", 8)],
+        program.programId
+      )[0];
+
+    const transfer = async () => {
+      const window = await program.account.dailyWindow.fetchNullable(dailyWindow);
+      await program.methods
+        .transferWithDailyClose(new anchor.BN(amount))
+        .accounts({
+          dailyWindow,
+          snapshot: snapshotFor(window ? window.day.toNumber() : 0),
+          sender: provider.wallet.publicKey,
+          recipient: recipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    };
+
+    const currentDay = async () => {
+      const now = await provider.connection.getBlockTime(await provider.connection.getSlot());
+      return Math.floor(now! / 86_400);
+    };
+
+    it("snapshots the previous day on the first transfer of a new day", async () => {
+      const before = await program.account.dailyWindow.fetch(dailyWindow);
+      expect(before.day.toNumber()).to.equal(fixtureDay);
+
+      await transfer();
+
+      const snapshot = await program.account.dailySnapshot.fetch(snapshotFor(fixtureDay));
+      expect(snapshot.day.toNumber()).to.equal(fixtureDay);
+      expect(snapshot.count.toNumber()).to.equal(before.count.toNumber());
+      expect(snapshot.volume.toNumber()).to.equal(before.volume.toNumber());
+
+      const window = await program.account.dailyWindow.fetch(dailyWindow);
+      expect(window.day.toNumber()).to.equal(await currentDay());
+      expect(window.count.toNumber()).to.equal(1);
+      expect(window.volume.toNumber()).to.equal(amount);
+    });
+
+    it("keeps accumulating without a snapshot within the same day", async () => {
+      const today = (await program.account.dailyWindow.fetch(dailyWindow)).day.toNumber();
+
+      await transfer();
+
+      const window = await program.account.dailyWindow.fetch(dailyWindow);
+      expect(window.count.toNumber()).to.equal(2);
+      expect(await provider.connection.getAccountInfo(snapshotFor(today))).to.be.null;
+    });
+  });
+
+  describe("set_lifetime_fee_cap", () => {
+    const feeBps = 100;
+    const amount = 100_000; // 1_000 fee per transfer
+    const fee = (amount * feeBps) / 10_000;
+    let feeConfigPda: PublicKey;
+    let mint: PublicKey;
+    let holder: Keypair;
+    let holderToken: PublicKey;
+    let treasuryToken: PublicKey;
+    let recipientToken: PublicKey;
+
+    const setCap = (cap: number) =>
+      program.methods
+        .setLifetimeFeeCap(new anchor.BN(cap))
+        .accounts({ state: statePda, authority: provider.wallet.publicKey })
+        .rpc();
+
+    const collectedSoFar = async () =>
+      (await program.account.programState.fetch(statePda)).totalFeesCollected.toNumber();
+
+    // Returns what the recipient received and the treasury collected
+    const transfer = async () => {
+      const balance = async (token: PublicKey) =>
+        Number((await getAccount(provider.connection, token)).amount);
+      const before = await Promise.all([recipientToken, treasuryToken].map(balance));
+      await program.methods
+        .transferTokenWithFee(new anchor.BN(amount))
+        .accounts({
+          state: statePda,
+          feeConfig: feeConfigPda,
+          sender: holder.publicKey,
+          mint,
+          senderToken: holderToken,
+          recipientToken,
+          treasuryToken,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([holder])
+        .rpc();
+      const after = await Promise.all([recipientToken, treasuryToken].map(balance));
+      return { received: after[0] - before[0], collected: after[1] - before[1] };
+    };
+
+    before(async () => {
+      const payer = (provider.wallet as anchor.Wallet).payer;
+      const treasury = Keypair.generate();
+      holder = Keypair.generate();
+      // Below the discount balance, so the full fee rate applies
+      await airdrop(holder.publicKey, 1);
+      [feeConfigPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("fee_config")],
+        program.programId
+      );
+
+      mint = await createMint(provider.connection, payer, payer.publicKey, null, 6);
+      const tokenAccount = async (owner: PublicKey) =>
+        (await getOrCreateAssociatedTokenAccount(provider.connection, payer, mint, owner)).address;
+      holderToken = await tokenAccount(holder.publicKey);
+      treasuryToken = await tokenAccount(treasury.publicKey);
+      recipientToken = await tokenAccount(recipient.publicKey);
+      await mintTo(provider.connection, payer, mint, holderToken, payer, 10_000_000);
+
+      await program.methods
+        .setFee(feeBps, 0, treasury.publicKey)
+        .accounts({
+          state: statePda,
+          feeConfig: feeConfigPda,
+          authority: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      // Earlier tests already collected fees, so the cap leaves room for 1.5 fees
+      await setCap((await collectedSoFar()) + fee + fee / 2);
+    });
+
+    after(async () => {
+      await setCap(0);
+    });
+
+    it("charges the full fee while below the cap", async () => {
+      expect(await transfer()).to.deep.equal({ received: amount - fee, collected: fee });
+    });
+
+    it("charges only the remainder when a fee crosses the cap", async () => {
+      expect(await transfer()).to.deep.equal({
+        received: amount - fee / 2,
+        collected: fee / 2,
+      });
+      const state = await program.account.programState.fetch(statePda);
+      expect(state.totalFeesCollected.toNumber()).to.equal(state.lifetimeFeeCap.toNumber());
+    });
+
+    it("transfers fee-free after the cap is reached", async () => {
+      expect(await transfer()).to.deep.equal({ received: amount, collected: 0 });
+    });
+  });
+
+  describe("transfer_signed_ack", () => {
+    const amount = 0.01 * LAMPORTS_PER_SOL;
+    const acknowledger = Keypair.generate();
+
+    const [ackNonce] = PublicKey.findProgramAddressSync(
+      [Buffer.from("ack_nonce"), acknowledger.publicKey.toBuffer()],
+      program.programId
+    );
+    const u64Bytes = (value: number) => new anchor.BN(value).toArrayLike(Buffer, "le", 8);
+    const currentNonce = async () =>
+      ((await program.account.recipientNonce.fetchNullable(ackNonce))?.nonce.toNumber() ?? 0);
+
+    // Ed25519 program instruction signed by the acknowledger, plus the signature it carries
+    const ack = async (lamports: number, nonce?: number) => {
+      const message = Buffer.concat([
+        program.programId.toBuffer(),
+        provider.wallet.publicKey.toBuffer(),
+        acknowledger.publicKey.toBuffer(),
+        u64Bytes(lamports),
+        u64Bytes(nonce ?? (await currentNonce())),
+      ]);
+      const ix = Ed25519Program.createInstructionWithPrivateKey({
+        privateKey: acknowledger.secretKey,
+        message,
+      });
+      // Layout: 16-byte header, 32-byte public key, then the 64-byte signature
+      return { ix, signature: [...ix.data.subarray(48, 112)] };
+    };
+
+    const transferIx = (signature: number[]) =>
+      program.methods
+        .transferSignedAck(new anchor.BN(amount), signature, acknowledger.publicKey)
+        .accounts({
+          sender: provider.wallet.publicKey,
+          recipient: acknowledger.publicKey,
+          ackNonce,
+          instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+          systemProgram: SystemProgram.programId,
+        })
+        .instruction();
+
+    it("transfers when the recipient signed the transfer", async () => {
+      const { ix, signature } = await ack(amount);
+      const before = await provider.connection.getBalance(acknowledger.publicKey);
+
+      await provider.sendAndConfirm(
+        new anchor.web3.Transaction().add(ix, await transferIx(signature))
+      );
+
+      expect(await provider.connection.getBalance(acknowledger.publicKey)).to.equal(
+        before + amount
+      );
+    });
+
+    it("rejects a replayed acknowledgement", async () => {
+      const { ix, signature } = await ack(amount, (await currentNonce()) - 1);
+
+      try {
+        await provider.sendAndConfirm(
+          new anchor.web3.Transaction().add(ix, await transferIx(signature))
+        );
+        expect.fail("Should have thrown InvalidSignature error");
+      } catch (error: any) {
+        expect(error.message + (error.logs ?? []).join("\n")).to.include("InvalidSignature");
+      }
+    });
+
+    it("rejects a signature over a different amount", async () => {
+      const { ix, signature } = await ack(amount + 1);
+
+      try {
+        await provider.sendAndConfirm(
+          new anchor.web3.Transaction().add(ix, await transferIx(signature))
+        );
+        expect.fail("Should have thrown InvalidSignature error");
+      } catch (error: any) {
+        expect(error.message + (error.logs ?? []).join("\n")).to.include("InvalidSignature");
+      }
+    });
+
+    it("rejects a transfer without a signature verification", async () => {
+      const { signature } = await ack(amount);
+
+      try {
+        await provider.sendAndConfirm(new anchor.web3.Transaction().add(await transferIx(signature)));
+        expect.fail("Should have thrown InvalidSignature error");
+      } catch (error: any) {
+        expect(error.message + (error.logs ?? []).join("\n")).to.include("InvalidSignature");
+      }
+    });
+  });
+
+  describe("Reversible transfers", () => {
+    const amount = 0.01 * LAMPORTS_PER_SOL;
+    let payer: Keypair;
+
+    const setChallengePeriod = (secs: number) =>
+      program.methods
+        .setChallengePeriod(secs)
+        .accounts({ state: statePda, authority: provider.wallet.publicKey })
+        .rpc();
+
+    const holdPda = (to: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("reversible"), payer.publicKey.toBuffer(), to.toBuffer()],
+        program.programId
+      )[0];
+
+    const hold = (to: PublicKey) =>
+      program.methods
+        .transferReversible(new anchor.BN(amount))
+        .accounts({
+          state: statePda,
+          reversibleTransfer: holdPda(to),
+          sender: payer.publicKey,
+          recipient: to,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([payer])
+        .rpc();
+
+    const reverse = (to: PublicKey) =>
+      program.methods
+        .reverseTransfer()
+        .accounts({ reversibleTransfer: holdPda(to), sender: payer.publicKey, recipient: to })
+        .signers([payer])
+        .rpc();
+
+    const finalize = (to: Keypair) =>
+      program.methods
+        .finalizeTransfer()
+        .accounts({
+          reversibleTransfer: holdPda(to.publicKey),
+          sender: payer.publicKey,
+          recipient: to.publicKey,
+        })
+        .signers([to])
+        .rpc();
+
+    before(async () => {
+      payer = Keypair.generate();
+      await airdrop(payer.publicKey, 1);
+    });
+
+    after(async () => {
+      await setChallengePeriod(0);
+    });
+
+    it("returns the funds when reversed during the challenge period", async () => {
+      await setChallengePeriod(60);
+      const to = Keypair.generate();
+      await hold(to.publicKey);
+
+      try {
+        await finalize(to);
+        expect.fail("Should have thrown EscrowNotExpired error");
+      } catch (error: any) {
+        expect(error.message).to.include("EscrowNotExpired");
+      }
+
+      await reverse(to.publicKey);
+
+      expect(await provider.connection.getBalance(to.publicKey)).to.equal(0);
+      expect(await provider.connection.getAccountInfo(holdPda(to.publicKey))).to.be.null;
+    });
+
+    it("lets the recipient finalize after the challenge period", async () => {
+      await setChallengePeriod(1);
+      const to = Keypair.generate();
+      await hold(to.publicKey);
+
+      await new Promise((resolve) => setTimeout(resolve, 3000));
+      try {
+        await reverse(to.publicKey);
+        expect.fail("Should have thrown ChallengePeriodOver error");
+      } catch (error: any) {
+        expect(error.message).to.include("ChallengePeriodOver");
+      }
+
+      await finalize(to);
+
+      expect(await provider.connection.getBalance(to.publicKey)).to.equal(amount);
+      expect(await provider.connection.getAccountInfo(holdPda(to.publicKey))).to.be.null;
+    });
+  });
+
+  describe("transfer_with_policy", () => {
+    const minIncoming = 0.02 * LAMPORTS_PER_SOL;
+
+    const statsPda = (recipient: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("recipient_stats"), recipient.toBuffer()],
+        program.programId
+      )[0];
+
+    // A fresh recipient registering the given policy
+    const withPolicy = async (min: number, requireMemo: boolean, requireKyc: boolean) => {
+      const to = Keypair.generate();
+      await airdrop(to.publicKey, 1);
+      await program.methods
+        .setAcceptancePolicy(new anchor.BN(min), requireMemo, requireKyc)
+        .accounts({
+          recipientStats: statsPda(to.publicKey),
+          recipient: to.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([to])
+        .rpc();
+      return to.publicKey;
+    };
+
+    const transfer = (to: PublicKey, amount: number, memo: string) =>
+      program.methods
+        .transferWithPolicy(new anchor.BN(amount), memo)
+        .accounts({
+          recipientStats: statsPda(to),
+          sender: provider.wallet.publicKey,
+          recipient: to,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+    const expectError = async (promise: Promise<unknown>, code: string) => {
+      try {
+        await promise;
+        expect.fail(`Should have thrown ${code} error`);
+      } catch (error: any) {
+        expect(error.message).to.include(code);
+      }
+    };
+
+    it("enforces the minimum amount", async () => {
+      const to = await withPolicy(minIncoming, false, false);
+
+      await expectError(transfer(to, minIncoming - 1, ""), "BelowRecipientMinimum");
+      await transfer(to, minIncoming, "");
+    });
+
+    it("enforces a required memo", async () => {
+      const to = await withPolicy(0, true, false);
+
+      await expectError(transfer(to, minIncoming, ""), "MemoRequired");
+      await transfer(to, minIncoming, "invoice 42");
+    });
+
+    it("enforces required KYC", async () => {
+      const to = await withPolicy(0, false, true);
+
+      await expectError(transfer(to, minIncoming, ""), "RecipientNotVerified");
+      await program.methods
+        .setKycVerified(true)
+        .accounts({
+          state: statePda,
+          recipientStats: statsPda(to),
+          recipient: to,
+          authority: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      await transfer(to, minIncoming, "");
+    });
+
+    it("reports the first violated rule", async () => {
+      const to = await withPolicy(minIncoming, true, true);
+
+      await expectError(transfer(to, minIncoming - 1, ""), "BelowRecipientMinimum");
+      await expectError(transfer(to, minIncoming, ""), "MemoRequired");
+      await expectError(transfer(to, minIncoming, "memo"), "RecipientNotVerified");
+    });
+  });
+
+  describe("collect_sol", () => {
+    const amountEach = 0.1 * LAMPORTS_PER_SOL;
+    const sources: Keypair[] = [];
+
+    const collect = (to: PublicKey, signers: Keypair[], signed = true) =>
+      program.methods
+        .collectSol(new anchor.BN(amountEach))
+        .accounts({ recipient: to, systemProgram: SystemProgram.programId })
+        .remainingAccounts(
+          sources.flatMap((source) => [
+            { pubkey: source.publicKey, isSigner: signed, isWritable: true },
+            {
+              pubkey: PublicKey.findProgramAddressSync(
+                [Buffer.from("guardian"), source.publicKey.toBuffer()],
+                program.programId
+              )[0],
+              isSigner: false,
+              isWritable: false,
+            },
+          ])
+        )
+        .signers(signers)
+        .rpc();
+
+    before(async () => {
+      for (let i = 0; i < 3; i++) {
+        const source = Keypair.generate();
+        await airdrop(source.publicKey, 1);
+        sources.push(source);
+      }
+    });
+
+    it("collects amount_each from three sources into one recipient", async () => {
+      const to = Keypair.generate().publicKey;
+      const before = await Promise.all(
+        sources.map((source) => provider.connection.getBalance(source.publicKey))
+      );
+
+      await collect(to, sources);
+
+      expect(await provider.connection.getBalance(to)).to.equal(3 * amountEach);
+      for (let i = 0; i < sources.length; i++) {
+        expect(await provider.connection.getBalance(sources[i].publicKey)).to.equal(
+          before[i] - amountEach
+        );
+      }
+    });
+
+    it("rejects sources that have not signed", async () => {
+      try {
+        await collect(Keypair.generate().publicKey, [], false);
+        expect.fail("Should have thrown Unauthorized error");
+      } catch (error: any) {
+        expect(error.message).to.include("Unauthorized");
+      }
+    });
+  });
+
+  describe("get_average_interval", () => {
+    const sleep = (ms: number) => new Promise((resolve) => setTimeout(resolve, ms));
+    const sender = Keypair.generate();
+    const recipient = Keypair.generate();
+    const senderStats = PublicKey.findProgramAddressSync(
+      [Buffer.from("sender_stats"), sender.publicKey.toBuffer()],
+      program.programId
+    )[0];
+
+    const transfer = () =>
+      program.methods
+        .transferSolBatched(new anchor.BN(0.01 * LAMPORTS_PER_SOL))
+        .accounts({
+          senderStats,
+          sender: sender.publicKey,
+          recipient: recipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([sender])
+        .rpc();
+
+    const averageInterval = async () =>
+      (await program.methods.getAverageInterval().accounts({ senderStats }).view()).toNumber();
+
+    before(async () => {
+      await airdrop(sender.publicKey, 1);
+    });
+
+    it("has no interval after the first transfer", async () => {
+      await transfer();
+
+      const stats = await program.account.senderStats.fetch(senderStats);
+      expect(stats.lastTransferTs.toNumber()).to.be.greaterThan(0);
+      expect(stats.intervalCount.toNumber()).to.equal(0);
+      expect(await averageInterval()).to.equal(0);
+    });
+
+    it("averages the gaps over three spaced transfers", async () => {
+      const first = (await program.account.senderStats.fetch(senderStats)).lastTransferTs.toNumber();
+
+      await sleep(2000);
+      await transfer();
+      await sleep(3000);
+      await transfer();
+
+      const stats = await program.account.senderStats.fetch(senderStats);
+      const last = stats.lastTransferTs.toNumber();
+      expect(stats.intervalCount.toNumber()).to.equal(2);
+      expect(stats.totalIntervalSecs.toNumber()).to.equal(last - first);
+      expect(last - first).to.be.at.least(4);
+      expect(await averageInterval()).to.equal(Math.floor((last - first) / 2));
+    });
+  });
+
+  describe("transfer_withhold", () => {
+    const sender = Keypair.generate();
+    const recipient = Keypair.generate();
+    const amount = 0.1 * LAMPORTS_PER_SOL;
+    const withholding = PublicKey.findProgramAddressSync(
+      [Buffer.from("tax_withholding"), sender.publicKey.toBuffer()],
+      program.programId
+    )[0];
+
+    const transferWithhold = (withholdBps: number) =>
+      program.methods
+        .transferWithhold(new anchor.BN(amount), withholdBps)
+        .accounts({
+          withholding,
+          sender: sender.publicKey,
+          recipient: recipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([sender])
+        .rpc();
+
+    const withdraw = (lamports: number) =>
+      program.methods
+        .withdrawWithholding(new anchor.BN(lamports))
+        .accounts({ withholding, owner: sender.publicKey })
+        .signers([sender])
+        .rpc();
+
+    before(async () => {
+      await airdrop(sender.publicKey, 1);
+    });
+
+    it("accrues the withheld share while paying the recipient in full", async () => {
+      await transferWithhold(2_500);
+      await transferWithhold(1_000);
+
+      expect(await provider.connection.getBalance(recipient.publicKey)).to.equal(2 * amount);
+      const account = await program.account.taxWithholding.fetch(withholding);
+      expect(account.owner.toBase58()).to.equal(sender.publicKey.toBase58());
+      expect(account.balance.toNumber()).to.equal((amount * 3_500) / 10_000);
+      expect(account.totalWithheld.toNumber()).to.equal((amount * 3_500) / 10_000);
+    });
+
+    it("rejects a withholding rate above the cap", async () => {
+      try {
+        await transferWithhold(5_001);
+        expect.fail("Should have thrown WithholdingTooHigh error");
+      } catch (error: any) {
+        expect(error.message).to.include("WithholdingTooHigh");
+      }
+    });
+
+    it("lets the owner withdraw the withheld SOL", async () => {
+      const before = await provider.connection.getBalance(withholding);
+      await withdraw((amount * 2_000) / 10_000);
+
+      expect(await provider.connection.getBalance(withholding)).to.equal(before - (amount * 2_000) / 10_000);
+      const account = await program.account.taxWithholding.fetch(withholding);
+      expect(account.balance.toNumber()).to.equal((amount * 1_500) / 10_000);
+      expect(account.totalWithheld.toNumber()).to.equal((amount * 3_500) / 10_000);
+    });
+
+    it("rejects withdrawing more than the withheld balance", async () => {
+      try {
+        await withdraw((amount * 2_000) / 10_000);
+        expect.fail("Should have thrown InsufficientFunds error");
+      } catch (error: any) {
+        expect(error.message).to.include("InsufficientFunds");
+      }
+    });
+  });
+
+  describe("deposit_to_vault", () => {
+    const owner = Keypair.generate();
+    const [vault, canonicalBump] = PublicKey.findProgramAddressSync(
+      [Buffer.from("sol_vault"), owner.publicKey.toBuffer()],
+      program.programId
+    );
+
+    // Finds the highest bump below the canonical one that still derives a PDA
+    const nonCanonical = (): [PublicKey, number] => {
+      for (let bump = canonicalBump - 1; bump >= 0; bump--) {
+        try {
+          const address = PublicKey.createProgramAddressSync(
+            [Buffer.from("sol_vault"), owner.publicKey.toBuffer(), Buffer.from([bump])],
+            program.programId
+          );
+          return [address, bump];
+        } catch {
+          // On the curve: not a valid PDA, keep looking
+        }
+      }
+      throw new Error("No non-canonical bump found");
+    };
+
+    const withdraw = (address: PublicKey, bump: number, lamports: number) =>
+      program.methods
+        .withdrawFromVault(new anchor.BN(lamports), bump)
+        .accounts({
+          vault: address,
+          owner: owner.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([owner])
+        .rpc();
+
+    before(async () => {
+      await airdrop(owner.publicKey, 1);
+      await program.methods
+        .depositToVault(new anchor.BN(0.2 * LAMPORTS_PER_SOL))
+        .accounts({ vault, owner: owner.publicKey, systemProgram: SystemProgram.programId })
+        .signers([owner])
+        .rpc();
+    });
+
+    it("withdraws with the canonical bump", async () => {
+      await withdraw(vault, canonicalBump, 0.05 * LAMPORTS_PER_SOL);
+
+      expect(await provider.connection.getBalance(vault)).to.equal(0.15 * LAMPORTS_PER_SOL);
+    });
+
+    it("rejects a non-canonical bump deriving a different vault", async () => {
+      const [address, bump] = nonCanonical();
+      await airdrop(address, 1);
+
+      try {
+        await withdraw(address, bump, 0.05 * LAMPORTS_PER_SOL);
+        expect.fail("Should have thrown NonCanonicalBump error");
+      } catch (error: any) {
+        expect(error.message).to.include("NonCanonicalBump");
+      }
+    });
+
+    it("rejects a wrong bump for the canonical vault", async () => {
+      const [, bump] = nonCanonical();
+
+      try {
+        await withdraw(vault, bump, 0.05 * LAMPORTS_PER_SOL);
+        expect.fail("Should have thrown ConstraintSeeds error");
+      } catch (error: any) {
+        expect(error.message).to.include("ConstraintSeeds");
+      }
+    });
+  });
+
+  describe("add_priority_recipient", () => {
+    const sender = Keypair.generate();
+    const capped = Keypair.generate();
+    const exchange = Keypair.generate();
+    const cap = 0.1 * LAMPORTS_PER_SOL;
+
+    const pda = (seed: string, key: PublicKey) =>
+      PublicKey.findProgramAddressSync([Buffer.from(seed), key.toBuffer()], program.programId)[0];
+
+    const setSenderDailyCap = (lamports: number) =>
+      program.methods
+        .setSenderDailyCap(new anchor.BN(lamports))
+        .accounts({ state: statePda, authority: provider.wallet.publicKey })
+        .rpc();
+
+    const transfer = (to: PublicKey, lamports: number) =>
+      program.methods
+        .transferSolDailyCapped(new anchor.BN(lamports))
+        .accounts({
+          state: statePda,
+          senderDaily: pda("sender_daily", sender.publicKey),
+          sender: sender.publicKey,
+          recipient: to,
+          priorityRecipient: pda("priority_recipient", to),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([sender])
+        .rpc();
+
+    before(async () => {
+      await airdrop(sender.publicKey, 1);
+      await setSenderDailyCap(cap);
+      await program.methods
+        .addPriorityRecipient()
+        .accounts({
+          state: statePda,
+          priority: pda("priority_recipient", exchange.publicKey),
+          recipient: exchange.publicKey,
+          authority: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    after(async () => {
+      await setSenderDailyCap(0);
+      await program.methods
+        .removePriorityRecipient()
+        .accounts({
+          state: statePda,
+          priority: pda("priority_recipient", exchange.publicKey),
+          recipient: exchange.publicKey,
+          authority: provider.wallet.publicKey,
+        })
+        .rpc();
+    });
+
+    it("caps the sender's transfers to ordinary recipients", async () => {
+      await transfer(capped.publicKey, cap);
+
+      try {
+        await transfer(capped.publicKey, 0.01 * LAMPORTS_PER_SOL);
+        expect.fail("Should have thrown DailyCapExceeded error");
+      } catch (error: any) {
+        expect(error.message).to.include("DailyCapExceeded");
+      }
+    });
+
+    it("lets the capped sender still transfer to a priority recipient", async () => {
+      await transfer(exchange.publicKey, 0.2 * LAMPORTS_PER_SOL);
+
+      expect(await provider.connection.getBalance(exchange.publicKey)).to.equal(
+        0.2 * LAMPORTS_PER_SOL
+      );
+      const spend = await program.account.senderDailySpend.fetch(
+        pda("sender_daily", sender.publicKey)
+      );
+      expect(spend.spent.toNumber()).to.equal(cap);
+    });
+  });
+
+  describe("transfer_with_bloom", () => {
+    const sender = Keypair.generate();
+    const paid = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+    const bloom = PublicKey.findProgramAddressSync(
+      [Buffer.from("recipient_bloom"), sender.publicKey.toBuffer()],
+      program.programId
+    )[0];
+
+    const possiblyPaid = (recipient: PublicKey): Promise<boolean> =>
+      program.methods.possiblyPaid(recipient).accounts({ bloom }).view();
+
+    before(async () => {
+      await airdrop(sender.publicKey, 1);
+      for (const recipient of paid) {
+        await program.methods
+          .transferWithBloom(new anchor.BN(0.01 * LAMPORTS_PER_SOL))
+          .accounts({
+            bloom,
+            sender: sender.publicKey,
+            recipient: recipient.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([sender])
+          .rpc();
+      }
+    });
+
+    it("reads every paid recipient as possibly paid", async () => {
+      for (const recipient of paid) {
+        expect(await possiblyPaid(recipient.publicKey)).to.equal(true);
+      }
+    });
+
+    it("usually reads new recipients as new", async () => {
+      let falsePositives = 0;
+      for (let i = 0; i < 20; i++) {
+        if (await possiblyPaid(Keypair.generate().publicKey)) {
+          falsePositives++;
+        }
+      }
+
+      // With at most 9 of 1024 bits set, a false positive is vanishingly rare
+      expect(falsePositives).to.be.at.most(1);
+    });
+  });
+
+  describe("set_config_cooldown", () => {
+    const sleep = (ms: number) => new Promise((resolve) => setTimeout(resolve, ms));
+    const cooldownSecs = 2;
+
+    const setConfigCooldown = (secs: number) =>
+      program.methods
+        .setConfigCooldown(secs)
+        .accounts({ state: statePda, authority: provider.wallet.publicKey })
+        .rpc();
+
+    const setMinHistory = (min: number) =>
+      program.methods
+        .setMinHistory(min)
+        .accounts({ state: statePda, authority: provider.wallet.publicKey })
+        .rpc();
+
+    before(async () => {
+      await setConfigCooldown(cooldownSecs);
+    });
+
+    after(async () => {
+      await sleep((cooldownSecs + 1) * 1000);
+      await setConfigCooldown(0);
+    });
+
+    it("rejects a config change inside the cooldown", async () => {
+      try {
+        await setMinHistory(0);
+        expect.fail("Should have thrown ConfigCooldownActive error");
+      } catch (error: any) {
+        expect(error.message).to.include("ConfigCooldownActive");
+      }
+    });
+
+    it("rejects a per-account setting inside the cooldown", async () => {
+      const recipient = Keypair.generate();
+      try {
+        await program.methods
+          .freezeRecipient()
+          .accounts({
+            state: statePda,
+            frozen: PublicKey.findProgramAddressSync(
+              [Buffer.from("frozen_recipient"), recipient.publicKey.toBuffer()],
+              program.programId
+            )[0],
+            recipient: recipient.publicKey,
+            authority: provider.wallet.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        expect.fail("Should have thrown ConfigCooldownActive error");
+      } catch (error: any) {
+        expect(error.message).to.include("ConfigCooldownActive");
+      }
+    });
+
+    it("accepts a config change once the cooldown has passed", async () => {
+      await sleep((cooldownSecs + 1) * 1000);
+      await setMinHistory(0);
+
+      const state = await program.account.programState.fetch(statePda);
+      expect(state.configCooldownSecs).to.equal(cooldownSecs);
+      expect(state.lastConfigChangeTs.toNumber()).to.be.greaterThan(0);
+    });
+  });
+
+  describe("set_memo_required", () => {
+    const MEMO_PROGRAM_ID = new PublicKey("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+    const recipient = Keypair.generate();
+    const amount = 0.01 * LAMPORTS_PER_SOL;
+
+    const setMemoRequired = (required: boolean) =>
+      program.methods
+        .setMemoRequired(required)
+        .accounts({ state: statePda, authority: provider.wallet.publicKey })
+        .rpc();
+
+    const memo = (text: string) =>
+      new anchor.web3.TransactionInstruction({
+        programId: MEMO_PROGRAM_ID,
+        keys: [{ pubkey: provider.wallet.publicKey, isSigner: true, isWritable: false }],
+        data: Buffer.from(text, "utf8"),
+      });
+
+    const transfer = (preInstructions: anchor.web3.TransactionInstruction[]) =>
+      program.methods
+        .transferSol(new anchor.BN(amount))
+        .accounts({
+          state: statePda,
+          sender: provider.wallet.publicKey,
+          recipient: recipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .preInstructions(preInstructions)
+        .rpc();
+
+    before(async () => {
+      await setMemoRequired(true);
+    });
+
+    after(async () => {
+      await setMemoRequired(false);
+    });
+
+    it("rejects a transfer without a memo", async () => {
+      try {
+        await transfer([]);
+        expect.fail("Should have thrown MemoRequired error");
+      } catch (error: any) {
+        expect(error.message).to.include("MemoRequired");
+      }
+    });
+
+    it("accepts a transfer sent with a memo", async () => {
+      await transfer([memo("invoice 42")]);
+
+      expect(await provider.connection.getBalance(recipient.publicKey)).to.equal(amount);
+    });
+
+    it("rejects a guarded transfer without a memo", async () => {
+      try {
+        await program.methods
+          .transferSolGuarded(new anchor.BN(amount), false)
+          .accounts({
+            sender: provider.wallet.publicKey,
+            recipient: recipient.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        expect.fail("Should have thrown MemoRequired error");
+      } catch (error: any) {
+        expect(error.message).to.include("MemoRequired");
+      }
+    });
+  });
+
+  describe("transfer_with_journal", () => {
+    const recipients = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+    const amounts = [0.01, 0.02, 0.03].map((sol) => sol * LAMPORTS_PER_SOL);
+    const [journal] = PublicKey.findProgramAddressSync(
+      [Buffer.from("journal")],
+      program.programId
+    );
+    let firstIndex: number;
+
+    before(async () => {
+      if (!(await provider.connection.getAccountInfo(journal))) {
+        await program.methods
+          .initJournal()
+          .accounts({
+            journal,
+            payer: provider.wallet.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+      }
+      firstIndex = (await program.account.journal.fetch(journal)).cursor.toNumber();
+    });
+
+    it("appends an entry per transfer, growing the account", async () => {
+      const sizeBefore = (await provider.connection.getAccountInfo(journal))!.data.length;
+
+      for (let i = 0; i < recipients.length; i++) {
+        await program.methods
+          .transferWithJournal(new anchor.BN(amounts[i]))
+          .accounts({
+            journal,
+            sender: provider.wallet.publicKey,
+            recipient: recipients[i].publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+      }
+
+      const info = (await provider.connection.getAccountInfo(journal))!;
+      expect(info.data.length).to.equal(sizeBefore + recipients.length * 80);
+      expect(info.lamports).to.be.at.least(
+        await provider.connection.getMinimumBalanceForRentExemption(info.data.length)
+      );
+      const account = await program.account.journal.fetch(journal);
+      expect(account.cursor.toNumber()).to.equal(firstIndex + recipients.length);
+    });
+
+    it("reads the entries back by index", async () => {
+      for (let i = 0; i < recipients.length; i++) {
+        const entry = await program.methods
+          .readJournalEntry(new anchor.BN(firstIndex + i))
+          .accounts({ journal })
+          .view();
+
+        expect(entry.sender.toBase58()).to.equal(provider.wallet.publicKey.toBase58());
+        expect(entry.recipient.toBase58()).to.equal(recipients[i].publicKey.toBase58());
+        expect(entry.amount.toNumber()).to.equal(amounts[i]);
+        expect(entry.timestamp.toNumber()).to.be.greaterThan(0);
+      }
+    });
+
+    it("rejects an index past the write cursor", async () => {
+      try {
+        await program.methods
+          .readJournalEntry(new anchor.BN(firstIndex + recipients.length))
+          .accounts({ journal })
+          .view();
+        expect.fail("Should have thrown JournalIndexOutOfRange error");
+      } catch (error: any) {
+        expect(error.message).to.include("JournalIndexOutOfRange");
+      }
+    });
+  });
+
+  describe("set_max_fraction_bps", () => {
+    const sender = Keypair.generate();
+    const recipient = Keypair.generate();
+
+    const setMaxFractionBps = (bps: number) =>
+      program.methods
+        .setMaxFractionBps(bps)
+        .accounts({ state: statePda, authority: provider.wallet.publicKey })
+        .rpc();
+
+    const transfer = (lamports: number, confirm: boolean) =>
+      program.methods
+        .transferSolGuarded(new anchor.BN(lamports), confirm)
+        .accounts({
+          state: statePda,
+          sender: sender.publicKey,
+          recipient: recipient.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([sender])
+        .rpc();
+
+    // Share of the sender's current balance, in lamports
+    const fractionOfBalance = async (bps: number) =>
+      Math.floor(((await provider.connection.getBalance(sender.publicKey)) * bps) / 10_000);
+
+    before(async () => {
+      await airdrop(sender.publicKey, 2);
+    });
+
+    after(async () => {
+      await setMaxFractionBps(0);
+    });
+
+    it("blocks a transfer above 90% of the balance by default", async () => {
+      try {
+        await transfer((await fractionOfBalance(9_000)) + 1, false);
+        expect.fail("Should have thrown ExceedsBalanceFraction error");
+      } catch (error: any) {
+        expect(error.message).to.include("ExceedsBalanceFraction");
+      }
+    });
+
+    it("allows a transfer at exactly the configured fraction", async () => {
+      await setMaxFractionBps(5_000);
+      const amount = await fractionOfBalance(5_000);
+
+      await transfer(amount, false);
+
+      expect(await provider.connection.getBalance(recipient.publicKey)).to.equal(amount);
+    });
+
+    it("rejects a transfer above the configured fraction", async () => {
+      try {
+        await transfer((await fractionOfBalance(5_000)) + 1, false);
+        expect.fail("Should have thrown ExceedsBalanceFraction error");
+      } catch (error: any) {
+        expect(error.message).to.include("ExceedsBalanceFraction");
+      }
+    });
+
+    it("allows a larger transfer once confirmed", async () => {
+      const before = await provider.connection.getBalance(recipient.publicKey);
+      const amount = await fractionOfBalance(8_000);
+
+      await transfer(amount, true);
+
+      expect(await provider.connection.getBalance(recipient.publicKey)).to.equal(before + amount);
+    });
+  });
+
+  describe("transfer_with_guardian", () => {
+    const sender = Keypair.generate();
+    const guardian = Keypair.generate();
+    const recipient = Keypair.generate();
+    const amount = 0.01 * LAMPORTS_PER_SOL;
+
+    const pda = (...seeds: Buffer[]) =>
+      PublicKey.findProgramAddressSync(seeds, program.programId)[0];
+    const guardianConfig = pda(Buffer.from("guardian"), sender.publicKey.toBuffer());
+
+    const transfer = (withGuardian: boolean) =>
+      program.methods
+        .transferWithGuardian(new anchor.BN(amount))
+        .accounts({
+          guardianConfig,
+          approved: pda(
+            Buffer.from("approved_recipient"),
+            sender.publicKey.toBuffer(),
+            recipient.publicKey.toBuffer()
+          ),
+          sender: sender.publicKey,
+          recipient: recipient.publicKey,
+          guardian: withGuardian ? guardian.publicKey : null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers(withGuardian ? [sender, guardian] : [sender])
+        .rpc();
+
+    before(async () => {
+      await airdrop(sender.publicKey, 1);
+      await program.methods
+        .setGuardian(guardian.publicKey)
+        .accounts({
+          guardianConfig,
+          sender: sender.publicKey,
+          currentGuardian: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([sender])
+        .rpc();
+    });
+
+    it("requires the guardian to co-sign the first transfer to a recipient", async () => {
+      try {
+        await transfer(false);
+        expect.fail("Should have thrown GuardianRequired error");
+      } catch (error: any) {
+        expect(error.message).to.include("GuardianRequired");
+      }
+
+      await transfer(true);
+
+      expect(await provider.connection.getBalance(recipient.publicKey)).to.equal(amount);
+    });
+
+    it("needs no guardian for a repeat transfer to the same recipient", async () => {
+      await transfer(false);
+
+      expect(await provider.connection.getBalance(recipient.publicKey)).to.equal(2 * amount);
+    });
+
+    it("rejects other transfer variants from a guarded sender", async () => {
+      try {
+        await program.methods
+          .transferSolGuarded(new anchor.BN(amount), false)
+          .accounts({
+            sender: sender.publicKey,
+            recipient: recipient.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([sender])
+          .rpc();
+        expect.fail("Should have thrown GuardianRequired error");
+      } catch (error: any) {
+        expect(error.message).to.include("GuardianRequired");
+      }
+    });
+
+    it("requires the current guardian to replace it", async () => {
+      try {
+        await program.methods
+          .setGuardian(sender.publicKey)
+          .accounts({
+            guardianConfig,
+            sender: sender.publicKey,
+            currentGuardian: null,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([sender])
+          .rpc();
+        expect.fail("Should have thrown GuardianRequired error");
+      } catch (error: any) {
+        expect(error.message).to.include("GuardianRequired");
+      }
+    });
+
+    it("rejects the default key as a guardian", async () => {
+      const other = Keypair.generate();
+      await airdrop(other.publicKey, 1);
+
+      try {
+        await program.methods
+          .setGuardian(PublicKey.default)
+          .accounts({
+            guardianConfig: pda(Buffer.from("guardian"), other.publicKey.toBuffer()),
+            sender: other.publicKey,
+            currentGuardian: null,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([other])
+          .rpc();
+        expect.fail("Should have thrown InvalidConfig error");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidConfig");
+      }
+    });
+  });
 });